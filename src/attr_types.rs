@@ -0,0 +1,180 @@
+// Attribute type model shared by the converters and the GraphML and GEXF readers and writers
+// Types are those of the GraphML keys, which the GEXF attribute types map onto. Values are parsed to graph
+// attributes from their type, and the type of written keys is widened to hold every value of the key.
+
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::gml_parser::MAX_DEPTH;
+use crate::graph::AttrValue;
+
+// Types are ordered from the narrowest to the widest, a key gets the widest type of all its values
+// Keys holding lists or maps are strings holding json, keys read without an attr.type are json keys.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum GraphmlAttributeTypes {
+    Boolean,
+    Int,
+    Long,
+    Float,
+    Double,
+    String,
+    Json,
+}
+
+impl GraphmlAttributeTypes {
+    pub fn of_int(number: i64) -> Self {
+        if i32::try_from(number).is_ok() {
+            GraphmlAttributeTypes::Int
+        } else {
+            GraphmlAttributeTypes::Long
+        }
+    }
+
+    pub fn of_float(number: f64) -> Self {
+        // Use a float when the single precision value is printed the same (ie. 1.1 but not 0.123456789)
+        if (number as f32).to_string() == number.to_string() {
+            GraphmlAttributeTypes::Float
+        } else {
+            GraphmlAttributeTypes::Double
+        }
+    }
+
+    pub fn of_value(value: &AttrValue) -> Self {
+        // Type of an attribute like the gml converter infers it, lists and maps are strings serialized as json
+        match value {
+            AttrValue::Int(value) => GraphmlAttributeTypes::of_int(*value),
+            AttrValue::Float(value) => GraphmlAttributeTypes::of_float(*value),
+            AttrValue::Bool(_) => GraphmlAttributeTypes::Boolean,
+            AttrValue::String(_) | AttrValue::List(_) | AttrValue::Map(_) => {
+                GraphmlAttributeTypes::String
+            }
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        // Type of a GraphML attr.type
+        match name {
+            "boolean" => Some(GraphmlAttributeTypes::Boolean),
+            "int" => Some(GraphmlAttributeTypes::Int),
+            "long" => Some(GraphmlAttributeTypes::Long),
+            "float" => Some(GraphmlAttributeTypes::Float),
+            "double" => Some(GraphmlAttributeTypes::Double),
+            "string" => Some(GraphmlAttributeTypes::String),
+            _ => None,
+        }
+    }
+
+    pub fn from_gexf_name(name: &str) -> Option<Self> {
+        // Type of a GEXF attribute type, big numbers are read as the widest ones and the text types as strings
+        match name {
+            "boolean" => Some(GraphmlAttributeTypes::Boolean),
+            "integer" | "short" | "byte" => Some(GraphmlAttributeTypes::Int),
+            "long" | "biginteger" => Some(GraphmlAttributeTypes::Long),
+            "float" => Some(GraphmlAttributeTypes::Float),
+            "double" | "bigdecimal" => Some(GraphmlAttributeTypes::Double),
+            "string" | "char" | "date" | "anyURI" => Some(GraphmlAttributeTypes::String),
+            _ => None,
+        }
+    }
+
+    pub fn widen(self, other: Self) -> Self {
+        // Get the narrowest type that can hold values of both types without loss (ie. int and float -> double)
+        match (self.min(other), self.max(other)) {
+            (narrowest, widest) if narrowest == widest => widest,
+            // Booleans mixed with numbers are strings
+            (GraphmlAttributeTypes::Boolean, widest) => widest.max(GraphmlAttributeTypes::String),
+            // A float can't hold every int value (ie. 123456789), a double can
+            (GraphmlAttributeTypes::Int, GraphmlAttributeTypes::Float) => {
+                GraphmlAttributeTypes::Double
+            }
+            // A double can't hold every long value either (ie. 2^53 + 1)
+            (
+                GraphmlAttributeTypes::Long,
+                GraphmlAttributeTypes::Float | GraphmlAttributeTypes::Double,
+            ) => GraphmlAttributeTypes::String,
+            (_, widest) => widest,
+        }
+    }
+
+    pub fn value(&self) -> Option<&'static str> {
+        match *self {
+            GraphmlAttributeTypes::Boolean => Some("boolean"),
+            GraphmlAttributeTypes::Int => Some("int"),
+            GraphmlAttributeTypes::Long => Some("long"),
+            GraphmlAttributeTypes::Float => Some("float"),
+            GraphmlAttributeTypes::Double => Some("double"),
+            GraphmlAttributeTypes::String => Some("string"),
+            GraphmlAttributeTypes::Json => None,
+        }
+    }
+
+    pub fn gexf_name(&self) -> &'static str {
+        // GEXF name of the type, lists and maps are written as json strings
+        match *self {
+            GraphmlAttributeTypes::Int => "integer",
+            attr_type => attr_type.value().unwrap_or("string"),
+        }
+    }
+
+    pub fn parse(self, value: &str) -> Option<AttrValue> {
+        // Convert a value to an attribute of the type, None when it isn't a value of the type.
+        // Json values are only read as json when they are lists or maps, other values (ie. 123) are strings.
+        match self {
+            GraphmlAttributeTypes::Json => match parse_json(value) {
+                Ok(Some(json)) => Some(AttrValue::from_json(&json)),
+                Ok(None) => Some(AttrValue::String(value.to_string())),
+                Err(_) => None,
+            },
+            GraphmlAttributeTypes::Boolean => match value.trim() {
+                "true" | "1" => Some(AttrValue::Bool(true)),
+                "false" | "0" => Some(AttrValue::Bool(false)),
+                _ => None,
+            },
+            GraphmlAttributeTypes::Int | GraphmlAttributeTypes::Long => {
+                value.trim().parse::<i64>().ok().map(AttrValue::Int)
+            }
+            GraphmlAttributeTypes::Float | GraphmlAttributeTypes::Double => {
+                value.trim().parse::<f64>().ok().map(AttrValue::Float)
+            }
+            GraphmlAttributeTypes::String => Some(AttrValue::String(value.to_string())),
+        }
+    }
+}
+
+fn json_depth(value: &str) -> usize {
+    // Deepest nesting of the lists and maps of a json value, the brackets in its strings aside
+    let (mut depth, mut deepest) = (0usize, 0);
+    let (mut in_string, mut escaped) = (false, false);
+    for byte in value.bytes() {
+        match byte {
+            _ if escaped => escaped = false,
+            b'\\' if in_string => escaped = true,
+            b'"' => in_string = !in_string,
+            _ if in_string => {}
+            b'[' | b'{' => {
+                depth += 1;
+                deepest = deepest.max(depth);
+            }
+            b']' | b'}' => depth = depth.saturating_sub(1),
+            _ => {}
+        }
+    }
+    deepest
+}
+
+pub fn parse_json(value: &str) -> Result<Option<Value>, String> {
+    // Json list or map held by a value (ie. "[1,2]"), None for the other values, which are read from their type.
+    // Lists and maps are read at any depth up to MAX_DEPTH levels (serde_json stops at 128 by default), deeper ones
+    // are an error as they are converted and written recursively.
+    if !value.trim_start().starts_with(['[', '{']) {
+        return Ok(None);
+    }
+    if json_depth(value) > MAX_DEPTH {
+        return Err(format!("Values nested deeper than {} levels", MAX_DEPTH));
+    }
+    let mut deserializer = serde_json::Deserializer::from_str(value);
+    deserializer.disable_recursion_limit();
+    Ok(Value::deserialize(&mut deserializer)
+        .and_then(|json| deserializer.end().map(|_| json))
+        .ok())
+}
@@ -0,0 +1,125 @@
+// Character cursor shared by the GML and DOT tokenizers
+// Reads the input line by line through a BufRead, keeps the offset of the next character in the current line and
+// the line and column of the last token started, and builds the errors of the format at that position.
+
+use std::io::BufRead;
+
+use crate::error::ConvertError;
+
+// Text format being tokenized, for the errors at a line and column
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Syntax {
+    Gml,
+    Dot,
+}
+
+impl Syntax {
+    pub fn error(self, line: usize, column: usize, message: String) -> ConvertError {
+        match self {
+            Syntax::Gml => ConvertError::Gml {
+                line,
+                column,
+                message,
+            },
+            Syntax::Dot => ConvertError::Dot {
+                line,
+                column,
+                message,
+            },
+        }
+    }
+}
+
+pub struct Cursor<R: BufRead> {
+    reader: R,
+    syntax: Syntax,
+    line: String,
+    offset: usize,
+    line_number: usize,
+    // Column of the character at a byte offset of the line, counted from the previous token so that long lines
+    // (ie. a whole file on one line) aren't counted again for every token
+    counted_offset: usize,
+    counted_column: usize,
+    token_line: usize,
+    token_column: usize,
+}
+
+impl<R: BufRead> Cursor<R> {
+    pub fn new(reader: R, syntax: Syntax) -> Self {
+        Cursor {
+            reader,
+            syntax,
+            line: String::new(),
+            offset: 0,
+            line_number: 0,
+            counted_offset: 0,
+            counted_column: 1,
+            token_line: 0,
+            token_column: 0,
+        }
+    }
+
+    pub fn position(&self) -> (usize, usize) {
+        // Line and column (both starting at 1) of the last token started
+        (self.token_line, self.token_column)
+    }
+
+    pub fn error(&self, message: String) -> ConvertError {
+        // Build an error at the position of the last token started
+        self.syntax
+            .error(self.token_line, self.token_column, message)
+    }
+
+    pub fn start_token(&mut self) {
+        // Keep the position of the next character as the one of the token being read
+        self.token_line = self.line_number;
+        self.counted_column += self.line[self.counted_offset..self.offset].chars().count();
+        self.counted_offset = self.offset;
+        self.token_column = self.counted_column;
+    }
+
+    pub fn read_line(&mut self) -> Result<bool, ConvertError> {
+        // Load the next line of the input, returns false at the end of the file
+        self.line.clear();
+        self.offset = 0;
+        self.counted_offset = 0;
+        self.counted_column = 1;
+        let read = self.reader.read_line(&mut self.line)?;
+        self.line_number += 1;
+        Ok(read > 0)
+    }
+
+    pub fn line(&self) -> &str {
+        &self.line
+    }
+
+    pub fn rest(&self) -> &str {
+        // Rest of the current line, from the next character
+        &self.line[self.offset..]
+    }
+
+    pub fn advance(&mut self, bytes: usize) {
+        self.offset += bytes;
+    }
+
+    pub fn skip_line(&mut self) {
+        // Consume the rest of the current line (ie. a comment)
+        self.offset = self.line.len();
+    }
+
+    pub fn peek_char(&self) -> Option<char> {
+        self.rest().chars().next()
+    }
+
+    pub fn peek_second_char(&self) -> Option<char> {
+        self.rest().chars().nth(1)
+    }
+
+    pub fn take_while<P: Fn(char) -> bool>(&mut self, predicate: P) -> String {
+        let rest = self.rest();
+        let end = rest.find(|c| !predicate(c)).unwrap_or(rest.len());
+        let taken = rest[..end].to_string();
+        self.offset += end;
+        taken
+    }
+}
@@ -0,0 +1,935 @@
+// Graphviz DOT reader and writer of graph events
+// The reader handles the common subset of DOT: node and edge statements (with edge chains and subgraph endpoints),
+// attribute lists, node, edge and graph attribute statements and subgraphs. Each graph is read in memory before
+// its events are returned, as a node statement can add attributes to a node declared before. Clusters
+// (subgraph cluster_*) become group nodes like the yEd groups of the converters: a node with isGroup 1, the
+// cluster attributes and the cluster id without its cluster_ prefix, and a gid with that id on the nodes inside it.
+// A group node whose id is also a node id keeps its cluster name instead (ie. cluster_1 and node 1).
+// Unquoted numerals are read as numbers, true and false as booleans and other values as strings. Attribute names
+// with dots are read back as the nested maps and lists the writer flattens (ie. graphics.x=1 -> graphics [ x 1 ]).
+
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::io::{BufRead, Write};
+
+use crate::dot_lexer::{Lexer, Token};
+use crate::error::ConvertError;
+use crate::format::{self, GraphReader, GraphWriter};
+use crate::graph::{self, AttrValue, Attrs, EventSource, GraphEvent};
+use crate::multi_graph::graph_prefix;
+
+const KEYWORDS: [&str; 6] = ["strict", "graph", "digraph", "node", "edge", "subgraph"];
+
+fn is_keyword(token: &Token, keyword: &str) -> bool {
+    // Keywords are case insensitive and never quoted
+    matches!(token, Token::Id(id) if id.eq_ignore_ascii_case(keyword))
+}
+
+fn id_text(token: Token) -> Option<String> {
+    match token {
+        Token::Id(text) | Token::Numeral(text) | Token::Quoted(text) | Token::Html(text) => {
+            Some(text)
+        }
+        _ => None,
+    }
+}
+
+fn token_value(token: Token) -> Option<AttrValue> {
+    // Attribute value of an id, only unquoted values are typed
+    let value = match token {
+        Token::Numeral(text) => match text.parse::<i64>() {
+            Ok(value) => AttrValue::Int(value),
+            Err(_) => AttrValue::Float(text.parse::<f64>().ok()?),
+        },
+        Token::Id(text) if text == "true" || text == "false" => AttrValue::Bool(text == "true"),
+        token => AttrValue::String(id_text(token)?),
+    };
+    Some(value)
+}
+
+fn list_of(map: Attrs) -> AttrValue {
+    // Read a map with the keys 0 to n - 1 back as a list
+    let is_list =
+        !map.is_empty() && (0..map.len()).all(|index| map.contains_key(&index.to_string()));
+    if !is_list {
+        return AttrValue::Map(map.into_iter().map(|(k, v)| (k, nested(v))).collect());
+    }
+    let mut items: Vec<(usize, AttrValue)> = map
+        .into_iter()
+        .map(|(index, value)| (index.parse().unwrap_or_default(), nested(value)))
+        .collect();
+    items.sort_by_key(|(index, _)| *index);
+    AttrValue::List(items.into_iter().map(|(_, value)| value).collect())
+}
+
+fn nested(value: AttrValue) -> AttrValue {
+    match value {
+        AttrValue::Map(map) => list_of(map),
+        value => value,
+    }
+}
+
+fn set_attr(attrs: &mut Attrs, name: &str, value: AttrValue) {
+    // Set an attribute, where the dotted names of flattened values (ie. graphics.x) are nested
+    let Some((name, rest)) = name
+        .split_once('.')
+        .filter(|(name, rest)| !name.is_empty() && !rest.is_empty())
+    else {
+        attrs.insert(name.to_string(), value);
+        return;
+    };
+    let entry = attrs
+        .entry(name.to_string())
+        .or_insert_with(|| AttrValue::Map(Attrs::new()));
+    if !matches!(entry, AttrValue::Map(_)) {
+        *entry = AttrValue::Map(Attrs::new());
+    }
+    if let AttrValue::Map(map) = entry {
+        set_attr(map, rest, value);
+    }
+}
+
+fn unflatten(attrs: Vec<(String, AttrValue)>) -> Attrs {
+    let mut nested_attrs = Attrs::new();
+    for (name, value) in attrs {
+        set_attr(&mut nested_attrs, &name, value);
+    }
+    nested_attrs
+        .into_iter()
+        .map(|(name, value)| (name, nested(value)))
+        .collect()
+}
+
+type AttrList = Vec<(String, AttrValue)>;
+
+// Attribute defaults and cluster of the statements of a graph or subgraph
+#[derive(Debug, Clone, Default)]
+struct Scope {
+    node_attrs: AttrList,
+    edge_attrs: AttrList,
+    // Id of the innermost cluster, given as gid to its nodes
+    cluster: Option<String>,
+    // Where the graph attributes go: the graph, the group node of a cluster, or nowhere for other subgraphs
+    top: bool,
+    group: Option<usize>,
+}
+
+// Endpoint of an edge statement: a node with its port, or the nodes of a subgraph
+enum Endpoint {
+    Node(String, Option<String>),
+    Nodes(Vec<String>),
+}
+
+impl Endpoint {
+    fn nodes(&self) -> Vec<(&str, Option<&str>)> {
+        match self {
+            Endpoint::Node(id, port) => vec![(id.as_str(), port.as_deref())],
+            Endpoint::Nodes(ids) => ids.iter().map(|id| (id.as_str(), None)).collect(),
+        }
+    }
+}
+
+// Graph being read, kept in memory until its end
+#[derive(Debug, Default)]
+struct GraphState {
+    directed: bool,
+    attrs: AttrList,
+    nodes: Vec<(String, AttrList)>,
+    node_index: HashMap<String, usize>,
+    // Group nodes of the clusters by cluster id, apart from the nodes as a cluster id can also be a node id
+    group_index: HashMap<String, usize>,
+    edges: Vec<(String, String, AttrList)>,
+    // Prefix of the node ids of the graphs after the first one
+    prefix: String,
+}
+
+impl GraphState {
+    fn add_node(&mut self, id: &str, scope: &Scope) -> usize {
+        // Declare a node the first time it is seen, with the node defaults of its scope. A node gets the gid of
+        // the first cluster it is seen in.
+        let id = format!("{}{}", self.prefix, id);
+        let index = match self.node_index.get(&id) {
+            Some(&index) => index,
+            None => {
+                self.node_index.insert(id.clone(), self.nodes.len());
+                self.nodes.push((id, scope.node_attrs.clone()));
+                self.nodes.len() - 1
+            }
+        };
+        let attrs = &mut self.nodes[index].1;
+        if let Some(cluster) = &scope.cluster
+            && !attrs.iter().any(|(name, _)| name == "gid")
+        {
+            attrs.push(("gid".to_string(), AttrValue::String(cluster.clone())));
+        }
+        index
+    }
+
+    fn add_group(&mut self, id: &str, scope: &Scope) -> usize {
+        // Declare the group node of a cluster the first time it is seen, inside the cluster containing it.
+        // Graphviz merges the subgraphs with the same name.
+        let id = format!("{}{}", self.prefix, id);
+        if let Some(&index) = self.group_index.get(&id) {
+            return index;
+        }
+        let mut attrs = vec![("isGroup".to_string(), AttrValue::Int(1))];
+        if let Some(cluster) = &scope.cluster {
+            attrs.push(("gid".to_string(), AttrValue::String(cluster.clone())));
+        }
+        self.group_index.insert(id.clone(), self.nodes.len());
+        self.nodes.push((id, attrs));
+        self.nodes.len() - 1
+    }
+
+    fn group_ids(&self) -> HashMap<String, String> {
+        // Ids of the group nodes whose cluster id is also a node id: the cluster name, with a number when it is
+        // taken too (ie. cluster_1, cluster_1_1)
+        let mut renamed = HashMap::new();
+        let mut taken: HashSet<&str> = self.node_index.keys().map(String::as_str).collect();
+        taken.extend(self.group_index.keys().map(String::as_str));
+        for (index, (id, _)) in self.nodes.iter().enumerate() {
+            if self.group_index.get(id) != Some(&index) || !self.node_index.contains_key(id) {
+                continue;
+            }
+            let name = format!("{}cluster_{}", self.prefix, &id[self.prefix.len()..]);
+            let mut unique = name.clone();
+            let mut number = 0;
+            while taken.contains(unique.as_str()) || renamed.values().any(|id| *id == unique) {
+                number += 1;
+                unique = format!("{}_{}", name, number);
+            }
+            renamed.insert(id.clone(), unique);
+        }
+        renamed
+    }
+
+    fn add_graph_attrs(&mut self, scope: &Scope, attrs: AttrList) {
+        match scope.group {
+            Some(index) => self.nodes[index].1.extend(attrs),
+            None if scope.top => self.attrs.extend(attrs),
+            None => {}
+        }
+    }
+
+    fn events(self) -> VecDeque<GraphEvent> {
+        // Events of the graph read, the edge id and dir attributes become the edge id and direction
+        let mut events = VecDeque::new();
+        let group_ids = self.group_ids();
+        events.push_back(GraphEvent::GraphStart {
+            directed: Some(self.directed),
+            attrs: unflatten(self.attrs),
+        });
+        for (index, (id, mut attrs)) in self.nodes.into_iter().enumerate() {
+            if let Some((_, AttrValue::String(gid))) =
+                attrs.iter_mut().find(|(name, _)| name == "gid")
+                && let Some(group_id) = group_ids.get(gid.as_str())
+            {
+                *gid = group_id.clone();
+            }
+            let id = match group_ids.get(&id) {
+                Some(group_id) if self.group_index.get(&id) == Some(&index) => group_id.clone(),
+                _ => id,
+            };
+            events.push_back(GraphEvent::Node {
+                id,
+                attrs: unflatten(attrs),
+            });
+        }
+        for (source, target, attrs) in self.edges {
+            let mut attrs = unflatten(attrs);
+            let id = match attrs.remove("id") {
+                Some(AttrValue::String(id)) => Some(id),
+                Some(id @ (AttrValue::Int(_) | AttrValue::Float(_))) => Some(value_text(&id)),
+                Some(id) => {
+                    attrs.insert("id".to_string(), id);
+                    None
+                }
+                None => None,
+            };
+            let directed = match (self.directed, attrs.get("dir")) {
+                (true, Some(AttrValue::String(dir))) if dir == "none" => Some(false),
+                (false, Some(AttrValue::String(dir))) if dir == "forward" => Some(true),
+                _ => None,
+            };
+            if directed.is_some() {
+                attrs.remove("dir");
+            }
+            events.push_back(GraphEvent::Edge {
+                id,
+                source,
+                target,
+                directed,
+                attrs,
+            });
+        }
+        events.push_back(GraphEvent::GraphEnd {
+            attrs: Attrs::new(),
+        });
+        events
+    }
+}
+
+pub struct EventReader<R: BufRead> {
+    tokens: Lexer<R>,
+    peeked: Option<Token>,
+    graphs: usize,
+}
+
+impl<R: BufRead> EventReader<R> {
+    pub fn new(input: R) -> Self {
+        EventReader {
+            tokens: Lexer::new(input),
+            peeked: None,
+            graphs: 0,
+        }
+    }
+
+    fn next_token(&mut self) -> Result<Option<Token>, ConvertError> {
+        match self.peeked.take() {
+            Some(token) => Ok(Some(token)),
+            None => self.tokens.next().transpose(),
+        }
+    }
+
+    fn peek(&mut self) -> Result<Option<&Token>, ConvertError> {
+        if self.peeked.is_none() {
+            self.peeked = self.tokens.next().transpose()?;
+        }
+        Ok(self.peeked.as_ref())
+    }
+
+    fn expect_token(&mut self, expected: &str) -> Result<Token, ConvertError> {
+        self.next_token()?.ok_or_else(|| {
+            self.tokens
+                .error(format!("Expected {}, found the end of the file", expected))
+        })
+    }
+
+    fn unexpected(&self, token: &Token, expected: &str) -> ConvertError {
+        self.tokens
+            .error(format!("Expected {}, found {:?}", expected, token))
+    }
+
+    fn read_id(&mut self) -> Result<Token, ConvertError> {
+        // Read an id, joining the quoted strings concatenated with + (ie. "a" + "b")
+        match self.expect_token("an id")? {
+            Token::Quoted(mut text) => {
+                while self.peek()? == Some(&Token::Plus) {
+                    self.next_token()?;
+                    match self.expect_token("a quoted string")? {
+                        Token::Quoted(next) => text.push_str(&next),
+                        token => return Err(self.unexpected(&token, "a quoted string")),
+                    }
+                }
+                Ok(Token::Quoted(text))
+            }
+            token @ (Token::Id(_) | Token::Numeral(_) | Token::Html(_)) => Ok(token),
+            token => Err(self.unexpected(&token, "an id")),
+        }
+    }
+
+    fn read_name(&mut self) -> Result<String, ConvertError> {
+        Ok(id_text(self.read_id()?).unwrap_or_default())
+    }
+
+    fn read_attr_list(&mut self) -> Result<AttrList, ConvertError> {
+        // Read the attribute lists following a statement: [a=1, b=2][c=3]
+        let mut attrs = Vec::new();
+        while self.peek()? == Some(&Token::LeftBracket) {
+            self.next_token()?;
+            loop {
+                match self.peek()? {
+                    Some(Token::RightBracket) => {
+                        self.next_token()?;
+                        break;
+                    }
+                    Some(Token::Comma | Token::Semicolon) => {
+                        self.next_token()?;
+                        continue;
+                    }
+                    _ => {}
+                }
+                let name = self.read_name()?;
+                // An attribute without a value is true (ie. [constraint])
+                let mut value = AttrValue::Bool(true);
+                if self.peek()? == Some(&Token::Equals) {
+                    self.next_token()?;
+                    value = token_value(self.read_id()?).unwrap_or(value);
+                }
+                attrs.push((name, value));
+            }
+        }
+        Ok(attrs)
+    }
+
+    fn read_port(&mut self) -> Result<Option<String>, ConvertError> {
+        // Read the port of a node id, with its compass point: a:p1 or a:p1:n
+        let mut port: Option<String> = None;
+        while self.peek()? == Some(&Token::Colon) {
+            self.next_token()?;
+            let part = self.read_name()?;
+            port = Some(match port {
+                Some(port) => format!("{}:{}", port, part),
+                None => part,
+            });
+        }
+        Ok(port)
+    }
+
+    fn read_subgraph(
+        &mut self,
+        graph: &mut GraphState,
+        scope: &Scope,
+    ) -> Result<Vec<String>, ConvertError> {
+        // Read a subgraph after its subgraph keyword (if any), returns the ids of its nodes.
+        // A cluster gets a group node, inside the cluster containing it.
+        let mut name = None;
+        if self.peek()? != Some(&Token::LeftBrace) {
+            name = Some(self.read_name()?);
+        }
+        match self.expect_token("{")? {
+            Token::LeftBrace => {}
+            token => return Err(self.unexpected(&token, "{")),
+        }
+        let mut inner = Scope {
+            top: false,
+            group: None,
+            ..scope.clone()
+        };
+        if let Some(name) = name.filter(|name| name.starts_with("cluster")) {
+            // The group node of cluster_a is a, and the one of clusterA is clusterA
+            let name = match name.strip_prefix("cluster_") {
+                Some(id) if !id.is_empty() => id.to_string(),
+                _ => name,
+            };
+            let index = graph.add_group(&name, scope);
+            inner.cluster = Some(graph.nodes[index].0.clone());
+            inner.group = Some(index);
+        }
+        self.read_statements(graph, &mut inner)
+    }
+
+    fn read_endpoint(
+        &mut self,
+        graph: &mut GraphState,
+        scope: &Scope,
+    ) -> Result<Endpoint, ConvertError> {
+        match self.peek()? {
+            Some(Token::LeftBrace) => Ok(Endpoint::Nodes(self.read_subgraph(graph, scope)?)),
+            Some(token) if is_keyword(token, "subgraph") => {
+                self.next_token()?;
+                Ok(Endpoint::Nodes(self.read_subgraph(graph, scope)?))
+            }
+            _ => {
+                let id = self.read_name()?;
+                let port = self.read_port()?;
+                graph.add_node(&id, scope);
+                Ok(Endpoint::Node(id, port))
+            }
+        }
+    }
+
+    fn read_edges(
+        &mut self,
+        graph: &mut GraphState,
+        scope: &Scope,
+        first: Endpoint,
+    ) -> Result<Vec<String>, ConvertError> {
+        // Read the rest of an edge statement (ie. -> b -> { c d } [color=red]), with an edge from each node of
+        // an endpoint to each node of the next one. Returns the ids of the nodes of the statement.
+        let mut endpoints = vec![first];
+        while let Some(Token::EdgeOp(_)) = self.peek()? {
+            self.next_token()?;
+            endpoints.push(self.read_endpoint(graph, scope)?);
+        }
+        let mut attrs = scope.edge_attrs.clone();
+        attrs.extend(self.read_attr_list()?);
+
+        for pair in endpoints.windows(2) {
+            for (source, tail_port) in pair[0].nodes() {
+                for (target, head_port) in pair[1].nodes() {
+                    let mut edge_attrs = attrs.clone();
+                    for (name, port) in [("tailport", tail_port), ("headport", head_port)] {
+                        if let Some(port) = port {
+                            edge_attrs
+                                .push((name.to_string(), AttrValue::String(port.to_string())));
+                        }
+                    }
+                    graph.edges.push((
+                        format!("{}{}", graph.prefix, source),
+                        format!("{}{}", graph.prefix, target),
+                        edge_attrs,
+                    ));
+                }
+            }
+        }
+        Ok(endpoints
+            .iter()
+            .flat_map(|endpoint| endpoint.nodes())
+            .map(|(id, _)| id.to_string())
+            .collect())
+    }
+
+    fn read_statements(
+        &mut self,
+        graph: &mut GraphState,
+        scope: &mut Scope,
+    ) -> Result<Vec<String>, ConvertError> {
+        // Read the statements of a graph or subgraph up to its closing brace, returns the ids of its nodes
+        let mut ids = Vec::new();
+        loop {
+            let token = self.expect_token("}")?;
+            match token {
+                Token::RightBrace => return Ok(ids),
+                Token::Semicolon => continue,
+                _ => {}
+            }
+            let keyword = |keyword| is_keyword(&token, keyword);
+            if (keyword("graph") || keyword("node") || keyword("edge"))
+                && self.peek()? == Some(&Token::LeftBracket)
+            {
+                let attrs = self.read_attr_list()?;
+                if keyword("node") {
+                    scope.node_attrs.extend(attrs);
+                } else if keyword("edge") {
+                    scope.edge_attrs.extend(attrs);
+                } else {
+                    graph.add_graph_attrs(scope, attrs);
+                }
+                continue;
+            }
+
+            let endpoint = if token == Token::LeftBrace {
+                self.peeked = Some(token);
+                Endpoint::Nodes(self.read_subgraph(graph, scope)?)
+            } else if keyword("subgraph") {
+                Endpoint::Nodes(self.read_subgraph(graph, scope)?)
+            } else if matches!(token, Token::Id(_))
+                && KEYWORDS.iter().any(|&keyword| is_keyword(&token, keyword))
+            {
+                return Err(self.unexpected(&token, "a statement"));
+            } else {
+                self.peeked = Some(token);
+                let id = self.read_name()?;
+                if self.peek()? == Some(&Token::Equals) {
+                    // Graph attribute statement: label="graph"
+                    self.next_token()?;
+                    let value = token_value(self.read_id()?).unwrap_or(AttrValue::Bool(true));
+                    graph.add_graph_attrs(scope, vec![(id, value)]);
+                    continue;
+                }
+                let port = self.read_port()?;
+                graph.add_node(&id, scope);
+                Endpoint::Node(id, port)
+            };
+
+            if let Some(Token::EdgeOp(_)) = self.peek()? {
+                ids.extend(self.read_edges(graph, scope, endpoint)?);
+                continue;
+            }
+            match endpoint {
+                Endpoint::Node(id, _) => {
+                    // Node statement, the attributes are added to those of the node when it was declared before
+                    let attrs = self.read_attr_list()?;
+                    let index = graph.add_node(&id, scope);
+                    graph.nodes[index].1.extend(attrs);
+                    ids.push(id);
+                }
+                Endpoint::Nodes(nodes) => ids.extend(nodes),
+            }
+        }
+    }
+
+    fn read_graph(&mut self) -> Result<Option<VecDeque<GraphEvent>>, ConvertError> {
+        // Read the next graph: [strict] (graph|digraph) [id] { statements }
+        let Some(mut token) = self.next_token()? else {
+            return Ok(None);
+        };
+        if is_keyword(&token, "strict") {
+            token = self.expect_token("graph or digraph")?;
+        }
+        let directed = if is_keyword(&token, "digraph") {
+            true
+        } else if is_keyword(&token, "graph") {
+            false
+        } else {
+            return Err(self.unexpected(&token, "graph or digraph"));
+        };
+        let mut graph = GraphState {
+            directed,
+            prefix: graph_prefix(self.graphs, None),
+            ..Default::default()
+        };
+        // The graph id is kept as its name, like networkx does in GML
+        if self.peek()? != Some(&Token::LeftBrace) {
+            let name = self.read_name()?;
+            graph
+                .attrs
+                .push(("name".to_string(), AttrValue::String(name)));
+        }
+        match self.expect_token("{")? {
+            Token::LeftBrace => {}
+            token => return Err(self.unexpected(&token, "{")),
+        }
+        let mut scope = Scope {
+            top: true,
+            ..Default::default()
+        };
+        self.read_statements(&mut graph, &mut scope)?;
+        self.graphs += 1;
+        Ok(Some(graph.events()))
+    }
+}
+
+impl<R: BufRead> EventSource for EventReader<R> {
+    fn read_events(&mut self, events: &mut VecDeque<GraphEvent>) -> Result<bool, ConvertError> {
+        // The events of a graph are returned before reading the next one
+        match self.read_graph()? {
+            Some(graph_events) => {
+                events.extend(graph_events);
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+}
+
+pub type Events<R> = graph::Events<EventReader<R>>;
+
+pub fn events<R: BufRead>(input: R) -> Events<R> {
+    // Stream the graphs of a dot input as events, one graph in memory at a time
+    graph::Events::new(EventReader::new(input))
+}
+
+fn is_numeral(text: &str) -> bool {
+    // DOT numeral: -?(.[0-9]+ | [0-9]+(.[0-9]*)?)
+    let digits = text.strip_prefix('-').unwrap_or(text);
+    let mut parts = digits.splitn(2, '.');
+    let whole = parts.next().unwrap_or_default();
+    let decimals = parts.next();
+    whole.chars().all(|c| c.is_ascii_digit())
+        && decimals.is_none_or(|decimals| decimals.chars().all(|c| c.is_ascii_digit()))
+        && (!whole.is_empty() || decimals.is_some_and(|decimals| !decimals.is_empty()))
+}
+
+fn is_identifier(text: &str) -> bool {
+    let mut chars = text.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_alphabetic() || c == '_' || !c.is_ascii())
+        && chars.all(|c| c.is_alphanumeric() || c == '_' || !c.is_ascii())
+        && !KEYWORDS
+            .iter()
+            .any(|keyword| text.eq_ignore_ascii_case(keyword))
+}
+
+fn quote(text: &str) -> String {
+    // Quote a string. Quotes are escaped, and so are the backslashes that would escape what follows them (a
+    // quote, a backslash, a line end or the end of the string). Other backslashes are kept for the Graphviz
+    // escape sequences (ie. \n in labels).
+    let mut quoted = String::from("\"");
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' if matches!(chars.peek(), Some('"' | '\\' | '\r' | '\n') | None) => {
+                quoted.push_str("\\\\")
+            }
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+fn id_string(id: &str) -> String {
+    // Node ids and attribute names are quoted unless they are identifiers or numerals
+    if is_identifier(id) || is_numeral(id) {
+        id.to_string()
+    } else {
+        quote(id)
+    }
+}
+
+fn value_text(value: &AttrValue) -> String {
+    match value {
+        AttrValue::Int(value) => value.to_string(),
+        AttrValue::Float(value) => format!("{:?}", value),
+        AttrValue::Bool(value) => value.to_string(),
+        AttrValue::String(value) => value.clone(),
+        AttrValue::List(_) | AttrValue::Map(_) => value.to_json().to_string(),
+    }
+}
+
+fn value_string(value: &AttrValue) -> String {
+    // Numbers and booleans are written unquoted to be read back with their type, strings that would be read
+    // as numbers or booleans are quoted
+    let text = value_text(value);
+    match value {
+        AttrValue::Int(_) | AttrValue::Float(_) if is_numeral(&text) => text,
+        AttrValue::Bool(_) => text,
+        AttrValue::String(_) if is_identifier(&text) && text != "true" && text != "false" => text,
+        _ => quote(&text),
+    }
+}
+
+fn flatten<'a>(name: String, value: &'a AttrValue, flat: &mut Vec<(String, &'a AttrValue)>) {
+    // Flatten nested lists and maps into dotted names (ie. graphics [ x 1 ] -> graphics.x=1, a [1, 2] -> a.0=1)
+    match value {
+        AttrValue::Map(attrs) if !attrs.is_empty() => {
+            for (key, value) in attrs {
+                flatten(format!("{}.{}", name, key), value, flat);
+            }
+        }
+        AttrValue::List(values) if !values.is_empty() => {
+            for (index, value) in values.iter().enumerate() {
+                flatten(format!("{}.{}", name, index), value, flat);
+            }
+        }
+        _ => flat.push((name, value)),
+    }
+}
+
+fn attr_list<'a, I>(attrs: I) -> String
+where
+    I: IntoIterator<Item = (&'a String, &'a AttrValue)>,
+{
+    // Attribute list of a node or edge statement: [a=1, "graphics.x"=2.0]
+    let mut flat = Vec::new();
+    for (name, value) in attrs {
+        flatten(name.clone(), value, &mut flat);
+    }
+    if flat.is_empty() {
+        return String::new();
+    }
+    let attrs: Vec<String> = flat
+        .iter()
+        .map(|(name, value)| format!("{}={}", id_string(name), value_string(value)))
+        .collect();
+    format!(" [{}]", attrs.join(", "))
+}
+
+fn is_group(attrs: &Attrs) -> bool {
+    matches!(
+        attrs.get("isGroup"),
+        Some(AttrValue::Int(1) | AttrValue::Bool(true))
+    )
+}
+
+fn cluster_name(id: &str) -> String {
+    // Graphviz only draws the subgraphs named cluster*, the reader removes the cluster_ prefix again
+    format!("cluster_{}", id)
+}
+
+pub struct EventWriter<W: Write> {
+    output: W,
+    graphs: usize,
+    directed: bool,
+    prefix: String,
+    // Group of each group node written, to nest the clusters
+    groups: HashMap<String, Option<String>>,
+}
+
+impl<W: Write> EventWriter<W> {
+    pub fn new(output: W) -> Self {
+        EventWriter {
+            output,
+            graphs: 0,
+            directed: false,
+            prefix: String::new(),
+            groups: HashMap::new(),
+        }
+    }
+
+    fn strip<'a>(&self, id: &'a str) -> &'a str {
+        // Remove the graph prefix from the ids of the graphs after the first one
+        id.strip_prefix(self.prefix.as_str()).unwrap_or(id)
+    }
+
+    fn gid(&self, attrs: &Attrs) -> Option<String> {
+        // Group node id of a node, a GML gid being the integer id of its group (read as n<id>)
+        match attrs.get("gid") {
+            Some(AttrValue::String(gid)) => Some(self.strip(gid).to_string()),
+            Some(AttrValue::Int(gid)) => Some(format!("n{}", gid)),
+            _ => None,
+        }
+    }
+
+    fn clusters(&self, attrs: &Attrs) -> Vec<String> {
+        // Clusters containing a node from the outermost one, from its gid and the groups written before
+        let mut clusters = Vec::new();
+        let mut seen = HashSet::new();
+        let mut group = self.gid(attrs);
+        while let Some(id) = group.filter(|id| seen.insert(id.clone())) {
+            clusters.push(cluster_name(&id));
+            group = self.groups.get(&id).cloned().flatten();
+        }
+        clusters.reverse();
+        clusters
+    }
+
+    fn write_in_clusters(
+        &mut self,
+        clusters: &[String],
+        lines: &[String],
+    ) -> Result<(), ConvertError> {
+        // Write statements inside the clusters, Graphviz merges the subgraphs with the same name
+        let indent = |depth: usize| "  ".repeat(depth + 1);
+        for (depth, cluster) in clusters.iter().enumerate() {
+            writeln!(
+                self.output,
+                "{}subgraph {} {{",
+                indent(depth),
+                id_string(cluster)
+            )?;
+        }
+        for line in lines {
+            writeln!(self.output, "{}{}", indent(clusters.len()), line)?;
+        }
+        for depth in (0..clusters.len()).rev() {
+            writeln!(self.output, "{}}}", indent(depth))?;
+        }
+        Ok(())
+    }
+
+    fn write_graph_attrs<'a>(
+        &mut self,
+        attrs: impl IntoIterator<Item = (&'a String, &'a AttrValue)>,
+    ) -> Result<(), ConvertError> {
+        let mut flat = Vec::new();
+        for (name, value) in attrs {
+            flatten(name.clone(), value, &mut flat);
+        }
+        for (name, value) in flat {
+            writeln!(
+                self.output,
+                "  {}={};",
+                id_string(&name),
+                value_string(value)
+            )?;
+        }
+        Ok(())
+    }
+
+    pub fn write(&mut self, event: &GraphEvent) -> Result<(), ConvertError> {
+        match event {
+            GraphEvent::GraphStart { directed, attrs } => {
+                if self.graphs > 0 {
+                    writeln!(self.output)?;
+                }
+                self.prefix = graph_prefix(self.graphs, None);
+                self.graphs += 1;
+                self.directed = *directed == Some(true);
+                self.groups.clear();
+                // The graph name is written as the graph id
+                let mut header = if self.directed { "digraph" } else { "graph" }.to_string();
+                if let Some(AttrValue::String(name)) = attrs.get("name") {
+                    header = format!("{} {}", header, id_string(name));
+                }
+                writeln!(self.output, "{} {{", header)?;
+                self.write_graph_attrs(attrs.iter().filter(|(name, _)| *name != "name"))?;
+            }
+            GraphEvent::Node { id, attrs } => {
+                let clusters = self.clusters(attrs);
+                let id = self.strip(id).to_string();
+                if is_group(attrs) {
+                    // A group node is written as a cluster with its attributes
+                    self.groups.insert(id.clone(), self.gid(attrs));
+                    let mut flat = Vec::new();
+                    for (name, value) in attrs
+                        .iter()
+                        .filter(|(name, _)| *name != "isGroup" && *name != "gid")
+                    {
+                        flatten(name.clone(), value, &mut flat);
+                    }
+                    let mut clusters = clusters;
+                    clusters.push(cluster_name(&id));
+                    let lines: Vec<String> = flat
+                        .into_iter()
+                        .map(|(name, value)| {
+                            format!("{}={};", id_string(&name), value_string(value))
+                        })
+                        .collect();
+                    return self.write_in_clusters(&clusters, &lines);
+                }
+                let attrs = attr_list(
+                    attrs
+                        .iter()
+                        .filter(|(name, _)| clusters.is_empty() || *name != "gid"),
+                );
+                let line = format!("{}{};", id_string(&id), attrs);
+                self.write_in_clusters(&clusters, &[line])?;
+            }
+            GraphEvent::Edge {
+                id,
+                source,
+                target,
+                directed,
+                attrs,
+            } => {
+                // Edges against the graph direction get a dir attribute, as DOT graphs can't mix them. The id and
+                // direction of the edge win over id and dir attributes, which are written once.
+                let mut extra = Attrs::new();
+                if let Some(id) = id {
+                    extra.insert("id".to_string(), AttrValue::String(id.clone()));
+                }
+                match (self.directed, directed) {
+                    (true, Some(false)) => {
+                        extra.insert("dir".to_string(), AttrValue::String("none".to_string()));
+                    }
+                    (false, Some(true)) => {
+                        extra.insert("dir".to_string(), AttrValue::String("forward".to_string()));
+                    }
+                    _ => {}
+                }
+                let op = if self.directed { "->" } else { "--" };
+                writeln!(
+                    self.output,
+                    "  {} {} {}{};",
+                    id_string(self.strip(source)),
+                    op,
+                    id_string(self.strip(target)),
+                    attr_list(
+                        extra
+                            .iter()
+                            .chain(attrs.iter().filter(|(name, _)| !extra.contains_key(*name)))
+                    )
+                )?;
+            }
+            GraphEvent::GraphEnd { attrs } => {
+                // Graph attribute statements apply to the whole graph wherever they are
+                self.write_graph_attrs(attrs)?;
+                writeln!(self.output, "}}")?;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn finish(mut self) -> Result<W, ConvertError> {
+        self.output.flush()?;
+        Ok(self.output)
+    }
+}
+
+// DOT in the format registry
+pub struct Dot;
+
+impl GraphReader for Dot {
+    fn read_events<'a>(&self, input: Box<dyn BufRead + 'a>) -> format::Events<'a> {
+        Box::new(events(input))
+    }
+}
+
+impl GraphWriter for Dot {
+    fn write_events(
+        &self,
+        events: &mut format::Events<'_>,
+        output: &mut dyn Write,
+    ) -> Result<(), ConvertError> {
+        let mut writer = EventWriter::new(output);
+        for event in events {
+            writer.write(&event?)?;
+        }
+        writer.finish()?;
+        Ok(())
+    }
+}
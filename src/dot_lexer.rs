@@ -0,0 +1,227 @@
+// Streaming Graphviz DOT tokenizer
+// Reads the input line by line through the shared cursor::Cursor and splits it into ids (identifiers, numerals,
+// quoted and html strings), edge operators and punctuation, skipping comments. Quoted strings and comments can span
+// several lines.
+
+use std::io::BufRead;
+
+use crate::cursor::{Cursor, Syntax};
+use crate::error::ConvertError;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Token {
+    // Identifiers, including the keywords (ie. digraph)
+    Id(String),
+    Numeral(String),
+    // Quoted strings are unquoted, with escaped quotes and backslashes unescaped and line continuations removed
+    Quoted(String),
+    // Html strings (ie. <<b>label</b>>) without their outer brackets
+    Html(String),
+    // -> (directed) or --
+    EdgeOp(bool),
+    LeftBrace,
+    RightBrace,
+    LeftBracket,
+    RightBracket,
+    Equals,
+    Semicolon,
+    Comma,
+    Colon,
+    Plus,
+}
+
+pub struct Lexer<R: BufRead> {
+    cursor: Cursor<R>,
+}
+
+impl<R: BufRead> Lexer<R> {
+    pub fn new(reader: R) -> Self {
+        Lexer {
+            cursor: Cursor::new(reader, Syntax::Dot),
+        }
+    }
+
+    pub fn position(&self) -> (usize, usize) {
+        // Line and column (both starting at 1) of the last token returned
+        self.cursor.position()
+    }
+
+    pub fn error(&self, message: String) -> ConvertError {
+        // Build an error at the position of the last token returned
+        self.cursor.error(message)
+    }
+
+    fn skip_block_comment(&mut self) -> Result<(), ConvertError> {
+        // Skip a /* */ comment (the opening is already consumed), which can span several lines
+        loop {
+            if let Some(end) = self.cursor.rest().find("*/") {
+                self.cursor.advance(end + 2);
+                return Ok(());
+            }
+            if !self.cursor.read_line()? {
+                return Err(self.error("Unterminated comment".to_string()));
+            }
+        }
+    }
+
+    fn read_quoted(&mut self) -> Result<Token, ConvertError> {
+        // Read a quoted string (the opening quote is already consumed). Quotes and backslashes are escaped, other
+        // backslashes are kept for the Graphviz escape sequences (ie. \n in labels), and a backslash at the end
+        // of a line continues the string on the next one.
+        let mut value = String::new();
+        loop {
+            let rest = self.cursor.rest();
+            let Some(end) = rest.find(['"', '\\']) else {
+                value.push_str(rest);
+                if !self.cursor.read_line()? {
+                    return Err(self.error("Unterminated string".to_string()));
+                }
+                continue;
+            };
+            value.push_str(&rest[..end]);
+            let closing = rest[end..].starts_with('"');
+            self.cursor.advance(end + 1);
+            if closing {
+                return Ok(Token::Quoted(value));
+            }
+            match self.cursor.peek_char() {
+                Some(c @ ('"' | '\\')) => {
+                    value.push(c);
+                    self.cursor.advance(1);
+                }
+                Some('\r' | '\n') | None => {
+                    if !self.cursor.read_line()? {
+                        return Err(self.error("Unterminated string".to_string()));
+                    }
+                }
+                Some(c) => {
+                    value.push('\\');
+                    value.push(c);
+                    self.cursor.advance(c.len_utf8());
+                }
+            }
+        }
+    }
+
+    fn read_html(&mut self) -> Result<Token, ConvertError> {
+        // Read an html string (the opening bracket is already consumed) up to its matching closing bracket
+        let mut value = String::new();
+        let mut depth = 1;
+        loop {
+            let rest = self.cursor.rest();
+            let Some(index) = rest.find(['<', '>']) else {
+                value.push_str(rest);
+                if !self.cursor.read_line()? {
+                    return Err(self.error("Unterminated html string".to_string()));
+                }
+                continue;
+            };
+            if rest[index..].starts_with('<') {
+                depth += 1;
+            } else {
+                depth -= 1;
+            }
+            if depth == 0 {
+                value.push_str(&rest[..index]);
+                self.cursor.advance(index + 1);
+                return Ok(Token::Html(value));
+            }
+            value.push_str(&rest[..=index]);
+            self.cursor.advance(index + 1);
+        }
+    }
+
+    fn read_numeral(&mut self) -> Result<Token, ConvertError> {
+        // A minus is only a sign before the digits, one after them starts an edge operator (ie. 1->2)
+        let mut numeral = String::new();
+        if self.cursor.peek_char() == Some('-') {
+            numeral.push('-');
+            self.cursor.advance(1);
+        }
+        numeral.push_str(&self.cursor.take_while(|c| c.is_ascii_digit() || c == '.'));
+        if numeral.parse::<f64>().is_ok() {
+            Ok(Token::Numeral(numeral))
+        } else {
+            Err(self.error(format!("Invalid numeral {:?}", numeral)))
+        }
+    }
+
+    fn read_token(&mut self) -> Result<Option<Token>, ConvertError> {
+        loop {
+            // Skip whitespace and move to the next line when the current one is consumed
+            self.cursor.take_while(char::is_whitespace);
+            let c = match self.cursor.peek_char() {
+                Some(c) => c,
+                None => {
+                    if !self.cursor.read_line()? {
+                        return Ok(None);
+                    }
+                    // Lines starting with # are output of the C preprocessor
+                    if self.cursor.line().trim_start().starts_with('#') {
+                        self.cursor.skip_line();
+                    }
+                    continue;
+                }
+            };
+
+            self.cursor.start_token();
+
+            let punctuation = match c {
+                '{' => Some(Token::LeftBrace),
+                '}' => Some(Token::RightBrace),
+                '[' => Some(Token::LeftBracket),
+                ']' => Some(Token::RightBracket),
+                '=' => Some(Token::Equals),
+                ';' => Some(Token::Semicolon),
+                ',' => Some(Token::Comma),
+                ':' => Some(Token::Colon),
+                '+' => Some(Token::Plus),
+                _ => None,
+            };
+            if let Some(token) = punctuation {
+                self.cursor.advance(1);
+                return Ok(Some(token));
+            }
+
+            let token = match (c, self.cursor.peek_second_char()) {
+                ('/', Some('/')) => {
+                    // skip comments until the end of the line
+                    self.cursor.skip_line();
+                    continue;
+                }
+                ('/', Some('*')) => {
+                    self.cursor.advance(2);
+                    self.skip_block_comment()?;
+                    continue;
+                }
+                ('-', Some(op @ ('>' | '-'))) => {
+                    self.cursor.advance(2);
+                    Token::EdgeOp(op == '>')
+                }
+                ('"', _) => {
+                    self.cursor.advance(1);
+                    self.read_quoted()?
+                }
+                ('<', _) => {
+                    self.cursor.advance(1);
+                    self.read_html()?
+                }
+                (c, _) if c.is_ascii_digit() || c == '-' || c == '.' => self.read_numeral()?,
+                (c, _) if c.is_alphabetic() || c == '_' || !c.is_ascii() => Token::Id(
+                    self.cursor
+                        .take_while(|c| c.is_alphanumeric() || c == '_' || !c.is_ascii()),
+                ),
+                _ => return Err(self.error(format!("Unexpected character {:?}", c))),
+            };
+            return Ok(Some(token));
+        }
+    }
+}
+
+impl<R: BufRead> Iterator for Lexer<R> {
+    type Item = Result<Token, ConvertError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.read_token().transpose()
+    }
+}
@@ -0,0 +1,119 @@
+// Errors returned by the converters
+// GML and DOT errors carry the line and column of the token where the problem was found, GraphML and GEXF errors
+// carry the byte position of the xml reader.
+
+use std::error::Error;
+use std::fmt;
+use std::io;
+
+#[derive(Debug)]
+pub enum ConvertError {
+    // Reading the input or writing the output failed
+    Io(io::Error),
+    // Invalid GML syntax or structure
+    Gml {
+        line: usize,
+        column: usize,
+        message: String,
+    },
+    // Invalid Graphviz DOT syntax
+    Dot {
+        line: usize,
+        column: usize,
+        message: String,
+    },
+    // Input that isn't well formed xml
+    Xml {
+        position: usize,
+        source: quick_xml::Error,
+    },
+    // Well formed xml that isn't valid GraphML (ie. missing attribute or unknown attr.type)
+    Graphml {
+        position: usize,
+        message: String,
+    },
+    // Well formed xml that isn't valid GEXF (ie. missing attribute or undeclared attribute)
+    Gexf {
+        position: usize,
+        message: String,
+    },
+    // Referential integrity problems found in strict mode (ie. edges to undeclared nodes), with their location
+    Integrity {
+        problems: Vec<String>,
+    },
+    // Format that isn't registered, or can't be read or written (ie. no reader)
+    UnsupportedFormat {
+        format: String,
+        message: String,
+    },
+}
+
+impl fmt::Display for ConvertError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConvertError::Io(e) => write!(f, "I/O error: {}", e),
+            ConvertError::Gml {
+                line,
+                column,
+                message,
+            } => write!(
+                f,
+                "Invalid GML at line {}, column {}: {}",
+                line, column, message
+            ),
+            ConvertError::Dot {
+                line,
+                column,
+                message,
+            } => write!(
+                f,
+                "Invalid DOT at line {}, column {}: {}",
+                line, column, message
+            ),
+            ConvertError::Xml { position, source } => {
+                write!(f, "Invalid XML at position {}: {}", position, source)
+            }
+            ConvertError::Graphml { position, message } => {
+                write!(f, "Invalid GraphML at position {}: {}", position, message)
+            }
+            ConvertError::Gexf { position, message } => {
+                write!(f, "Invalid GEXF at position {}: {}", position, message)
+            }
+            ConvertError::Integrity { problems } => {
+                write!(f, "Integrity problems: {}", problems.join("; "))
+            }
+            ConvertError::UnsupportedFormat { format, message } => {
+                write!(f, "Unsupported format {:?}: {}", format, message)
+            }
+        }
+    }
+}
+
+impl Error for ConvertError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            ConvertError::Io(e) => Some(e),
+            ConvertError::Xml { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for ConvertError {
+    fn from(e: io::Error) -> Self {
+        ConvertError::Io(e)
+    }
+}
+
+impl From<quick_xml::Error> for ConvertError {
+    // Errors of the xml writer, which are io errors in practice and have no input position
+    fn from(e: quick_xml::Error) -> Self {
+        match e {
+            quick_xml::Error::Io(e) => ConvertError::Io(e),
+            e => ConvertError::Xml {
+                position: 0,
+                source: e,
+            },
+        }
+    }
+}
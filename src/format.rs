@@ -0,0 +1,233 @@
+// Graph formats as streaming readers and writers of graph events, and a registry of them by name and extension
+// Any format with a reader converts to any format with a writer through the events, unless it has a direct
+// converter to the other format (ie. gml and graphml, whose converters keep nested graphs and key defaults).
+// Other formats (ie. in house ones) plug in by implementing the traits and registering them.
+
+use std::ffi::OsStr;
+use std::fs::File;
+use std::io::{BufRead, BufReader, Write};
+use std::path::Path;
+
+use crate::error::ConvertError;
+use crate::graph::GraphEvent;
+use crate::stats::ConversionStats;
+use crate::{dot, gexf, gml, graphml};
+
+// Events of a reader, until the end of its input or its first error
+pub type Events<'a> = Box<dyn Iterator<Item = Result<GraphEvent, ConvertError>> + 'a>;
+
+pub trait GraphReader {
+    fn read_events<'a>(&self, input: Box<dyn BufRead + 'a>) -> Events<'a>;
+}
+
+pub trait GraphWriter {
+    // Write every event to the output, stopping at the first error of the events
+    fn write_events(
+        &self,
+        events: &mut Events<'_>,
+        output: &mut dyn Write,
+    ) -> Result<(), ConvertError>;
+}
+
+pub trait GraphConverter {
+    // Convert the input to the output format without going through the graph events
+    fn convert<'a>(
+        &self,
+        input: Box<dyn BufRead + 'a>,
+        output: &mut dyn Write,
+    ) -> Result<ConversionStats, ConvertError>;
+
+    // Convert a file, which can be read twice
+    fn convert_file(
+        &self,
+        input: BufReader<File>,
+        output: &mut dyn Write,
+    ) -> Result<ConversionStats, ConvertError> {
+        self.convert(Box::new(input), output)
+    }
+}
+
+pub struct Format {
+    pub name: String,
+    // File extensions without the dot (ie. gml)
+    pub extensions: Vec<String>,
+    pub reader: Option<Box<dyn GraphReader>>,
+    pub writer: Option<Box<dyn GraphWriter>>,
+    // Direct converters by output format name
+    pub converters: Vec<(String, Box<dyn GraphConverter>)>,
+}
+
+impl Format {
+    pub fn new(name: &str, extensions: &[&str]) -> Self {
+        Format {
+            name: name.to_string(),
+            extensions: extensions
+                .iter()
+                .map(|extension| extension.to_string())
+                .collect(),
+            reader: None,
+            writer: None,
+            converters: Vec::new(),
+        }
+    }
+
+    pub fn with_reader(mut self, reader: impl GraphReader + 'static) -> Self {
+        self.reader = Some(Box::new(reader));
+        self
+    }
+
+    pub fn with_writer(mut self, writer: impl GraphWriter + 'static) -> Self {
+        self.writer = Some(Box::new(writer));
+        self
+    }
+
+    pub fn with_converter(mut self, to: &str, converter: impl GraphConverter + 'static) -> Self {
+        self.converters.push((to.to_string(), Box::new(converter)));
+        self
+    }
+
+    pub fn converter(&self, to: &str) -> Option<&dyn GraphConverter> {
+        self.converters
+            .iter()
+            .find(|(name, _)| name == to)
+            .map(|(_, converter)| converter.as_ref())
+    }
+}
+
+#[derive(Default)]
+pub struct Registry {
+    formats: Vec<Format>,
+}
+
+impl Registry {
+    pub fn new() -> Self {
+        // Registry with the formats of the crate
+        Registry::with_options(gml::Gml::default(), graphml::Graphml::default())
+    }
+
+    pub fn with_options(gml: gml::Gml, graphml: graphml::Graphml) -> Self {
+        // Registry with the formats of the crate, gml and graphml with the options of their converters
+        let mut registry = Registry::empty();
+        registry.register(
+            Format::new("gml", &["gml"])
+                .with_reader(gml.clone())
+                .with_writer(gml.clone())
+                .with_converter("graphml", gml),
+        );
+        registry.register(
+            Format::new("graphml", &["graphml"])
+                .with_reader(graphml.clone())
+                .with_writer(graphml.clone())
+                .with_converter("gml", graphml),
+        );
+        registry.register(
+            Format::new("gexf", &["gexf"])
+                .with_reader(gexf::Gexf)
+                .with_writer(gexf::Gexf),
+        );
+        registry.register(
+            Format::new("dot", &["dot", "gv"])
+                .with_reader(dot::Dot)
+                .with_writer(dot::Dot),
+        );
+        registry
+    }
+
+    pub fn empty() -> Self {
+        Default::default()
+    }
+
+    pub fn register(&mut self, format: Format) {
+        // Add a format, replacing the format with the same name
+        self.formats
+            .retain(|registered| registered.name != format.name);
+        self.formats.push(format);
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Format> {
+        self.formats.iter().find(|format| format.name == name)
+    }
+
+    pub fn format_of(&self, path: &Path) -> Option<&Format> {
+        // Detect the format of a file from its extension, ignoring the case (ie. GML)
+        let extension = path.extension().and_then(OsStr::to_str)?.to_lowercase();
+        self.formats
+            .iter()
+            .find(|format| format.extensions.contains(&extension))
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.formats.iter().map(|format| format.name.as_str())
+    }
+
+    pub fn convert<'a>(
+        &self,
+        from: &str,
+        to: &str,
+        input: Box<dyn BufRead + 'a>,
+        mut output: impl Write,
+    ) -> Result<ConversionStats, ConvertError> {
+        // Convert between two registered formats, with the direct converter or through the graph events
+        if let Some(converter) = self.format(from)?.converter(to) {
+            return converter.convert(input, &mut output);
+        }
+        self.convert_events(from, to, input, output)
+    }
+
+    pub fn convert_file(
+        &self,
+        from: &str,
+        to: &str,
+        input: BufReader<File>,
+        mut output: impl Write,
+    ) -> Result<ConversionStats, ConvertError> {
+        // Convert a file between two registered formats, the direct converters can read it twice
+        if let Some(converter) = self.format(from)?.converter(to) {
+            return converter.convert_file(input, &mut output);
+        }
+        self.convert_events(from, to, Box::new(input), output)
+    }
+
+    fn format(&self, name: &str) -> Result<&Format, ConvertError> {
+        self.get(name)
+            .ok_or_else(|| ConvertError::UnsupportedFormat {
+                format: name.to_string(),
+                message: "unknown format".to_string(),
+            })
+    }
+
+    fn convert_events<'a>(
+        &self,
+        from: &str,
+        to: &str,
+        input: Box<dyn BufRead + 'a>,
+        mut output: impl Write,
+    ) -> Result<ConversionStats, ConvertError> {
+        // Convert between two registered formats through the graph events
+        let unsupported = |name: &str, message: &str| ConvertError::UnsupportedFormat {
+            format: name.to_string(),
+            message: message.to_string(),
+        };
+        let reader = self
+            .format(from)?
+            .reader
+            .as_ref()
+            .ok_or_else(|| unsupported(from, "no reader"))?;
+        let writer = self
+            .format(to)?
+            .writer
+            .as_ref()
+            .ok_or_else(|| unsupported(to, "no writer"))?;
+
+        let mut stats = ConversionStats::default();
+        let counted = reader.read_events(input).inspect(|event| match event {
+            Ok(GraphEvent::Node { .. }) => stats.nodes += 1,
+            Ok(GraphEvent::Edge { .. }) => stats.edges += 1,
+            _ => {}
+        });
+        let mut events: Events<'_> = Box::new(counted);
+        writer.write_events(&mut events, &mut output)?;
+        drop(events);
+        Ok(stats)
+    }
+}
@@ -0,0 +1,888 @@
+// GEXF (Gephi) reader and writer of graph events
+// Attribute declarations (<attributes class="node|edge">) are read like GraphML keys: the values of a node or
+// edge are typed from the type of their attribute and named after its title. Labels, edge weights, spells and
+// start/end times become attributes of the same name, dynamic attribute values a list of their values and
+// times, and the viz: elements a viz map (ie. viz [ color [ r 255 g 0 b 0 ] size 2.0 ]). Nested nodes are
+// flattened, and attribute defaults and options are left out.
+
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Seek, SeekFrom, Write};
+use std::str;
+
+use quick_xml::events::{BytesDecl, BytesEnd, BytesStart, BytesText, Event};
+use quick_xml::{Reader, Writer};
+
+use crate::attr_types::{GraphmlAttributeTypes, parse_json};
+use crate::error::ConvertError;
+use crate::format::{self, GraphReader, GraphWriter};
+use crate::graph::{self, AttrValue, Attrs, Edge, EventSource, GraphEvent, GraphHeader};
+use crate::graphml::data_text;
+use crate::xml::{Dialect, Keys, get_attribute, required_attribute};
+
+#[derive(Debug, Clone)]
+struct Key {
+    title: String,
+    attr_type: GraphmlAttributeTypes,
+    // List types (ie. liststring) hold several values of the type
+    list: bool,
+}
+
+// Meta elements kept as graph attributes
+const META: [&str; 3] = ["creator", "description", "keywords"];
+// Attributes of the nodes and edges that are written as xml attributes rather than attribute values
+const NODE_ATTRIBUTES: [&str; 4] = ["label", "pid", "start", "end"];
+const EDGE_ATTRIBUTES: [&str; 5] = ["label", "weight", "kind", "start", "end"];
+
+fn attr_value(value: &str, key: &Key, position: usize) -> Result<AttrValue, ConvertError> {
+    // Convert an attribute value to the attribute type, serialized lists and maps are read whatever the type.
+    // List types (ie. liststring) hold [a, b] or a|b values.
+    let invalid = || ConvertError::Gexf {
+        position,
+        message: format!("Invalid {} value {:?}", key.title, value),
+    };
+    match parse_json(value) {
+        Ok(Some(json)) => return Ok(AttrValue::from_json(&json)),
+        Ok(None) => {}
+        Err(message) => return Err(ConvertError::Gexf { position, message }),
+    }
+    if !key.list {
+        return key.attr_type.parse(value).ok_or_else(invalid);
+    }
+    let items = value.trim();
+    let items = match items.strip_prefix('[').and_then(|v| v.strip_suffix(']')) {
+        Some(items) => items.split(','),
+        None => items.split('|'),
+    };
+    items
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+        .map(|item| key.attr_type.parse(item).ok_or_else(invalid))
+        .collect::<Result<_, _>>()
+        .map(AttrValue::List)
+}
+
+fn read_key(element: &BytesStart, position: usize) -> Result<(String, Key), ConvertError> {
+    // Read an attribute declaration: <attribute id="0" title="url" type="string"/>
+    let key_id = required_attribute(element, b"id", position, Dialect::Gexf)?;
+    let type_name =
+        get_attribute(element, b"type", position)?.unwrap_or_else(|| "string".to_string());
+    let item_type = type_name.strip_prefix("list");
+    let Some(attr_type) = GraphmlAttributeTypes::from_gexf_name(item_type.unwrap_or(&type_name))
+    else {
+        return Err(ConvertError::Gexf {
+            position,
+            message: format!(
+                "Unrecognized type {:?} for attribute {:?}",
+                type_name, key_id
+            ),
+        });
+    };
+    let key = Key {
+        // Attributes without a title are named after their id
+        title: get_attribute(element, b"title", position)?.unwrap_or_else(|| key_id.clone()),
+        attr_type,
+        list: item_type.is_some(),
+    };
+    Ok((key_id, key))
+}
+
+fn edge_direction(element: &BytesStart, position: usize) -> Result<Option<bool>, ConvertError> {
+    // Mutual edges go both ways, which is an undirected edge in the other formats
+    match get_attribute(element, b"type", position)?.as_deref() {
+        Some("directed") => Ok(Some(true)),
+        Some("undirected" | "mutual") => Ok(Some(false)),
+        Some(value) => Err(ConvertError::Gexf {
+            position,
+            message: format!("Invalid edge type {:?}", value),
+        }),
+        None => Ok(None),
+    }
+}
+
+fn viz_value(value: &str) -> AttrValue {
+    // Viz values are numbers, apart from shapes and hex colors
+    if let Ok(value) = value.parse::<i64>() {
+        AttrValue::Int(value)
+    } else if let Ok(value) = value.parse::<f64>() {
+        AttrValue::Float(value)
+    } else {
+        AttrValue::String(value.to_string())
+    }
+}
+
+fn read_viz(element: &BytesStart, position: usize) -> Result<AttrValue, ConvertError> {
+    // Read a viz element: a map of its xml attributes (ie. <viz:position x="1.0" y="2.0"/>) or the value of
+    // <viz:size value="2.0"/>
+    let mut attrs = Attrs::new();
+    for attr in element.html_attributes() {
+        let val = attr.map_err(|source| ConvertError::Xml { position, source })?;
+        let value = val
+            .unescaped_value()
+            .map_err(|source| ConvertError::Xml { position, source })?;
+        attrs.insert(
+            String::from_utf8_lossy(val.key).to_string(),
+            viz_value(&String::from_utf8_lossy(&value)),
+        );
+    }
+    match attrs.remove("value") {
+        Some(value) if attrs.is_empty() => Ok(value),
+        Some(value) => {
+            attrs.insert("value".to_string(), value);
+            Ok(AttrValue::Map(attrs))
+        }
+        None => Ok(AttrValue::Map(attrs)),
+    }
+}
+
+fn spell(element: &BytesStart, position: usize) -> Result<Attrs, ConvertError> {
+    // Times of a spell or dynamic attribute value, kept as strings as their format depends on the graph
+    let mut times = Attrs::new();
+    for name in ["start", "end"] {
+        if let Some(value) = get_attribute(element, name.as_bytes(), position)? {
+            times.insert(name.to_string(), AttrValue::String(value));
+        }
+    }
+    Ok(times)
+}
+
+fn push_value(attrs: &mut Attrs, name: &str, value: AttrValue) {
+    // Add a value to a list attribute
+    match attrs.get_mut(name) {
+        Some(AttrValue::List(values)) => values.push(value),
+        _ => {
+            attrs.insert(name.to_string(), AttrValue::List(vec![value]));
+        }
+    }
+}
+
+fn element_attrs<'a>(
+    edge: &'a mut Option<Edge>,
+    nodes: &'a mut [(String, Attrs)],
+) -> Option<(&'static str, &'a mut Attrs)> {
+    // Class and attributes of the node or edge being read
+    match (edge.as_mut(), nodes.last_mut()) {
+        (Some(edge), _) => Some(("edge", &mut edge.attrs)),
+        (None, Some((_, attrs))) => Some(("node", attrs)),
+        (None, None) => None,
+    }
+}
+
+pub struct EventReader<R: BufRead> {
+    reader: Reader<R>,
+    buf: Vec<u8>,
+    // Attribute declarations by class (node or edge) and id
+    keys: HashMap<(String, String), Key>,
+    // Class of the attribute declarations being read
+    class: Option<String>,
+    // Edge default and attributes of the graph being read, the meta before it are kept as its attributes
+    header: GraphHeader,
+    // Nodes being read: the nodes containing the nested nodes being read, then the current node. A node is
+    // returned at its end, after its nested nodes.
+    nodes: Vec<(String, Attrs)>,
+    edge: Option<Edge>,
+    // Depth of the elements being skipped (ie. an attribute default)
+    skipped: usize,
+    // Meta element being read and its text
+    meta: Option<(String, String)>,
+}
+
+impl<R: BufRead> EventReader<R> {
+    pub fn new(input: R) -> Self {
+        let mut reader = Reader::from_reader(input);
+        reader.expand_empty_elements(true);
+        EventReader {
+            reader,
+            buf: Vec::new(),
+            keys: HashMap::new(),
+            class: None,
+            header: GraphHeader::default(),
+            nodes: Vec::new(),
+            edge: None,
+            skipped: 0,
+            meta: None,
+        }
+    }
+}
+
+impl<R: BufRead> EventSource for EventReader<R> {
+    fn read_events(&mut self, events: &mut VecDeque<GraphEvent>) -> Result<bool, ConvertError> {
+        loop {
+            self.buf.clear();
+            let event = self.reader.read_event(&mut self.buf);
+            let position = self.reader.buffer_position();
+            match event {
+                // Elements inside skipped ones
+                Ok(Event::Start(_)) if self.skipped > 0 => self.skipped += 1,
+                Ok(Event::Start(ref e)) => match e.local_name() {
+                    b"gexf" | b"meta" | b"nodes" | b"edges" | b"attvalues" | b"spells" => {}
+                    name if self.meta.is_none()
+                        && META.contains(&str::from_utf8(name).unwrap_or_default()) =>
+                    {
+                        let name = String::from_utf8_lossy(name).to_string();
+                        self.meta = Some((name, String::new()));
+                    }
+                    b"graph" => {
+                        // Edges are undirected when the graph doesn't say
+                        self.header.directed = get_attribute(e, b"defaultedgetype", position)?
+                            .map(|value| value == "directed");
+                        self.header.started = false;
+                    }
+                    b"attributes" => {
+                        self.class = get_attribute(e, b"class", position)?;
+                    }
+                    b"attribute" => {
+                        let (id, key) = read_key(e, position)?;
+                        if let Some(class) = self.class.clone() {
+                            self.keys.insert((class, id), key);
+                        }
+                        // The attribute default and options are left out
+                        self.skipped += 1;
+                    }
+                    b"node" => {
+                        let id = required_attribute(e, b"id", position, Dialect::Gexf)?;
+                        let mut attrs = Attrs::new();
+                        for name in NODE_ATTRIBUTES {
+                            if let Some(value) = get_attribute(e, name.as_bytes(), position)? {
+                                attrs.insert(name.to_string(), AttrValue::String(value));
+                            }
+                        }
+                        self.nodes.push((id, attrs));
+                    }
+                    b"edge" => {
+                        // The edge direction is only kept when it differs from the graph one
+                        let directed = self.header.directed.unwrap_or(false);
+                        let mut attrs = Attrs::new();
+                        for name in EDGE_ATTRIBUTES {
+                            if let Some(value) = get_attribute(e, name.as_bytes(), position)? {
+                                let value = match name {
+                                    "weight" => value
+                                        .trim()
+                                        .parse::<f64>()
+                                        .map(AttrValue::Float)
+                                        .map_err(|_| ConvertError::Gexf {
+                                        position,
+                                        message: format!("Invalid weight value {:?}", value),
+                                    })?,
+                                    _ => AttrValue::String(value),
+                                };
+                                attrs.insert(name.to_string(), value);
+                            }
+                        }
+                        self.edge = Some(Edge {
+                            id: get_attribute(e, b"id", position)?,
+                            source: required_attribute(e, b"source", position, Dialect::Gexf)?,
+                            target: required_attribute(e, b"target", position, Dialect::Gexf)?,
+                            directed: edge_direction(e, position)?
+                                .filter(|&value| value != directed),
+                            attrs,
+                        });
+                    }
+                    b"attvalue" => {
+                        // GEXF 1.1 uses id instead of for
+                        let key_id = match get_attribute(e, b"for", position)? {
+                            Some(key_id) => key_id,
+                            None => required_attribute(e, b"id", position, Dialect::Gexf)?,
+                        };
+                        let value = required_attribute(e, b"value", position, Dialect::Gexf)?;
+                        let times = spell(e, position)?;
+                        if let Some((class, attrs)) = element_attrs(&mut self.edge, &mut self.nodes)
+                        {
+                            let key = self
+                                .keys
+                                .get(&(class.to_string(), key_id.clone()))
+                                .ok_or_else(|| ConvertError::Gexf {
+                                    position,
+                                    message: format!("Undeclared {} attribute {:?}", class, key_id),
+                                })?;
+                            let value = attr_value(&value, key, position)?;
+                            if times.is_empty() {
+                                attrs.insert(key.title.clone(), value);
+                            } else {
+                                // Dynamic values are a list of [ value start end ] maps
+                                let mut value = Attrs::from([("value".to_string(), value)]);
+                                value.extend(times);
+                                push_value(attrs, &key.title, AttrValue::Map(value));
+                            }
+                        }
+                        self.skipped += 1;
+                    }
+                    b"spell" => {
+                        let times = spell(e, position)?;
+                        if let Some((_, attrs)) = element_attrs(&mut self.edge, &mut self.nodes) {
+                            push_value(attrs, "spells", AttrValue::Map(times));
+                        }
+                        self.skipped += 1;
+                    }
+                    name @ (b"color" | b"position" | b"size" | b"thickness" | b"shape") => {
+                        let name = String::from_utf8_lossy(name).to_string();
+                        let value = read_viz(e, position)?;
+                        if let Some((_, attrs)) = element_attrs(&mut self.edge, &mut self.nodes) {
+                            let viz = attrs
+                                .entry("viz".to_string())
+                                .or_insert_with(|| AttrValue::Map(Attrs::new()));
+                            if let AttrValue::Map(viz) = viz {
+                                viz.insert(name, value);
+                            }
+                        }
+                        self.skipped += 1;
+                    }
+                    _ => {
+                        // parents and any other element
+                        self.skipped += 1;
+                    }
+                },
+                Ok(Event::End(ref e)) => {
+                    if self.skipped > 0 {
+                        self.skipped -= 1;
+                        continue;
+                    }
+                    match e.local_name() {
+                        b"graph" => {
+                            self.header.end(events);
+                            return Ok(true);
+                        }
+                        b"attributes" => self.class = None,
+                        b"node" => {
+                            if let Some((id, attrs)) = self.nodes.pop() {
+                                self.header.push(GraphEvent::Node { id, attrs }, events);
+                                return Ok(true);
+                            }
+                        }
+                        b"edge" => {
+                            if let Some(edge) = self.edge.take() {
+                                let event = GraphEvent::Edge {
+                                    id: edge.id,
+                                    source: edge.source,
+                                    target: edge.target,
+                                    directed: edge.directed,
+                                    attrs: edge.attrs,
+                                };
+                                self.header.push(event, events);
+                                return Ok(true);
+                            }
+                        }
+                        _ => {
+                            if let Some((name, text)) = self.meta.take() {
+                                self.header.attrs.insert(name, AttrValue::String(text));
+                            }
+                        }
+                    }
+                }
+                Ok(Event::Text(ref e)) | Ok(Event::CData(ref e)) if self.skipped == 0 => {
+                    if let Some((_, text)) = self.meta.as_mut() {
+                        let value = e
+                            .unescape_and_decode(&self.reader)
+                            .map_err(|source| ConvertError::Xml { position, source })?;
+                        text.push_str(&value);
+                    }
+                }
+                Ok(Event::Eof) => return Ok(false),
+                Err(source) => return Err(ConvertError::Xml { position, source }),
+                _ => {}
+            }
+        }
+    }
+}
+
+pub type Events<R> = graph::Events<EventReader<R>>;
+
+pub fn events<R: BufRead>(input: R) -> Events<R> {
+    // Stream the graph of a gexf input as events, without keeping it in memory
+    graph::Events::new(EventReader::new(input))
+}
+
+fn time_text(value: &AttrValue) -> Option<String> {
+    match value {
+        AttrValue::String(value) => Some(value.clone()),
+        AttrValue::Int(_) | AttrValue::Float(_) => Some(data_text(value)),
+        _ => None,
+    }
+}
+
+fn list_items(value: &AttrValue) -> Option<&[AttrValue]> {
+    // Items of a list, a single map being a list of one item (ie. a single GML spells list)
+    match value {
+        AttrValue::List(values) => Some(values),
+        AttrValue::Map(_) => Some(std::slice::from_ref(value)),
+        _ => None,
+    }
+}
+
+fn spells(value: &AttrValue) -> Option<Vec<(Option<String>, Option<String>)>> {
+    // Times of a spells list: [ [ start "1" end "2" ] ... ]
+    let values = list_items(value)?;
+    values
+        .iter()
+        .map(|value| match value {
+            AttrValue::Map(times) if times.keys().all(|name| name == "start" || name == "end") => {
+                let time = |name| times.get(name).map(time_text);
+                match (time("start"), time("end")) {
+                    (Some(None), _) | (_, Some(None)) => None,
+                    (start, end) => Some((start.flatten(), end.flatten())),
+                }
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+// Value of a dynamic attribute with its start and end times
+type DynamicValue<'a> = (&'a AttrValue, Vec<(&'static str, String)>);
+
+fn dynamic_values(value: &AttrValue) -> Option<Vec<DynamicValue<'_>>> {
+    // Values and times of a dynamic attribute: [ [ value 1 start "1" end "2" ] ... ]
+    let values = list_items(value)?;
+    if values.is_empty() {
+        return None;
+    }
+    values
+        .iter()
+        .map(|value| {
+            let AttrValue::Map(item) = value else {
+                return None;
+            };
+            let times = item
+                .iter()
+                .filter(|(name, _)| *name != "value")
+                .map(|(name, time)| match name.as_str() {
+                    "start" => Some(("start", time_text(time)?)),
+                    "end" => Some(("end", time_text(time)?)),
+                    _ => None,
+                })
+                .collect::<Option<Vec<_>>>()?;
+            match item.get("value") {
+                Some(value) if !times.is_empty() => Some((value, times)),
+                _ => None,
+            }
+        })
+        .collect()
+}
+
+fn is_viz(value: &AttrValue) -> bool {
+    let AttrValue::Map(viz) = value else {
+        return false;
+    };
+    viz.iter().all(|(name, value)| match name.as_str() {
+        "color" | "position" => matches!(value, AttrValue::Map(_)),
+        "size" | "thickness" | "shape" => !matches!(value, AttrValue::List(_)),
+        _ => false,
+    })
+}
+
+fn element_attribute(element: &str, name: &str, value: &AttrValue) -> bool {
+    // Whether an attribute is written as an xml attribute of its node or edge
+    match (element, name) {
+        ("node", "label" | "pid") | ("edge", "label" | "kind") => {
+            matches!(value, AttrValue::String(_))
+        }
+        ("edge", "weight") => matches!(value, AttrValue::Int(_) | AttrValue::Float(_)),
+        (_, "start" | "end") => time_text(value).is_some(),
+        (_, "spells") => spells(value).is_some(),
+        (_, "viz") => is_viz(value),
+        _ => false,
+    }
+}
+
+fn write_times<'a>(elem: &mut BytesStart<'a>, times: &[(&'a str, Option<String>)]) {
+    for (name, time) in times {
+        if let Some(time) = time {
+            elem.push_attribute((*name, time.as_str()));
+        }
+    }
+}
+
+fn write_viz<W: Write>(writer: &mut Writer<W>, viz: &Attrs) -> Result<(), ConvertError> {
+    for (name, value) in viz {
+        let tag = format!("viz:{}", name);
+        let mut elem = BytesStart::owned_name(tag.as_bytes());
+        match value {
+            AttrValue::Map(attrs) => {
+                for (name, value) in attrs {
+                    elem.push_attribute((name.as_str(), data_text(value).as_str()));
+                }
+            }
+            value => elem.push_attribute(("value", data_text(value).as_str())),
+        }
+        writer.write_event(Event::Empty(elem))?;
+    }
+    Ok(())
+}
+
+pub struct EventWriter<W: Write> {
+    output: W,
+    // The attribute declarations are only known once every event is written, and the nodes come before the
+    // edges in GEXF, so the nodes and edges are written to anonymous temp files first and copied to the output
+    // after the declarations. Each temp file starts with the elements containing its nodes or edges, so that
+    // they are indented like the rest of the output, and is copied from its offset.
+    nodes: Writer<BufWriter<File>>,
+    nodes_offset: u64,
+    edges: Writer<BufWriter<File>>,
+    edges_offset: u64,
+    // Numbers of the explicit edge ids of the form e<n>, which the ids given to the edges without one skip
+    edge_numbers: HashSet<u64>,
+    keys: Keys,
+    // Classes with dynamic values
+    dynamic_classes: Vec<&'static str>,
+    // Number of graphs written, only the direction and attributes of the first one are kept
+    graphs: usize,
+    directed: Option<bool>,
+    // Direction of the graph being written, its edges get a type when it differs from the first one
+    graph_directed: Option<bool>,
+    attrs: Option<Attrs>,
+    dynamic: bool,
+}
+
+fn body(container: &[u8]) -> Result<(Writer<BufWriter<File>>, u64), ConvertError> {
+    let mut writer = Writer::new_with_indent(BufWriter::new(tempfile::tempfile()?), b' ', 2);
+    for name in [&b"gexf"[..], b"graph", container] {
+        writer.write_event(Event::Start(BytesStart::borrowed_name(name)))?;
+    }
+    let offset = writer.inner().stream_position()?;
+    Ok((writer, offset))
+}
+
+fn copy_body<W: Write>(
+    body: Writer<BufWriter<File>>,
+    offset: u64,
+    output: &mut W,
+) -> Result<(), ConvertError> {
+    let mut body = body.into_inner().into_inner().map_err(|e| e.into_error())?;
+    body.seek(SeekFrom::Start(offset))?;
+    io::copy(&mut body, output)?;
+    Ok(())
+}
+
+fn edge_number(id: &str) -> Option<u64> {
+    // Number of an edge id of the form e<n> (ie. e1 but not e01)
+    id.strip_prefix('e')
+        .and_then(|number| number.parse::<u64>().ok())
+        .filter(|number| id == format!("e{}", number))
+}
+
+fn with_edge_id<'a>(
+    elem: BytesStart<'a>,
+    numbers: &mut impl Iterator<Item = u64>,
+) -> BytesStart<'a> {
+    // Edge element with the next generated id first when it has none, other elements as they are
+    if elem.name() != b"edge"
+        || elem
+            .attributes()
+            .any(|attr| attr.is_ok_and(|attr| attr.key == b"id"))
+    {
+        return elem;
+    }
+    let mut edge = BytesStart::owned_name(b"edge".to_vec());
+    let id = format!("e{}", numbers.next().unwrap_or_default());
+    edge.push_attribute(("id", id.as_str()));
+    edge.extend_attributes(elem.attributes().filter_map(Result::ok));
+    edge
+}
+
+fn copy_edges<W: Write>(
+    body: Writer<BufWriter<File>>,
+    offset: u64,
+    output: &mut W,
+    edge_numbers: &HashSet<u64>,
+) -> Result<(), ConvertError> {
+    // Copy the edges like copy_body, giving each edge without an id the next number no explicit id uses
+    // (ie. e0), numbered like the converters do
+    let mut body = body.into_inner().into_inner().map_err(|e| e.into_error())?;
+    body.seek(SeekFrom::Start(offset))?;
+    let mut reader = Reader::from_reader(BufReader::new(body));
+    let mut writer = Writer::new(output);
+    let mut numbers = (0..).filter(|number| !edge_numbers.contains(number));
+    let mut buf = Vec::new();
+    loop {
+        match reader.read_event(&mut buf)? {
+            Event::Eof => break,
+            Event::Start(elem) => {
+                writer.write_event(Event::Start(with_edge_id(elem, &mut numbers)))?
+            }
+            Event::Empty(elem) => {
+                writer.write_event(Event::Empty(with_edge_id(elem, &mut numbers)))?
+            }
+            event => writer.write_event(event)?,
+        }
+        buf.clear();
+    }
+    Ok(())
+}
+
+impl<W: Write> EventWriter<W> {
+    pub fn new(output: W) -> Result<Self, ConvertError> {
+        let (nodes, nodes_offset) = body(b"nodes")?;
+        let (edges, edges_offset) = body(b"edges")?;
+        Ok(EventWriter {
+            output,
+            nodes,
+            nodes_offset,
+            edges,
+            edges_offset,
+            edge_numbers: HashSet::new(),
+            keys: Keys::default(),
+            dynamic_classes: Vec::new(),
+            graphs: 0,
+            directed: None,
+            graph_directed: None,
+            attrs: None,
+            dynamic: false,
+        })
+    }
+
+    fn write_element(
+        &mut self,
+        element: &'static str,
+        mut elem: BytesStart,
+        attrs: &Attrs,
+    ) -> Result<(), ConvertError> {
+        // Write a node or edge with its attribute values, spells and viz elements
+        let mut values = Vec::new();
+        let mut spell_times = None;
+        let mut viz = None;
+        for (name, value) in attrs {
+            if !element_attribute(element, name, value) {
+                match dynamic_values(value) {
+                    Some(dynamic) => {
+                        for (value, _) in &dynamic {
+                            self.keys.add(element, name, value);
+                        }
+                        if !self.dynamic_classes.contains(&element) {
+                            self.dynamic_classes.push(element);
+                        }
+                        values.extend(
+                            dynamic
+                                .into_iter()
+                                .map(|(value, times)| (name, value, times)),
+                        );
+                    }
+                    None => {
+                        self.keys.add(element, name, value);
+                        values.push((name, value, Vec::new()));
+                    }
+                }
+                continue;
+            }
+            match name.as_str() {
+                "spells" => spell_times = spells(value),
+                "viz" => viz = Some(value),
+                "start" | "end" => {
+                    self.dynamic = true;
+                    elem.push_attribute((
+                        name.as_str(),
+                        time_text(value).unwrap_or_default().as_str(),
+                    ));
+                }
+                _ => elem.push_attribute((name.as_str(), data_text(value).as_str())),
+            }
+        }
+
+        let writer = match element {
+            "node" => &mut self.nodes,
+            _ => &mut self.edges,
+        };
+        if values.is_empty() && spell_times.is_none() && viz.is_none() {
+            writer.write_event(Event::Empty(elem))?;
+            return Ok(());
+        }
+        writer.write_event(Event::Start(elem))?;
+        if !values.is_empty() {
+            writer.write_event(Event::Start(BytesStart::borrowed_name(b"attvalues")))?;
+            for (name, value, times) in values {
+                let mut elem = BytesStart::borrowed_name(b"attvalue");
+                elem.push_attribute(("for", self.keys.index(element, name).to_string().as_str()));
+                elem.push_attribute(("value", data_text(value).as_str()));
+                for (name, time) in &times {
+                    elem.push_attribute((*name, time.as_str()));
+                }
+                writer.write_event(Event::Empty(elem))?;
+            }
+            writer.write_event(Event::End(BytesEnd::borrowed(b"attvalues")))?;
+        }
+        if let Some(spell_times) = spell_times {
+            self.dynamic = true;
+            writer.write_event(Event::Start(BytesStart::borrowed_name(b"spells")))?;
+            for (start, end) in spell_times {
+                let mut elem = BytesStart::borrowed_name(b"spell");
+                write_times(&mut elem, &[("start", start), ("end", end)]);
+                writer.write_event(Event::Empty(elem))?;
+            }
+            writer.write_event(Event::End(BytesEnd::borrowed(b"spells")))?;
+        }
+        if let Some(AttrValue::Map(viz)) = viz {
+            write_viz(writer, viz)?;
+        }
+        writer.write_event(Event::End(BytesEnd::borrowed(element.as_bytes())))?;
+        Ok(())
+    }
+
+    pub fn write(&mut self, event: &GraphEvent) -> Result<(), ConvertError> {
+        // GEXF has a single graph, the graphs after the first one are merged into it
+        match event {
+            GraphEvent::GraphStart { directed, attrs } => {
+                self.graphs += 1;
+                self.graph_directed = *directed;
+                if self.graphs == 1 {
+                    self.directed = *directed;
+                    self.attrs = Some(attrs.clone());
+                }
+            }
+            GraphEvent::Node { id, attrs } => {
+                let mut elem = BytesStart::borrowed_name(b"node");
+                elem.push_attribute(("id", id.as_str()));
+                self.write_element("node", elem, attrs)?;
+            }
+            GraphEvent::Edge {
+                id,
+                source,
+                target,
+                directed,
+                attrs,
+            } => {
+                // GEXF edges need an id, the edges without one get theirs once every explicit id is known
+                let mut elem = BytesStart::borrowed_name(b"edge");
+                if let Some(id) = id {
+                    if let Some(number) = edge_number(id) {
+                        self.edge_numbers.insert(number);
+                    }
+                    elem.push_attribute(("id", id.as_str()));
+                }
+                elem.push_attribute(("source", source.as_str()));
+                elem.push_attribute(("target", target.as_str()));
+                // The edges of merged graphs keep the direction of their graph when it differs from the first one
+                let directed = directed.or_else(|| {
+                    (self.graph_directed != self.directed)
+                        .then(|| self.graph_directed.unwrap_or(false))
+                });
+                if let Some(directed) = directed {
+                    let edge_type = if directed { "directed" } else { "undirected" };
+                    elem.push_attribute(("type", edge_type));
+                }
+                self.write_element("edge", elem, attrs)?;
+            }
+            GraphEvent::GraphEnd { attrs } => {
+                if self.graphs == 1
+                    && let Some(graph_attrs) = self.attrs.as_mut()
+                {
+                    graph_attrs.extend(attrs.clone());
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub fn finish(mut self) -> Result<W, ConvertError> {
+        // Write the header, meta and attribute declarations, then copy the nodes and edges from the temp files
+        let mut writer = Writer::new_with_indent(&mut self.output, b' ', 2);
+        let header = BytesDecl::new(b"1.0", Some(b"UTF-8"), None);
+        writer.write_event(Event::Decl(header))?;
+        let mut elem = BytesStart::borrowed_name(b"gexf");
+        elem.push_attribute(("xmlns", "http://gexf.net/1.3"));
+        elem.push_attribute(("xmlns:viz", "http://gexf.net/1.3/viz"));
+        elem.push_attribute(("xmlns:xsi", "http://www.w3.org/2001/XMLSchema-instance"));
+        elem.push_attribute((
+            "xsi:schemaLocation",
+            "http://gexf.net/1.3 http://gexf.net/1.3/gexf.xsd",
+        ));
+        elem.push_attribute(("version", "1.3"));
+        writer.write_event(Event::Start(elem))?;
+
+        // Graph attributes other than the meta ones have no GEXF equivalent
+        let attrs = self.attrs.unwrap_or_default();
+        let meta: Vec<_> = META
+            .iter()
+            .filter_map(|&name| match attrs.get(name) {
+                Some(AttrValue::String(text)) => Some((name, text)),
+                _ => None,
+            })
+            .collect();
+        if !meta.is_empty() {
+            writer.write_event(Event::Start(BytesStart::borrowed_name(b"meta")))?;
+            for (name, text) in meta {
+                writer.write_event(Event::Start(BytesStart::borrowed_name(name.as_bytes())))?;
+                writer.write_event(Event::Text(BytesText::from_plain_str(text)))?;
+                writer.write_event(Event::End(BytesEnd::borrowed(name.as_bytes())))?;
+            }
+            writer.write_event(Event::End(BytesEnd::borrowed(b"meta")))?;
+        }
+
+        let mut elem = BytesStart::borrowed_name(b"graph");
+        match self.directed {
+            Some(true) => elem.push_attribute(("defaultedgetype", "directed")),
+            Some(false) => elem.push_attribute(("defaultedgetype", "undirected")),
+            None => {}
+        }
+        let dynamic = self.dynamic || !self.dynamic_classes.is_empty();
+        elem.push_attribute(("mode", if dynamic { "dynamic" } else { "static" }));
+        writer.write_event(Event::Start(elem))?;
+        for class in ["node", "edge"] {
+            let keys: Vec<_> = self
+                .keys
+                .keys
+                .iter()
+                .enumerate()
+                .filter(|(_, (element, _, _))| *element == class)
+                .collect();
+            if keys.is_empty() {
+                continue;
+            }
+            let mut elem = BytesStart::borrowed_name(b"attributes");
+            elem.push_attribute(("class", class));
+            let mode = if self.dynamic_classes.contains(&class) {
+                "dynamic"
+            } else {
+                "static"
+            };
+            elem.push_attribute(("mode", mode));
+            writer.write_event(Event::Start(elem))?;
+            for (index, (_, title, attr_type)) in keys {
+                let mut elem = BytesStart::borrowed_name(b"attribute");
+                elem.push_attribute(("id", index.to_string().as_str()));
+                elem.push_attribute(("title", title.as_str()));
+                elem.push_attribute(("type", attr_type.gexf_name()));
+                writer.write_event(Event::Empty(elem))?;
+            }
+            writer.write_event(Event::End(BytesEnd::borrowed(b"attributes")))?;
+        }
+
+        writer.write_event(Event::Start(BytesStart::borrowed_name(b"nodes")))?;
+        copy_body(self.nodes, self.nodes_offset, writer.inner())?;
+        writer.write_event(Event::End(BytesEnd::borrowed(b"nodes")))?;
+        writer.write_event(Event::Start(BytesStart::borrowed_name(b"edges")))?;
+        copy_edges(
+            self.edges,
+            self.edges_offset,
+            writer.inner(),
+            &self.edge_numbers,
+        )?;
+        writer.write_event(Event::End(BytesEnd::borrowed(b"edges")))?;
+        writer.write_event(Event::End(BytesEnd::borrowed(b"graph")))?;
+        writer.write_event(Event::End(BytesEnd::borrowed(b"gexf")))?;
+        self.output.flush()?;
+        Ok(self.output)
+    }
+}
+
+// GEXF in the format registry
+pub struct Gexf;
+
+impl GraphReader for Gexf {
+    fn read_events<'a>(&self, input: Box<dyn BufRead + 'a>) -> format::Events<'a> {
+        Box::new(events(input))
+    }
+}
+
+impl GraphWriter for Gexf {
+    fn write_events(
+        &self,
+        events: &mut format::Events<'_>,
+        output: &mut dyn Write,
+    ) -> Result<(), ConvertError> {
+        let mut writer = EventWriter::new(output)?;
+        for event in events {
+            writer.write(&event?)?;
+        }
+        writer.finish()?;
+        Ok(())
+    }
+}
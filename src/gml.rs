@@ -0,0 +1,375 @@
+// GML reader and writer of graph events and of the in memory graph model
+// The reader streams the items of the GML parser the converter uses, one node or edge at a time. The graph
+// attributes before the first node or edge (and the keys before the graph, ie. Creator) are returned with the graph
+// start, the ones after it with the graph end.
+// Integer node ids are read as n<id> and edge ids as e<id>, and a node graphmlId attribute restores its original
+// id, the same way the converters map the ids. String gids (ie. the DOT clusters) are written as the GML id of their
+// group node.
+
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{BufRead, BufReader, Write};
+
+use crate::error::ConvertError;
+use crate::format::{self, GraphConverter, GraphReader, GraphWriter};
+use crate::gml_lexer::Token;
+use crate::gml_parser::{self, End, Item, Node, Parser, Value, group};
+use crate::gml_to_graphml;
+use crate::gml_writer::{INDENT_2, LINE_BREAK, gml_string, write_attrs, write_line};
+use crate::graph::{self, AttrValue, Attrs, EventSource, Graph, GraphEvent, GraphHeader};
+use crate::multi_graph::graph_prefix;
+use crate::node_ids::{GmlIds, GraphmlIds, ORIGINAL_ID_ATTRIBUTE, parse_graphml_edge_id};
+use crate::stats::ConversionStats;
+
+fn attr_value(value: &Value) -> AttrValue {
+    // Convert a gml value to an attribute like the converter converts it to json, strings holding a json list
+    // (ie. a nested list) are read as lists
+    match value {
+        Value::Scalar(Token::Int(number)) => match number.parse::<i64>() {
+            Ok(number) => AttrValue::Int(number),
+            Err(_) => AttrValue::String(number.to_string()),
+        },
+        Value::Scalar(Token::Real(number)) => match number.parse::<f64>() {
+            Ok(number) => AttrValue::Float(number),
+            Err(_) => AttrValue::String(number.to_string()),
+        },
+        Value::Scalar(_) | Value::List(_) => AttrValue::from_json(&value.to_json()),
+    }
+}
+
+fn attrs(pairs: &[(String, Value)]) -> Attrs {
+    // Attributes of the pairs of a list, a key that is repeated in the same list becomes a list
+    // (ie. [ a 1 a 2 ] -> a: [1, 2])
+    group(pairs)
+        .into_iter()
+        .map(|(name, values)| {
+            let value = match values.as_slice() {
+                [value] => attr_value(value),
+                values => AttrValue::List(values.iter().map(|value| attr_value(value)).collect()),
+            };
+            (name.to_string(), value)
+        })
+        .collect()
+}
+
+fn read_node(node: Node, ids: &mut GraphmlIds) -> GraphEvent {
+    // Read a node, with its GraphML style id
+    let mut attrs = attrs(&node.attrs);
+    let mut id = node.id.unwrap_or_default();
+    if let Some(name) = node.name
+        && !node.gml_id.is_some_and(|gml_id| ids.insert(gml_id, &name))
+    {
+        // The name can't be used as an id (ie. used by another node, or an edge read before the node already
+        // used its default id), keep it as an attribute instead
+        attrs.insert(ORIGINAL_ID_ATTRIBUTE.to_string(), AttrValue::String(name));
+    }
+    if let Some(gml_id) = node.gml_id {
+        id = ids.get(gml_id);
+    }
+    GraphEvent::Node { id, attrs }
+}
+
+fn read_edge(mut edge: gml_parser::Edge, ids: &mut GraphmlIds, directed: bool) -> GraphEvent {
+    // Read an edge, its direction is only kept when it differs from the graph one
+    let mut end_id = |end: Option<End>| match end {
+        Some(End {
+            gml_id: Some(gml_id),
+            ..
+        }) => ids.get_referenced(gml_id),
+        Some(end) => end.text,
+        None => String::new(),
+    };
+    let source = end_id(edge.source);
+    let target = end_id(edge.target);
+    let mut id = edge.id.map(|id| ids.edge_id(&id));
+    if let Some(key) = edge.key {
+        // The parallel edge key of a multigraph is its id when it has none, like in the converters
+        match id {
+            None => id = Some(ids.edge_id(key.text().unwrap_or_default())),
+            Some(_) => edge.attrs.insert(0, ("key".to_string(), key)),
+        }
+    }
+    GraphEvent::Edge {
+        id,
+        source,
+        target,
+        directed: edge.directed.filter(|&value| value != directed),
+        attrs: attrs(&edge.attrs),
+    }
+}
+
+#[derive(Debug, Default)]
+struct GraphState {
+    header: GraphHeader,
+    // Graph attributes read since the last node or edge, added to the header before the next event
+    attrs: Vec<(String, Value)>,
+    ids: GraphmlIds,
+}
+
+impl GraphState {
+    fn push(&mut self, event: GraphEvent, events: &mut VecDeque<GraphEvent>) {
+        self.header
+            .attrs
+            .extend(attrs(&std::mem::take(&mut self.attrs)));
+        self.header.push(event, events);
+    }
+
+    fn end(&mut self, events: &mut VecDeque<GraphEvent>) {
+        self.header
+            .attrs
+            .extend(attrs(&std::mem::take(&mut self.attrs)));
+        self.header.end(events);
+    }
+}
+
+pub struct EventReader<R: BufRead> {
+    parser: Parser<R>,
+    // Graph being read, None at the top level
+    graph: Option<GraphState>,
+    // Number of graphs read
+    graphs: usize,
+}
+
+impl<R: BufRead> EventReader<R> {
+    pub fn new(input: R) -> Self {
+        EventReader {
+            parser: Parser::new(input),
+            graph: None,
+            graphs: 0,
+        }
+    }
+}
+
+impl<R: BufRead> EventSource for EventReader<R> {
+    fn read_events(&mut self, events: &mut VecDeque<GraphEvent>) -> Result<bool, ConvertError> {
+        loop {
+            let Some(item) = self.parser.next_item()? else {
+                return Ok(false);
+            };
+            if let Item::GraphStart = item {
+                // The ids of the graphs after the first one are namespaced (ie. g1:n1)
+                let prefix = graph_prefix(self.graphs, None);
+                self.graphs += 1;
+                self.graph = Some(GraphState {
+                    ids: GraphmlIds::with_prefix(prefix),
+                    ..Default::default()
+                });
+                continue;
+            }
+            let graph = self.graph.as_mut().expect("items are read in a graph");
+            match item {
+                Item::GraphStart => unreachable!("Graph starts are read above"),
+                Item::Directed(directed) => graph.header.directed = Some(directed),
+                // Graph attributes after the first node or edge are kept until the end of the graph
+                Item::GraphAttr { name, value } => graph.attrs.push((name, value)),
+                Item::Node(node) => {
+                    let event = read_node(node, &mut graph.ids);
+                    graph.push(event, events);
+                    return Ok(true);
+                }
+                Item::Edge(edge) => {
+                    let directed = graph.header.directed.unwrap_or(false);
+                    let event = read_edge(edge, &mut graph.ids, directed);
+                    graph.push(event, events);
+                    return Ok(true);
+                }
+                Item::GraphEnd => {
+                    if let Some(mut graph) = self.graph.take() {
+                        graph.end(events);
+                    }
+                    return Ok(true);
+                }
+            }
+        }
+    }
+}
+
+pub type Events<R> = graph::Events<EventReader<R>>;
+
+pub fn events<R: BufRead>(input: R) -> Events<R> {
+    // Stream the graphs of a gml input as events, without keeping them in memory.
+    // Note: nodes should be declared before their edges for their original ids (graphmlId attributes) to be used
+    graph::Events::new(EventReader::new(input))
+}
+
+pub fn read_all<R: BufRead>(input: R) -> Result<Vec<Graph>, ConvertError> {
+    // Read every graph of a gml input
+    let mut events = events(input);
+    let mut graphs = Vec::new();
+    while let Some(graph) = Graph::read_events(&mut events)? {
+        graphs.push(graph);
+    }
+    Ok(graphs)
+}
+
+pub fn read<R: BufRead>(input: R) -> Result<Graph, ConvertError> {
+    // Read the first graph of a gml input
+    Graph::read_events(&mut events(input))?.ok_or_else(|| ConvertError::Gml {
+        line: 0,
+        column: 0,
+        message: "No graph found".to_string(),
+    })
+}
+
+fn write_edge_id<W: Write>(writer: &mut W, id: &str, indent: &str) -> std::io::Result<()> {
+    // Default ids are written back as integers (ie. e1 -> 1), any other id as a string
+    match parse_graphml_edge_id(id) {
+        Some(gml_id) => write_line(writer, indent, "id", &gml_id.to_string()),
+        None => write_line(writer, indent, "id", &gml_string(id)),
+    }
+}
+
+pub struct EventWriter<W: Write> {
+    output: W,
+    // GML ids of the nodes of the current graph, assigned in order so that the numbered ids are kept
+    ids: GmlIds,
+    graphs: usize,
+}
+
+impl<W: Write> EventWriter<W> {
+    pub fn new(output: W) -> Self {
+        EventWriter {
+            output,
+            ids: GmlIds::new(),
+            graphs: 0,
+        }
+    }
+
+    pub fn write(&mut self, event: &GraphEvent) -> Result<(), ConvertError> {
+        // Write an event in the same layout as the converters
+        let indent_4 = format!("{}{}", INDENT_2, INDENT_2);
+        let writer = &mut self.output;
+        match event {
+            GraphEvent::GraphStart { directed, attrs } => {
+                // Graphs after the first one start on a new line
+                if self.graphs > 0 {
+                    writer.write_all(LINE_BREAK)?;
+                }
+                // Read back the namespaced ids of the graphs after the first one
+                self.ids = GmlIds::with_prefix(graph_prefix(self.graphs, None));
+                self.graphs += 1;
+                writer.write_all(b"graph [")?;
+                writer.write_all(LINE_BREAK)?;
+                if let Some(directed) = directed {
+                    write_line(writer, INDENT_2, "directed", &(*directed as i8).to_string())?;
+                }
+                write_attrs(writer, attrs, INDENT_2)?;
+            }
+            GraphEvent::Node { id, attrs } => {
+                let gml_id = self.ids.get_or_insert(id);
+                writer.write_all(format!("{}node [", INDENT_2).as_bytes())?;
+                writer.write_all(LINE_BREAK)?;
+                write_line(writer, &indent_4, "id", &gml_id.to_string())?;
+                if let Some(name) = self.ids.original_id(id, gml_id) {
+                    write_line(writer, &indent_4, ORIGINAL_ID_ATTRIBUTE, &gml_string(name))?;
+                }
+                match attrs.get("gid") {
+                    // A GML gid is the integer id of the group node
+                    Some(AttrValue::String(gid)) => {
+                        let mut attrs = attrs.clone();
+                        let gid = self.ids.get_or_insert(gid);
+                        attrs.insert("gid".to_string(), AttrValue::Int(gid));
+                        write_attrs(writer, &attrs, &indent_4)?;
+                    }
+                    _ => write_attrs(writer, attrs, &indent_4)?,
+                }
+                writer.write_all(format!("{}]", INDENT_2).as_bytes())?;
+                writer.write_all(LINE_BREAK)?;
+            }
+            GraphEvent::Edge {
+                id,
+                source,
+                target,
+                directed,
+                attrs,
+            } => {
+                writer.write_all(format!("{}edge [", INDENT_2).as_bytes())?;
+                writer.write_all(LINE_BREAK)?;
+                if let Some(id) = id {
+                    write_edge_id(writer, self.ids.strip_prefix(id), &indent_4)?;
+                }
+                let source = self.ids.get_or_insert(source);
+                write_line(writer, &indent_4, "source", &source.to_string())?;
+                let target = self.ids.get_or_insert(target);
+                write_line(writer, &indent_4, "target", &target.to_string())?;
+                if let Some(directed) = directed {
+                    write_line(
+                        writer,
+                        &indent_4,
+                        "directed",
+                        &(*directed as i8).to_string(),
+                    )?;
+                }
+                write_attrs(writer, attrs, &indent_4)?;
+                writer.write_all(format!("{}]", INDENT_2).as_bytes())?;
+                writer.write_all(LINE_BREAK)?;
+            }
+            GraphEvent::GraphEnd { attrs } => {
+                write_attrs(writer, attrs, INDENT_2)?;
+                writer.write_all(b"]")?;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn finish(mut self) -> Result<W, ConvertError> {
+        self.output.flush()?;
+        Ok(self.output)
+    }
+}
+
+pub fn write<W: Write>(graph: &Graph, output: W) -> Result<(), ConvertError> {
+    // Write a graph, nodes and edges in their order in the graph
+    let mut writer = EventWriter::new(output);
+    for event in graph.events() {
+        writer.write(&event)?;
+    }
+    writer.finish()?;
+    Ok(())
+}
+
+// GML in the format registry, with the options of its converter to graphml
+#[derive(Debug, Clone, Default)]
+pub struct Gml {
+    pub options: gml_to_graphml::Options,
+}
+
+impl GraphReader for Gml {
+    fn read_events<'a>(&self, input: Box<dyn BufRead + 'a>) -> format::Events<'a> {
+        Box::new(events(input))
+    }
+}
+
+impl GraphConverter for Gml {
+    fn convert<'a>(
+        &self,
+        input: Box<dyn BufRead + 'a>,
+        output: &mut dyn Write,
+    ) -> Result<ConversionStats, ConvertError> {
+        gml_to_graphml::convert_with_options(input, output, &self.options)
+    }
+
+    fn convert_file(
+        &self,
+        input: BufReader<File>,
+        output: &mut dyn Write,
+    ) -> Result<ConversionStats, ConvertError> {
+        // Files are read twice, so no temp file is needed
+        gml_to_graphml::convert_seekable_with_options(input, output, &self.options)
+    }
+}
+
+impl GraphWriter for Gml {
+    fn write_events(
+        &self,
+        events: &mut format::Events<'_>,
+        output: &mut dyn Write,
+    ) -> Result<(), ConvertError> {
+        let mut writer = EventWriter::new(output);
+        for event in events {
+            writer.write(&event?)?;
+        }
+        writer.finish()?;
+        Ok(())
+    }
+}
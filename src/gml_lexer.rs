@@ -0,0 +1,165 @@
+// Streaming GML tokenizer
+// Reads the input line by line through the shared cursor::Cursor and splits it into keys, numbers, strings and
+// list brackets, so that the layout of the file (one pair per line or everything on one line) doesn't matter.
+
+use std::io::BufRead;
+
+use crate::cursor::{Cursor, Syntax};
+use crate::error::ConvertError;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Token {
+    Key(String),
+    // Numbers keep their original text so they can be written back unchanged
+    Int(String),
+    Real(String),
+    // Strings are unquoted, with html entities (ie. &quot;) decoded
+    Str(String),
+    ListStart,
+    ListEnd,
+}
+
+pub struct Lexer<R: BufRead> {
+    cursor: Cursor<R>,
+}
+
+impl<R: BufRead> Lexer<R> {
+    pub fn new(reader: R) -> Self {
+        Lexer {
+            cursor: Cursor::new(reader, Syntax::Gml),
+        }
+    }
+
+    pub fn position(&self) -> (usize, usize) {
+        // Line and column (both starting at 1) of the last token returned
+        self.cursor.position()
+    }
+
+    pub fn error(&self, message: String) -> ConvertError {
+        // Build an error at the position of the last token returned
+        self.cursor.error(message)
+    }
+
+    fn read_string(&mut self) -> Result<Token, ConvertError> {
+        // Read a quoted string (the opening quote is already consumed), which can span several lines
+        let mut value = String::new();
+        loop {
+            let rest = self.cursor.rest();
+            if let Some(end) = rest.find('"') {
+                value.push_str(&rest[..end]);
+                self.cursor.advance(end + 1);
+                return Ok(Token::Str(decode_entities(&value)));
+            }
+            value.push_str(rest);
+            if !self.cursor.read_line()? {
+                return Err(self.error("Unterminated string".to_string()));
+            }
+        }
+    }
+
+    fn read_number(&mut self) -> Result<Token, ConvertError> {
+        let number = self
+            .cursor
+            .take_while(|c| c.is_ascii_digit() || "+-.eE".contains(c));
+        if number.parse::<i64>().is_ok() {
+            Ok(Token::Int(number))
+        } else if number.parse::<f64>().is_ok() {
+            Ok(Token::Real(number))
+        } else {
+            Err(self.error(format!("Invalid number {:?}", number)))
+        }
+    }
+
+    fn read_token(&mut self) -> Result<Option<Token>, ConvertError> {
+        loop {
+            // Skip whitespace and move to the next line when the current one is consumed
+            self.cursor.take_while(char::is_whitespace);
+            let c = match self.cursor.peek_char() {
+                Some(c) => c,
+                None => {
+                    if !self.cursor.read_line()? {
+                        return Ok(None);
+                    }
+                    continue;
+                }
+            };
+
+            self.cursor.start_token();
+
+            let token = match c {
+                '#' => {
+                    // skip comments until the end of the line
+                    self.cursor.skip_line();
+                    continue;
+                }
+                '[' => {
+                    self.cursor.advance(1);
+                    Token::ListStart
+                }
+                ']' => {
+                    self.cursor.advance(1);
+                    Token::ListEnd
+                }
+                '"' => {
+                    self.cursor.advance(1);
+                    self.read_string()?
+                }
+                c if c.is_ascii_digit() || "+-.".contains(c) => self.read_number()?,
+                c if c.is_ascii_alphabetic() || c == '_' => Token::Key(
+                    self.cursor
+                        .take_while(|c| c.is_ascii_alphanumeric() || c == '_'),
+                ),
+                _ => return Err(self.error(format!("Unexpected character {:?}", c))),
+            };
+            return Ok(Some(token));
+        }
+    }
+}
+
+impl<R: BufRead> Iterator for Lexer<R> {
+    type Item = Result<Token, ConvertError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.read_token().transpose()
+    }
+}
+
+fn decode_entities(value: &str) -> String {
+    // Decode the html character entities GML uses inside strings, unknown entities are kept as is
+    if !value.contains('&') {
+        return value.to_string();
+    }
+    let mut decoded = String::with_capacity(value.len());
+    let mut rest = value;
+    while let Some(start) = rest.find('&') {
+        decoded.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let entity = rest.find(';').map(|end| (&rest[1..end], end));
+        let character = entity.and_then(|(name, _)| match name {
+            "quot" => Some('"'),
+            "amp" => Some('&'),
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "apos" => Some('\''),
+            _ => name
+                .strip_prefix('#')
+                .and_then(|code| match code.strip_prefix('x') {
+                    Some(hex) => u32::from_str_radix(hex, 16).ok(),
+                    None => code.parse::<u32>().ok(),
+                })
+                .and_then(char::from_u32),
+        });
+        match (character, entity) {
+            (Some(character), Some((_, end))) => {
+                decoded.push(character);
+                rest = &rest[end + 1..];
+            }
+            _ => {
+                decoded.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    decoded.push_str(rest);
+    decoded
+}
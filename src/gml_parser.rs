@@ -0,0 +1,407 @@
+// Streaming GML parser shared by the gml event reader and the gml to graphml converter
+// Reads the tokens of the input and returns the GML items in file order: the start and end of the top level
+// graphs, their direction and attributes, and their nodes and edges, which are read as a whole as they are small.
+// The keys both readers give a meaning to are taken out of the node and edge attributes: the ids, the original
+// id (name), the edge ends, the edge directions and the parallel edge keys of multigraphs. Keys outside of a graph
+// (ie. Creator) are attributes of the next graph, a graph without a closing bracket ends with the input, and the
+// direction of a graph can't change after its first node or edge, as they were returned with the previous one.
+
+use std::collections::VecDeque;
+use std::io::BufRead;
+
+use serde_json::{Map, Number};
+
+use crate::attr_types::parse_json;
+use crate::error::ConvertError;
+use crate::gml_lexer::{Lexer, Token};
+use crate::node_ids::{ORIGINAL_ID_ATTRIBUTE, graphml_edge_id};
+
+// Deepest nesting of the lists of a graph, deeper lists are an error
+pub const MAX_DEPTH: usize = 500;
+
+// Value of a key: a number or a string, or a list of key value pairs
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Scalar(Token),
+    List(Vec<(String, Value)>),
+}
+
+impl Value {
+    pub fn int(&self) -> Option<i64> {
+        match self {
+            Value::Scalar(Token::Int(value)) => value.parse::<i64>().ok(),
+            _ => None,
+        }
+    }
+
+    pub fn text(&self) -> Option<&str> {
+        // Scalar value as written, strings without their quotes
+        match self {
+            Value::Scalar(Token::Int(value) | Token::Real(value) | Token::Str(value)) => {
+                Some(value)
+            }
+            _ => None,
+        }
+    }
+
+    pub fn to_json(&self) -> serde_json::Value {
+        // Json value of a gml value: numbers that can't be represented in json are kept as strings, and strings
+        // holding a json list (how the gml writers keep a list nested in a list) are read as that list
+        match self {
+            Value::Scalar(Token::Int(value)) => match value.parse::<i64>() {
+                Ok(number) => serde_json::Value::from(number),
+                Err(_) => serde_json::Value::from(value.as_str()),
+            },
+            Value::Scalar(Token::Real(value)) => value
+                .parse::<f64>()
+                .ok()
+                .and_then(Number::from_f64)
+                .map_or_else(
+                    || serde_json::Value::from(value.as_str()),
+                    serde_json::Value::Number,
+                ),
+            Value::Scalar(Token::Str(value)) => match json_list(value) {
+                Some(list) => list,
+                None => serde_json::Value::from(value.as_str()),
+            },
+            Value::Scalar(_) => unreachable!("Only scalar tokens are kept as values"),
+            Value::List(pairs) => serde_json::Value::Object(
+                group(pairs)
+                    .into_iter()
+                    .map(|(name, values)| (name.to_string(), json_values(&values)))
+                    .collect::<Map<String, serde_json::Value>>(),
+            ),
+        }
+    }
+}
+
+pub fn json_list(value: &str) -> Option<serde_json::Value> {
+    // Json list held by a gml string (ie. "[1,2]")
+    parse_json(value)
+        .ok()
+        .flatten()
+        .filter(serde_json::Value::is_array)
+}
+
+pub fn json_values(values: &[&Value]) -> serde_json::Value {
+    // Json value of the values of a key, a key that is repeated in the same list is a json array
+    // (ie. [ a 1 a 2 ] -> {"a": [1, 2]}). GML can't nest arrays directly, so an array is always a repeated key.
+    match values {
+        [value] => value.to_json(),
+        values => serde_json::Value::Array(values.iter().map(|value| value.to_json()).collect()),
+    }
+}
+
+pub fn group(pairs: &[(String, Value)]) -> Vec<(&str, Vec<&Value>)> {
+    // Values of each key of a list, in the order the keys are first found
+    let mut groups: Vec<(&str, Vec<&Value>)> = Vec::new();
+    for (name, value) in pairs {
+        match groups.iter_mut().find(|(group, _)| group == name) {
+            Some((_, values)) => values.push(value),
+            None => groups.push((name, vec![value])),
+        }
+    }
+    groups
+}
+
+// Source or target of an edge
+#[derive(Debug, Clone, PartialEq)]
+pub struct End {
+    // GML id of the node, when it is an integer
+    pub gml_id: Option<i64>,
+    // Id as written (ie. for string ids)
+    pub text: String,
+    // Line and column of the id, for the warnings about undeclared nodes
+    pub position: (usize, usize),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Node {
+    // Line and column of the node key
+    pub position: (usize, usize),
+    pub gml_id: Option<i64>,
+    // Id of the nodes without an integer id, as written
+    pub id: Option<String>,
+    // Original id, from the first ORIGINAL_ID_ATTRIBUTE string
+    pub name: Option<String>,
+    pub attrs: Vec<(String, Value)>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Edge {
+    // Line and column of the edge key
+    pub position: (usize, usize),
+    // GraphML style id (ie. id 1 -> e1)
+    pub id: Option<String>,
+    // Parallel edge key of a multigraph, its id when it has none
+    pub key: Option<Value>,
+    pub source: Option<End>,
+    pub target: Option<End>,
+    pub directed: Option<bool>,
+    pub attrs: Vec<(String, Value)>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Item {
+    GraphStart,
+    // Direction of the graph, before its first node or edge
+    Directed(bool),
+    GraphAttr { name: String, value: Value },
+    Node(Node),
+    Edge(Edge),
+    GraphEnd,
+}
+
+// Key value pair of a list, with the position of its value
+struct Pair {
+    name: String,
+    value: Value,
+    position: (usize, usize),
+}
+
+fn read_list<R: BufRead>(tokens: &mut Lexer<R>) -> Result<Vec<Pair>, ConvertError> {
+    // Read the key value pairs of a list until its closing bracket. The lists nested in it are kept on a stack
+    // with their key until their own closing bracket, so any nesting is read without recursion, up to MAX_DEPTH
+    // levels as the values are converted and written recursively.
+    let mut stack: Vec<(String, Vec<Pair>)> = Vec::new();
+    let mut pairs = Vec::new();
+    loop {
+        let name = match tokens.next().transpose()? {
+            Some(Token::Key(name)) => name,
+            Some(Token::ListEnd) => match stack.pop() {
+                Some((name, parent)) => {
+                    let list = std::mem::replace(&mut pairs, parent);
+                    pairs.push(Pair {
+                        name,
+                        value: Value::List(
+                            list.into_iter()
+                                .map(|pair| (pair.name, pair.value))
+                                .collect(),
+                        ),
+                        position: Lexer::position(tokens),
+                    });
+                    continue;
+                }
+                None => return Ok(pairs),
+            },
+            None => return Err(tokens.error("Unterminated list".to_string())),
+            Some(_) => return Err(tokens.error("Expected a key".to_string())),
+        };
+        match tokens.next().transpose()? {
+            Some(Token::ListStart) if stack.len() + 1 >= MAX_DEPTH => {
+                return Err(tokens.error(format!("Lists nested deeper than {} levels", MAX_DEPTH)));
+            }
+            Some(Token::ListStart) => stack.push((name, std::mem::take(&mut pairs))),
+            Some(token @ (Token::Int(_) | Token::Real(_) | Token::Str(_))) => pairs.push(Pair {
+                name,
+                value: Value::Scalar(token),
+                position: Lexer::position(tokens),
+            }),
+            _ => return Err(tokens.error(format!("Missing value for key {:?}", name))),
+        }
+    }
+}
+
+fn read_value<R: BufRead>(tokens: &mut Lexer<R>, name: &str) -> Result<Value, ConvertError> {
+    // Read the value of a key, a list is read up to its closing bracket
+    match tokens.next().transpose()? {
+        Some(Token::ListStart) => Ok(Value::List(
+            read_list(tokens)?
+                .into_iter()
+                .map(|pair| (pair.name, pair.value))
+                .collect(),
+        )),
+        Some(token @ (Token::Int(_) | Token::Real(_) | Token::Str(_))) => Ok(Value::Scalar(token)),
+        _ => Err(tokens.error(format!("Missing value for key {:?}", name))),
+    }
+}
+
+fn read_node(pairs: Vec<Pair>, position: (usize, usize)) -> Node {
+    // Take the id and name of a node out of its attributes
+    let mut node = Node {
+        position,
+        gml_id: None,
+        id: None,
+        name: None,
+        attrs: Vec::new(),
+    };
+    for Pair { name, value, .. } in pairs {
+        match (name.as_str(), &value) {
+            // Integer ids that don't fit are used as written
+            ("id", value) if value.int().is_some() => node.gml_id = value.int(),
+            ("id", Value::Scalar(_)) => node.id = value.text().map(ToString::to_string),
+            (ORIGINAL_ID_ATTRIBUTE, Value::Scalar(Token::Str(id))) if node.name.is_none() => {
+                node.name = Some(id.to_string())
+            }
+            _ => node.attrs.push((name, value)),
+        }
+    }
+    node
+}
+
+fn read_edge(pairs: Vec<Pair>, position: (usize, usize), multigraph: bool) -> Edge {
+    // Take the id, ends, direction and key of an edge out of its attributes
+    let mut edge = Edge {
+        position,
+        id: None,
+        key: None,
+        source: None,
+        target: None,
+        directed: None,
+        attrs: Vec::new(),
+    };
+    for Pair {
+        name,
+        value,
+        position,
+    } in pairs
+    {
+        let end = |value: &Value| End {
+            gml_id: value.int(),
+            text: value.text().unwrap_or_default().to_string(),
+            position,
+        };
+        match (name.as_str(), &value) {
+            ("source", Value::Scalar(_)) => edge.source = Some(end(&value)),
+            ("target", Value::Scalar(_)) => edge.target = Some(end(&value)),
+            ("id", Value::Scalar(_)) => {
+                edge.id = match value.int() {
+                    Some(id) => Some(graphml_edge_id(id)),
+                    None => value.text().map(ToString::to_string),
+                }
+            }
+            ("directed", value) if value.int().is_some() => {
+                edge.directed = value.int().map(|directed| directed != 0)
+            }
+            ("key", Value::Scalar(_)) if multigraph && edge.key.is_none() => edge.key = Some(value),
+            _ => edge.attrs.push((name, value)),
+        }
+    }
+    edge
+}
+
+pub struct Parser<R: BufRead> {
+    tokens: Lexer<R>,
+    in_graph: bool,
+    // Keys read outside of a graph, for the next graph
+    pending: VecDeque<Pair>,
+    // Direction and kind of the graph being read, and whether a node or edge of it was returned
+    directed: bool,
+    multigraph: bool,
+    started: bool,
+}
+
+impl<R: BufRead> Parser<R> {
+    pub fn new(input: R) -> Self {
+        Parser {
+            tokens: Lexer::new(input),
+            in_graph: false,
+            pending: VecDeque::new(),
+            directed: false,
+            multigraph: false,
+            started: false,
+        }
+    }
+
+    fn graph_item(&mut self, pair: Pair) -> Result<Item, ConvertError> {
+        // Item of a key of the graph being read, other than a node or an edge
+        let Pair {
+            name,
+            value,
+            position,
+        } = pair;
+        if name == "directed"
+            && let Some(directed) = value.int()
+        {
+            let directed = directed != 0;
+            if self.started && directed != self.directed {
+                return Err(ConvertError::Gml {
+                    line: position.0,
+                    column: position.1,
+                    message: "The graph direction can't change after its first node or edge"
+                        .to_string(),
+                });
+            }
+            self.directed = directed;
+            return Ok(Item::Directed(directed));
+        }
+        if name == "multigraph" {
+            self.multigraph = value.int() == Some(1);
+        }
+        Ok(Item::GraphAttr { name, value })
+    }
+
+    pub fn next_item(&mut self) -> Result<Option<Item>, ConvertError> {
+        loop {
+            if self.in_graph
+                && let Some(pair) = self.pending.pop_front()
+            {
+                return self.graph_item(pair).map(Some);
+            }
+            let name = match self.tokens.next().transpose()? {
+                Some(Token::Key(name)) => name,
+                Some(Token::ListEnd) if self.in_graph => {
+                    self.in_graph = false;
+                    return Ok(Some(Item::GraphEnd));
+                }
+                // Unmatched closing brackets outside of a graph are left out
+                Some(Token::ListEnd) => continue,
+                Some(_) => return Err(self.tokens.error("Expected a key".to_string())),
+                None if self.in_graph => {
+                    self.in_graph = false;
+                    return Ok(Some(Item::GraphEnd));
+                }
+                // Keys after the last graph have no graph to go to
+                None => return Ok(None),
+            };
+            let position = self.tokens.position();
+            if !self.in_graph && name == "graph" {
+                if self.tokens.next().transpose()? != Some(Token::ListStart) {
+                    return Err(self.tokens.error("Expected a graph list".to_string()));
+                }
+                self.in_graph = true;
+                self.directed = false;
+                self.multigraph = false;
+                self.started = false;
+                return Ok(Some(Item::GraphStart));
+            }
+            if self.in_graph && (name == "node" || name == "edge") {
+                // Nodes and edges are read as a whole, as they are small
+                let pairs = match self.tokens.next().transpose()? {
+                    Some(Token::ListStart) => read_list(&mut self.tokens)?,
+                    Some(token @ (Token::Int(_) | Token::Real(_) | Token::Str(_))) => {
+                        // A scalar node or edge key is a graph attribute
+                        let pair = Pair {
+                            name,
+                            value: Value::Scalar(token),
+                            position: self.tokens.position(),
+                        };
+                        return self.graph_item(pair).map(Some);
+                    }
+                    _ => {
+                        return Err(self
+                            .tokens
+                            .error(format!("Missing value for key {:?}", name)));
+                    }
+                };
+                self.started = true;
+                return Ok(Some(if name == "node" {
+                    Item::Node(read_node(pairs, position))
+                } else {
+                    Item::Edge(read_edge(pairs, position, self.multigraph))
+                }));
+            }
+            let value = read_value(&mut self.tokens, &name)?;
+            let pair = Pair {
+                name,
+                value,
+                position: self.tokens.position(),
+            };
+            if !self.in_graph {
+                self.pending.push_back(pair);
+                continue;
+            }
+            return self.graph_item(pair).map(Some);
+        }
+    }
+}
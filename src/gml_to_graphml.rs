@@ -1,48 +1,58 @@
 // GML to graphml converter
 
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::fs::File;
-use std::io::{BufRead, BufReader, BufWriter, Write, copy};
+use std::io::{self, BufRead, BufReader, BufWriter, Seek, SeekFrom, Write, copy};
 
-use itertools::Itertools;
 use quick_xml::Writer;
-use quick_xml::events::{BytesDecl, BytesEnd, BytesStart, BytesText, Event};
-use regex::Regex;
-use serde_json::{Map, Number, Value, json};
-use uuid::Uuid;
+use quick_xml::events::{BytesEnd, BytesStart, BytesText, Event};
 
 use std::path::Path;
-use std::str::FromStr;
+
+use crate::attr_types::{GraphmlAttributeTypes, parse_json};
+use crate::error::ConvertError;
+use crate::gml_lexer::Token;
+use crate::gml_parser::{End, Item, Parser, Value, json_list};
+use crate::integrity::Integrity;
+use crate::multi_graph::{MultipleGraphs, graph_prefix};
+use crate::node_ids::{GraphmlIds, ORIGINAL_ID_ATTRIBUTE};
+use crate::stats::ConversionStats;
+use crate::summary::GraphSummary;
+use crate::xml::write_graphml_header;
 
 use std::hash::Hash;
-use std::{env, fs};
 
 #[derive(Debug, Clone)]
 struct Node {
     id: String,
+    gml_id: Option<i64>,
+    // Original GraphML id of the node, read from the ORIGINAL_ID_ATTRIBUTE
+    name: Option<String>,
+    // GML id of the group node containing the node, and whether the node is a group (isGroup 1)
+    gid: Option<i64>,
+    is_group: bool,
     data: BTreeMap<String, Vec<String>>,
 }
 
 #[derive(Debug, Clone)]
 struct Edge {
+    id: Option<String>,
+    // Direction of the edge, when it differs from the graph edge default (ie. in a mixed graph)
+    directed: Option<bool>,
     source: String,
     target: String,
+    source_gml_id: Option<i64>,
+    target_gml_id: Option<i64>,
     data: BTreeMap<String, Vec<String>>,
 }
 
 #[derive(Debug, Clone)]
 struct GraphInfo {
     directed: Option<bool>,
+    multigraph: bool,
     data: BTreeMap<String, Vec<String>>,
-}
-
-#[derive(Debug, Clone, PartialEq, Eq, Hash, Copy)]
-enum CurrentState {
-    Graph,
-    Node,
-    NodeObject,
-    Edge,
-    EdgeObject,
+    // Data read after the first node or edge, once the graph element is written, written at the end of the graph
+    late_data: BTreeMap<String, Vec<String>>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
@@ -62,22 +72,20 @@ impl GraphmlElems {
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
-enum GraphmlAttributeTypes {
-    Int,
-    Float,
-    String,
-}
-
-impl GraphmlAttributeTypes {
-    fn value(&self) -> &str {
-        match *self {
-            GraphmlAttributeTypes::Int => "int",
-            GraphmlAttributeTypes::Float => "float",
-            GraphmlAttributeTypes::String => "string",
-            // Add double if necessary
-            //GraphmlAttributeTypes::Double => "double",
-        }
+fn value_type(value: &str) -> GraphmlAttributeTypes {
+    // Infer the type of a single gml value from its range. GML has no booleans, so the boolean type is never
+    // inferred, and lists (serialized as json) are strings.
+    if value.starts_with('\"') && value.ends_with('\"') {
+        GraphmlAttributeTypes::String
+    }
+    // Otherwise check to see if it's a number
+    else if let Ok(number) = value.parse::<i64>() {
+        GraphmlAttributeTypes::of_int(number)
+    } else if let Ok(number) = value.parse::<f64>() {
+        GraphmlAttributeTypes::of_float(number)
+    } else {
+        // everything else is a string type
+        GraphmlAttributeTypes::String
     }
 }
 
@@ -91,26 +99,26 @@ struct KeyAttributes {
 struct KeyValues {
     id: String,
     attr_type: GraphmlAttributeTypes,
+    // Value written in the key, which the data values equal to it are omitted for
+    default: Option<String>,
 }
 
-const LINE_BREAK: &[u8] = b"\n";
-
-fn add_header(writer: &mut Writer<BufWriter<File>>) {
-    // Write the Graphml header
-
-    // Add the xml declaration
-    let header = BytesDecl::new(b"1.0", Some(b"UTF-8"), Some(b"yes"));
-    writer.write_event(Event::Decl(header)).ok();
-
-    // Open the graphml node and add the boilerplate attributes
-    let mut elem = BytesStart::borrowed_name(b"graphml");
-    elem.push_attribute(("xmlns", "http://graphml.graphdrawing.org/xmlns"));
-    elem.push_attribute(("xmlns:xsi", "http://www.w3.org/2001/XMLSchema-instance"));
-    elem.push_attribute(("xsi:schemaLocation", "http://graphml.graphdrawing.org/xmlns http://graphml.graphdrawing.org/xmlns/1.0/graphml.xsd"));
-    writer.write_event(Event::Start(elem)).ok();
+// Number of elements with each value, by key id
+type ValueCounts = HashMap<String, HashMap<String, usize>>;
+
+#[derive(Debug, Clone, Default)]
+pub struct Options {
+    // Write the most common value of the node and edge keys as their default, when every element has a value
+    pub write_defaults: bool,
+    // Write the graphs of a gml stream as several graph elements or merge them
+    pub graphs: MultipleGraphs,
+    // Fail on referential integrity problems (ie. edges to undeclared nodes) instead of warning about them
+    pub strict: bool,
 }
 
-fn add_graph_info(writer: &mut Writer<BufWriter<&File>>, graph: &GraphInfo) {
+const LINE_BREAK: &[u8] = b"\n";
+
+fn add_graph_info<W: Write>(writer: &mut Writer<W>, graph: &GraphInfo) -> Result<(), ConvertError> {
     // Add the graph node: <data key="d0">Test gml file</data>
     let name = b"graph";
     let mut elem = BytesStart::borrowed_name(name);
@@ -124,86 +132,169 @@ fn add_graph_info(writer: &mut Writer<BufWriter<&File>>, graph: &GraphInfo) {
         }
         _ => {}
     }
-    add_elem_with_keys(writer, &graph.data, elem, name, false);
+    if graph.multigraph {
+        // Hint that the edge ids are the parallel edge keys, not a canonical numbering
+        elem.push_attribute(("parse.edgeids", "free"));
+    }
+    add_elem_with_keys(writer, &graph.data, elem, name, false, &HashMap::new())
 }
 
-fn add_footer(writer: &mut Writer<BufWriter<&File>>) {
-    // Close the graph and graphml xml nodes
-    writer
-        .write_event(Event::End(BytesEnd::borrowed(b"graph")))
-        .ok();
-    writer
-        .write_event(Event::End(BytesEnd::borrowed(b"graphml")))
-        .ok();
+fn add_nested_graph_start<W: Write>(
+    writer: &mut Writer<W>,
+    node: &Node,
+    graph: &GraphInfo,
+    defaults: &HashMap<String, String>,
+) -> Result<(), ConvertError> {
+    // Open a group node and its nested graph: <node id="n1"><graph id="n1:" edgedefault="directed">
+    let mut node_elem = BytesStart::borrowed_name(b"node");
+    node_elem.push_attribute(("id", node.id.as_str()));
+    add_elem_with_keys(writer, &node.data, node_elem, b"node", false, defaults)?;
+    let mut graph_elem = BytesStart::borrowed_name(b"graph");
+    graph_elem.push_attribute(("id", format!("{}:", node.id).as_str()));
+    match graph.directed {
+        Some(true) => graph_elem.push_attribute(("edgedefault", "directed")),
+        Some(false) => graph_elem.push_attribute(("edgedefault", "undirected")),
+        None => {}
+    }
+    writer.write_event(Event::Start(graph_elem))?;
+    Ok(())
 }
 
-fn add_node(writer: &mut Writer<BufWriter<&File>>, node: &Node) {
+fn add_nested_graph_end<W: Write>(writer: &mut Writer<W>) -> Result<(), ConvertError> {
+    // Close the nested graph and its group node
+    writer.write_event(Event::End(BytesEnd::borrowed(b"graph")))?;
+    writer.write_event(Event::End(BytesEnd::borrowed(b"node")))?;
+    Ok(())
+}
+
+fn add_graph_end<W: Write>(
+    writer: &mut Writer<W>,
+    groups: Groups,
+    keys: &mut BTreeMap<KeyAttributes, KeyValues>,
+    graph: &GraphInfo,
+    defaults: &HashMap<String, String>,
+) -> Result<(), ConvertError> {
+    // Add the nested graphs kept until the end of the graph, and close the graph xml node
+    add_groups(writer, groups, keys, graph, defaults)?;
+    writer.write_event(Event::End(BytesEnd::borrowed(b"graph")))?;
+    Ok(())
+}
+
+fn add_footer<W: Write>(writer: &mut Writer<W>) -> Result<(), ConvertError> {
+    // Close the graphml xml node
+    writer.write_event(Event::End(BytesEnd::borrowed(b"graphml")))?;
+    Ok(())
+}
+
+fn add_node<W: Write>(
+    writer: &mut Writer<W>,
+    node: &Node,
+    defaults: &HashMap<String, String>,
+) -> Result<(), ConvertError> {
     // Add a new xml node: <node id="1"><data key="d0">1.0</data></node>
     let name = b"node";
     let mut node_elem = BytesStart::borrowed_name(name);
     node_elem.push_attribute(("id", node.id.as_str()));
-    add_elem_with_keys(writer, &node.data, node_elem, name, true);
+    add_elem_with_keys(writer, &node.data, node_elem, name, true, defaults)
 }
 
-fn add_edge(writer: &mut Writer<BufWriter<&File>>, edge: &Edge) {
-    // Add a new xml edge: <edge source="1" target="2"><data key="d1">1.1</data></edge>
+fn add_edge<W: Write>(
+    writer: &mut Writer<W>,
+    edge: &Edge,
+    defaults: &HashMap<String, String>,
+) -> Result<(), ConvertError> {
+    // Add a new xml edge: <edge id="e1" source="1" target="2"><data key="d1">1.1</data></edge>
     let name = b"edge";
     let mut edge_elem = BytesStart::borrowed_name(name);
+    if let Some(id) = &edge.id {
+        edge_elem.push_attribute(("id", id.as_str()));
+    }
     edge_elem.push_attribute(("source", edge.source.as_str()));
     edge_elem.push_attribute(("target", edge.target.as_str()));
-    add_elem_with_keys(writer, &edge.data, edge_elem, name, true);
+    if let Some(directed) = edge.directed {
+        edge_elem.push_attribute(("directed", if directed { "true" } else { "false" }));
+    }
+    add_elem_with_keys(writer, &edge.data, edge_elem, name, true, defaults)
 }
 
-fn add_elem_with_keys(
-    writer: &mut Writer<BufWriter<&File>>,
+fn add_elem_with_keys<W: Write>(
+    writer: &mut Writer<W>,
     elem_data: &BTreeMap<String, Vec<String>>,
     elem: BytesStart,
     elem_name: &[u8],
     close: bool,
-) {
+    defaults: &HashMap<String, String>,
+) -> Result<(), ConvertError> {
     // Add a xml element with data keys. Note close is false for graph as it is closed at the end
-    writer.write_event(Event::Start(elem)).ok();
+    writer.write_event(Event::Start(elem))?;
+    add_data(writer, elem_data, defaults)?;
+    if close {
+        writer.write_event(Event::End(BytesEnd::borrowed(elem_name)))?;
+    }
+    Ok(())
+}
+
+fn add_data<W: Write>(
+    writer: &mut Writer<W>,
+    elem_data: &BTreeMap<String, Vec<String>>,
+    defaults: &HashMap<String, String>,
+) -> Result<(), ConvertError> {
+    // Add the data of an element: <data key="d0">1.0</data>
+    // Values equal to the default of their key are omitted
     for (key, value) in elem_data {
+        let formatted_value = format_value(value);
+        if defaults.get(key) == Some(&formatted_value) {
+            continue;
+        }
         let mut data = BytesStart::borrowed_name(b"data");
         data.push_attribute(("key", key.as_str()));
-        writer.write_event(Event::Start(data)).ok();
-
-        // Generate the text element from the value.
-        // Note: use from_plain_str instead of from_escaped_str to escape double quotes from json values
-
-        // Make a list from the value if it has a size > 1
-        let formatted_value = {
-            if value.len() > 1 {
-                format!("[{}]", value.join(","))
-            } else {
-                value.first().expect("").to_owned()
-            }
-        };
+        writer.write_event(Event::Start(data))?;
+        writer.write_event(Event::Text(value_text(&formatted_value)))?;
+        writer.write_event(Event::End(BytesEnd::borrowed(b"data")))?;
+    }
+    Ok(())
+}
 
-        let text = {
-            if formatted_value.starts_with('\"') || formatted_value.ends_with('\"') {
-                // Remove outside quotes if there are any
-                BytesText::from_plain_str(&formatted_value[1..formatted_value.len() - 1])
-            } else {
-                BytesText::from_plain_str(formatted_value.as_str())
-            }
-        };
+fn json_value(raw: &str) -> serde_json::Value {
+    // Get the json value of a raw value, strings are between quotes and lists are serialized as json
+    match raw
+        .strip_prefix('"')
+        .and_then(|value| value.strip_suffix('"'))
+    {
+        Some(string) => serde_json::Value::from(string),
+        None => match parse_json(raw) {
+            Ok(Some(json)) => json,
+            _ => serde_json::from_str(raw).unwrap_or_else(|_| serde_json::Value::from(raw)),
+        },
+    }
+}
 
-        writer.write_event(Event::Text(text)).ok();
-        writer
-            .write_event(Event::End(BytesEnd::borrowed(b"data")))
-            .ok();
+fn format_value(value: &[String]) -> String {
+    // Make a json list from the value if it has a size > 1
+    if value.len() > 1 {
+        serde_json::Value::Array(value.iter().map(|raw| json_value(raw)).collect()).to_string()
+    } else {
+        value.first().expect("").to_owned()
     }
-    if close {
-        writer
-            .write_event(Event::End(BytesEnd::borrowed(elem_name)))
-            .ok();
+}
+
+fn value_text(formatted_value: &str) -> BytesText<'_> {
+    // Generate the text element from the value.
+    // Note: use from_plain_str instead of from_escaped_str to escape double quotes from json values
+    if formatted_value.starts_with('\"') || formatted_value.ends_with('\"') {
+        // Remove outside quotes if there are any
+        BytesText::from_plain_str(&formatted_value[1..formatted_value.len() - 1])
+    } else {
+        BytesText::from_plain_str(formatted_value)
     }
 }
 
-fn add_keys(writer: &mut Writer<BufWriter<File>>, keys: &BTreeMap<KeyAttributes, KeyValues>) {
+fn add_keys<W: Write>(
+    writer: &mut Writer<W>,
+    keys: &BTreeMap<KeyAttributes, KeyValues>,
+) -> Result<(), ConvertError> {
     // Write the list of xml keys
-    // <key id="d10" for="edge" attr.name="list" attr.type="string" />
+    // <key id="d10" for="edge" attr.name="weight" attr.type="double" />
 
     // Sort the keys by id values
     let mut v = Vec::from_iter(keys);
@@ -219,39 +310,42 @@ fn add_keys(writer: &mut Writer<BufWriter<File>>, keys: &BTreeMap<KeyAttributes,
         elem.push_attribute(("id", value.id.as_str()));
         elem.push_attribute(("for", key.for_elem.value()));
         elem.push_attribute(("attr.name", key.attr_name.as_str()));
-        elem.push_attribute(("attr.type", value.attr_type.value()));
-        writer.write_event(Event::Empty(elem)).ok();
+        if let Some(attr_type) = value.attr_type.value() {
+            elem.push_attribute(("attr.type", attr_type));
+        }
+        match &value.default {
+            Some(default) => {
+                // <key ...><default>0</default></key>
+                writer.write_event(Event::Start(elem))?;
+                writer.write_event(Event::Start(BytesStart::borrowed_name(b"default")))?;
+                writer.write_event(Event::Text(value_text(default)))?;
+                writer.write_event(Event::End(BytesEnd::borrowed(b"default")))?;
+                writer.write_event(Event::End(BytesEnd::borrowed(b"key")))?;
+            }
+            None => writer.write_event(Event::Empty(elem))?,
+        }
     }
+    Ok(())
 }
 
 fn get_or_add_key_id(
     keys: &mut BTreeMap<KeyAttributes, KeyValues>,
     key_attr: &KeyAttributes,
-    value: &str,
+    attribute_type: GraphmlAttributeTypes,
 ) -> String {
     // Get the key id if it already exists otherwise create a new key id
-    match keys.get(key_attr) {
-        Some(values) => values.id.to_string(),
+    // The key type is widened to hold the new value, as the key block is only written once every value is seen
+    match keys.get_mut(key_attr) {
+        Some(values) => {
+            values.attr_type = values.attr_type.widen(attribute_type);
+            values.id.to_string()
+        }
         None => {
-            let attribute_type = {
-                // Note: it is assumed that future values of same key will be same type!
-                if value.starts_with('\"') && value.ends_with('\"') {
-                    GraphmlAttributeTypes::String
-                }
-                // Otherwise check to see if it's a number
-                else if value.parse::<u32>().is_ok() {
-                    GraphmlAttributeTypes::Int
-                } else if value.parse::<f64>().is_ok() {
-                    GraphmlAttributeTypes::Float
-                } else {
-                    // everything else is a string type
-                    GraphmlAttributeTypes::String
-                }
-            };
             let id = format!("d{}", keys.len());
             let values = KeyValues {
                 id: id.clone(),
                 attr_type: attribute_type,
+                default: None,
             };
             keys.insert(key_attr.clone(), values);
             id
@@ -272,282 +366,655 @@ fn update_element(
         attr_name: name.to_string(),
         for_elem: element,
     };
-    let key_id = get_or_add_key_id(keys, &key_attr, value);
+    let key_id = get_or_add_key_id(keys, &key_attr, value_type(value));
 
     // Get the original data or an empty vec if no data exists for the element
-    let elem_data = elem_map.entry(key_id).or_insert_with(Vec::new);
+    let elem_data = elem_map.entry(key_id).or_default();
 
     // Check if the value has been seen for the same node which would mean a list type instead
     // Update the key attribute value to string as it will contain a serialized list and the
     // previous type could be int or float
-    if elem_data.len() > 1 {
-        let elem = keys
-            .get_mut(&key_attr)
-            .expect("Error retrieving previous key!");
-        (*elem).attr_type = GraphmlAttributeTypes::String;
+    if !elem_data.is_empty() {
+        get_or_add_key_id(keys, &key_attr, GraphmlAttributeTypes::String);
     }
     // Add the value to the global node hashmap
     elem_data.push(value.to_string());
 }
 
-pub fn export_to_graphml(input_gml: &Path, output_path: &Path) {
-    // Convert the import file to graphml using a bufreader and xml bufwriter
-    // Write to a temp file and copy the file with header information added after at the output path destination
-    // Todo: check if instantiating a bufwriter with a bigger capacity makes it faster for large files
-    let tmp_dir = env::temp_dir();
-    let tmp_name = Uuid::new_v4().to_string();
-    let tmp_path = tmp_dir.join(tmp_name);
-    let tmp_file = File::create(&tmp_path).expect("Unable to create file");
-    let mut output_file = File::create(output_path).expect("Unable to create file");
+fn data_items(data: &BTreeMap<String, Vec<String>>) -> impl Iterator<Item = (&str, &str)> {
+    // Every value of an element data, with its key id
+    data.iter().flat_map(|(id, values)| {
+        values
+            .iter()
+            .map(move |value| (id.as_str(), value.as_str()))
+    })
+}
+
+fn count_values(counts: &mut ValueCounts, elem_data: &BTreeMap<String, Vec<String>>) {
+    // Count the value of each key of a completed element
+    for (key, value) in elem_data {
+        *counts
+            .entry(key.to_string())
+            .or_default()
+            .entry(format_value(value))
+            .or_default() += 1;
+    }
+}
+
+fn set_defaults(
+    keys: &mut BTreeMap<KeyAttributes, KeyValues>,
+    counts: &ValueCounts,
+    stats: &ConversionStats,
+) {
+    // Use the most common value of each node and edge key as its default.
+    // A default applies to every element without a value, so it is only set when every element has a value.
+    for (key, values) in keys.iter_mut() {
+        let elements = match key.for_elem {
+            GraphmlElems::Node => stats.nodes,
+            GraphmlElems::Edge => stats.edges,
+            GraphmlElems::Graph => continue,
+        };
+        let Some(key_counts) = counts.get(&values.id) else {
+            continue;
+        };
+        if key_counts.values().sum::<usize>() != elements {
+            continue;
+        }
+        // Break ties on the value so that the output doesn't depend on the hashmap order
+        values.default = key_counts
+            .iter()
+            .filter(|&(_, &count)| count > 1)
+            .max_by(|(a, a_count), (b, b_count)| a_count.cmp(b_count).then(b.cmp(a)))
+            .map(|(value, _)| value.to_string());
+    }
+}
 
-    let writer = BufWriter::new(&tmp_file);
-    let mut xml_writer = Writer::new_with_indent(writer, b' ', 2);
+// Nodes of the groups (nodes with a gid or isGroup 1) and the edges inside a group, which are kept until the
+// end of the graph to be written as nested graphs, as GML nodes can be in any order
+#[derive(Debug, Default)]
+struct Groups {
+    nodes: Vec<Node>,
+    // Group GML id of the nodes kept, by node GML id
+    parents: HashMap<i64, i64>,
+    // Edges between nodes of the same group, by group GML id
+    edges: HashMap<i64, Vec<Edge>>,
+}
 
-    let input_file = File::open(input_gml).expect("Issue reading file at path");
-    let buf_reader = BufReader::new(input_file);
+impl Groups {
+    fn edge_group(&self, edge: &Edge) -> Option<i64> {
+        // Get the group of an edge, when both its nodes are in the same group
+        let source_group = self.parents.get(&edge.source_gml_id?)?;
+        let target_group = self.parents.get(&edge.target_gml_id?)?;
+        (source_group == target_group).then_some(*source_group)
+    }
+}
 
-    // Current node info - Todo initialize empty
-    let mut node = Node {
-        id: "".to_string(),
-        data: Default::default(),
-    };
+fn add_group_node<W: Write>(
+    writer: &mut Writer<W>,
+    index: usize,
+    groups: &mut Groups,
+    children: &HashMap<i64, Vec<usize>>,
+    written: &mut [bool],
+    graph: &GraphInfo,
+    defaults: &HashMap<String, String>,
+) -> Result<(), ConvertError> {
+    // Add a node kept for the groups, with its nested graph when it is a group
+    written[index] = true;
+    let node = &groups.nodes[index];
+    let gml_id = node.gml_id.unwrap_or_default();
+    // Children already written are in a gid cycle, and stay where they are
+    let group_children: Vec<usize> = children
+        .get(&gml_id)
+        .map(Vec::as_slice)
+        .unwrap_or_default()
+        .iter()
+        .copied()
+        .filter(|&child| !written[child])
+        .collect();
+    if !node.is_group && group_children.is_empty() && !groups.edges.contains_key(&gml_id) {
+        return add_node(writer, node, defaults);
+    }
+    add_nested_graph_start(writer, node, graph, defaults)?;
+    for child in group_children {
+        if !written[child] {
+            add_group_node(writer, child, groups, children, written, graph, defaults)?;
+        }
+    }
+    for edge in groups.edges.remove(&gml_id).unwrap_or_default() {
+        add_edge(writer, &edge, defaults)?;
+    }
+    add_nested_graph_end(writer)
+}
 
-    // Current edge info
-    let mut edge = Edge {
-        source: String::new(),
-        target: String::new(),
-        data: Default::default(),
+fn add_groups<W: Write>(
+    writer: &mut Writer<W>,
+    mut groups: Groups,
+    keys: &mut BTreeMap<KeyAttributes, KeyValues>,
+    graph: &GraphInfo,
+    defaults: &HashMap<String, String>,
+) -> Result<(), ConvertError> {
+    // Add the nodes kept for the groups as nested graphs, from the nodes in no group
+    let group_ids: HashSet<i64> = groups.nodes.iter().filter_map(|node| node.gml_id).collect();
+    let mut children: HashMap<i64, Vec<usize>> = HashMap::new();
+    let mut roots = Vec::new();
+    for (index, node) in groups.nodes.iter().enumerate() {
+        match node.gid {
+            Some(gid) if group_ids.contains(&gid) && node.gml_id != Some(gid) => {
+                children.entry(gid).or_default().push(index)
+            }
+            _ => roots.push(index),
+        }
+    }
+    let mut written = vec![false; groups.nodes.len()];
+    for index in roots {
+        if groups.nodes[index].gid.is_some() {
+            // The group doesn't exist, keep the gid as node data
+            set_unknown_gid(&mut groups.nodes[index], keys);
+        }
+        add_group_node(
+            writer,
+            index,
+            &mut groups,
+            &children,
+            &mut written,
+            graph,
+            defaults,
+        )?;
+    }
+    // Nodes left are in groups containing each other (ie. a gid cycle), start from the first one
+    while let Some(index) = written.iter().position(|written| !written) {
+        set_unknown_gid(&mut groups.nodes[index], keys);
+        add_group_node(
+            writer,
+            index,
+            &mut groups,
+            &children,
+            &mut written,
+            graph,
+            defaults,
+        )?;
+    }
+    // Edges of groups that don't exist are added to the graph
+    for edges in groups.edges.into_values() {
+        for edge in edges {
+            add_edge(writer, &edge, defaults)?;
+        }
+    }
+    Ok(())
+}
+
+fn set_unknown_gid(node: &mut Node, keys: &mut BTreeMap<KeyAttributes, KeyValues>) {
+    // Keep the gid of a node that can't be nested in its group as node data
+    if let Some(gid) = node.gid.take() {
+        update_element(
+            &mut node.data,
+            keys,
+            &gid.to_string(),
+            GraphmlElems::Node,
+            "gid",
+        );
+    }
+}
+
+fn raw_value(value: &Value) -> String {
+    // Get a value as written in the gml file, strings are kept between quotes to mark their type. Lists, and
+    // strings holding a json list, are serialized as json.
+    match value {
+        Value::Scalar(Token::Int(value) | Token::Real(value)) => value.to_string(),
+        Value::Scalar(Token::Str(value)) if json_list(value).is_none() => format!("\"{}\"", value),
+        value => value.to_json().to_string(),
+    }
+}
+
+fn set_node_id(
+    node: &mut Node,
+    ids: &mut GraphmlIds,
+    keys: &mut BTreeMap<KeyAttributes, KeyValues>,
+) -> bool {
+    // Set the GraphML id of a completed node, from its original id attribute if it has one or from its GML id.
+    // Returns true when the name was left out because an edge already used the default id of the node.
+    let gml_id = node.gml_id.take();
+    let mut named_late = false;
+    if let Some(name) = node.name.take()
+        && !gml_id.is_some_and(|gml_id| ids.insert(gml_id, &name))
+    {
+        // The name can't be used as an id (ie. used by another node), keep it as data instead
+        named_late = gml_id.is_some_and(|gml_id| ids.is_referenced(gml_id));
+        let value = format!("\"{}\"", name);
+        update_element(
+            &mut node.data,
+            keys,
+            &value,
+            GraphmlElems::Node,
+            ORIGINAL_ID_ATTRIBUTE,
+        );
+    }
+    if let Some(gml_id) = gml_id {
+        node.id = ids.get(gml_id);
+    }
+    named_late
+}
+
+fn edge_end_id(end: &End, ids: &mut GraphmlIds, single_pass: bool) -> String {
+    // Get the GraphML id of an edge source or target.
+    // In a single pass, the nodes named after their edges keep their default id so that the edges still match
+    match end.gml_id {
+        Some(gml_id) if single_pass => ids.get_referenced(gml_id),
+        Some(gml_id) => ids.get(gml_id),
+        None => end.text.to_string(),
+    }
+}
+
+fn location((line, column): (usize, usize)) -> String {
+    format!("line {}, column {}", line, column)
+}
+
+fn write_graph_body<R: BufRead, W: Write>(
+    buf_reader: R,
+    output: W,
+    keys: &mut BTreeMap<KeyAttributes, KeyValues>,
+    mut counts: Option<&mut ValueCounts>,
+    options: &Options,
+    mut summary: Option<&mut GraphSummary>,
+    mut names: Option<&mut Vec<GraphmlIds>>,
+) -> Result<ConversionStats, ConvertError> {
+    // Convert the gml graphs to graphml graph elements, adding the keys used by their data to the given keys
+    // The node and edge values are counted when counts are given, to find the key defaults, and summarized
+    // when a summary is given. The node names of each graph are collected in names when it is empty, and used
+    // for the edges before their nodes when it was filled by a previous pass.
+    let mut xml_writer = Writer::new_with_indent(output, b' ', 2);
+    let mut stats = ConversionStats::default();
+    let graphs = options.graphs;
+    let mut integrity = Integrity::new(options.strict, "");
+
+    // Defaults of the given keys, by key id
+    let defaults: HashMap<String, String> = keys
+        .values()
+        .filter_map(|values| Some((values.id.clone(), values.default.clone()?)))
+        .collect();
+
+    // GraphML ids restored from the original id attribute of the nodes
+    let known_names = names.as_ref().is_some_and(|names| !names.is_empty());
+    let mut ids = match names.as_deref_mut() {
+        Some(names) if known_names => std::mem::take(&mut names[0]),
+        _ => GraphmlIds::new(),
     };
 
+    // Nodes and edges written as nested graphs at the end
+    let mut groups = Groups::default();
+
     // Current graph info
     let mut graph = GraphInfo {
         directed: None,
+        multigraph: false,
         data: Default::default(),
+        late_data: Default::default(),
     };
 
     let mut graph_info_added = false;
-
-    // Key info
-    let mut keys = BTreeMap::<KeyAttributes, KeyValues>::new();
-
-    // Current dict info (inside an edge or a node)
-    let mut dict_key_value = String::new(); // key value name for the dict;
-    let mut inner_dict: Map<String, Value> = Map::new();
-    let mut list_item_staging = String::new(); // staging item for possible lists
-
-    let mut state = CurrentState::Graph;
-
-    let re_node_start = Regex::new(r"node \[").unwrap();
-    let re_edge_start = Regex::new(r"edge \[").unwrap();
-    let re_graph_start = Regex::new(r"graph \[").unwrap();
-    let re_closing_bracket = Regex::new(r"^\]$").unwrap();
-
-    for line in buf_reader.lines() {
-        let line = line.expect("Unable to read line");
-        if line.trim().starts_with('#') {
-            // skip comments - Note, comments could be added directly?
-            continue;
+    // Number of graphs started
+    let mut graph_index = 0;
+    // Direction of a merged graph, given to its edges when it differs from the first graph
+    let mut merged_directed = None;
+
+    let mut parser = Parser::new(buf_reader);
+    while let Some(item) = parser.next_item()? {
+        // The attributes of the merged graphs are left out, only the first graph ones are kept.
+        // Their direction is kept on their edges instead.
+        let merged = graphs == MultipleGraphs::Merge && graph_index > 1;
+        if matches!(item, Item::Node(_) | Item::Edge(_)) && !graph_info_added {
+            // Add graph data when entering the first node or edge
+            add_graph_info(&mut xml_writer, &graph)?;
+            graph_info_added = true;
         }
-
-        match line.trim() {
-            line if re_graph_start.is_match(line) => {
-                // entering graph - least likely to happen so it can be last
-                state = CurrentState::Graph;
-            }
-            line if re_node_start.is_match(line) => {
-                // entering node data
-                if !graph_info_added {
-                    // Add graph data when entering the first node
-                    add_graph_info(&mut xml_writer, &graph);
-                    graph_info_added = true;
+        match item {
+            Item::GraphStart => {
+                // entering graph, the next graphs get their own ids
+                if graph_index > 0 {
+                    let graph_ids = match names.as_deref_mut() {
+                        Some(names) if known_names => {
+                            names.get_mut(graph_index).map(std::mem::take)
+                        }
+                        _ => None,
+                    }
+                    .unwrap_or_else(|| GraphmlIds::with_prefix(graph_prefix(graph_index, None)));
+                    let graph_ids = std::mem::replace(&mut ids, graph_ids);
+                    if let Some(names) = names.as_deref_mut()
+                        && !known_names
+                    {
+                        names.push(graph_ids);
+                    }
+                    if graphs == MultipleGraphs::Separate {
+                        graph = GraphInfo {
+                            directed: None,
+                            multigraph: false,
+                            data: Default::default(),
+                            late_data: Default::default(),
+                        };
+                    }
                 }
-                state = CurrentState::Node;
+                graph_index += 1;
             }
-            line if re_edge_start.is_match(line) => {
-                // entering edge
-                state = CurrentState::Edge;
+            Item::Directed(directed) if merged => merged_directed = Some(directed),
+            Item::Directed(directed) => graph.directed = Some(directed),
+            Item::GraphAttr { .. } if merged => {}
+            Item::GraphAttr { name, value } => {
+                if name == "multigraph" {
+                    // Kept as graph data too, so that it comes back when converting to gml
+                    graph.multigraph = value.int() == Some(1);
+                }
+                // The attributes after the first node or edge are written at the end of the graph
+                let data = if graph_info_added {
+                    &mut graph.late_data
+                } else {
+                    &mut graph.data
+                };
+                update_element(data, keys, &raw_value(&value), GraphmlElems::Graph, &name);
             }
-            line if re_closing_bracket.is_match(line) && line.len() == 1 => {
-                // End previous open item (node, edge, graph, or in-items dict or list)
-                // if in [node, edge, graph] add write the data
-                // Note, only if the line contains a closing bracket and nothing else ie ("[]" would not work)
-                match state {
-                    CurrentState::Edge => {
-                        // Add edge when exiting an edge
-                        add_edge(&mut xml_writer, &edge);
-                        state = CurrentState::Graph;
-                        edge.data.clear();
-                    }
-                    CurrentState::Node => {
-                        // Add node and increment node id when exiting node
-                        add_node(&mut xml_writer, &node);
-                        state = CurrentState::Graph;
-                        node.data.clear();
-                    }
-                    CurrentState::Graph => continue, // graph completed, ignore
-                    CurrentState::NodeObject => {
-                        let serialized_value = json!(inner_dict).to_string();
-                        update_element(
+            Item::Node(parsed) => {
+                // Add node, or keep it for the end when it is in a group
+                let position = parsed.position;
+                let mut node = Node {
+                    id: parsed.id.unwrap_or_default(),
+                    gml_id: parsed.gml_id,
+                    name: parsed.name,
+                    gid: None,
+                    is_group: false,
+                    data: Default::default(),
+                };
+                for (name, value) in &parsed.attrs {
+                    match (name.as_str(), value.int()) {
+                        ("gid", Some(gid)) => node.gid = Some(gid),
+                        ("isGroup", Some(is_group)) => node.is_group = is_group == 1,
+                        _ => update_element(
                             &mut node.data,
-                            &mut keys,
-                            serialized_value.as_str(),
+                            keys,
+                            &raw_value(value),
                             GraphmlElems::Node,
-                            dict_key_value.as_str(),
-                        );
-                        state = CurrentState::Node;
-                        inner_dict.clear();
-                        list_item_staging.clear();
-                    }
-                    CurrentState::EdgeObject => {
-                        let serialized_value = json!(inner_dict).to_string();
-                        update_element(
-                            &mut edge.data,
-                            &mut keys,
-                            serialized_value.as_str(),
-                            GraphmlElems::Edge,
-                            dict_key_value.as_str(),
-                        );
-                        state = CurrentState::Edge;
-                        inner_dict.clear();
-                        list_item_staging.clear();
+                            name,
+                        ),
                     }
-                };
-            }
-            _ => {
-                // Parse a single data line to extract name and value
-                let (name, value) = line
-                    .trim()
-                    .splitn(2, char::is_whitespace)
-                    .collect_tuple()
-                    .expect("Error parsing data");
-
-                match state {
-                    CurrentState::Graph => {
-                        // Add graph attributes
-                        match name {
-                            "directed" => {
-                                graph.directed = Some(value == "1");
-                            }
-                            _ => {
-                                // Update the global graph object with the new value
-                                update_element(
-                                    &mut graph.data,
-                                    &mut keys,
-                                    value,
-                                    GraphmlElems::Graph,
-                                    name,
-                                );
-                            }
-                        };
+                }
+                let gml_id = node.gml_id;
+                match gml_id {
+                    Some(gml_id) => integrity.add_node(&gml_id.to_string(), || location(position)),
+                    None if node.id.is_empty() => {
+                        integrity.add_problem(format!("Node without id at {}", location(position)))
                     }
-                    CurrentState::Node => {
-                        if name == "id" {
-                            // add a default n in front of the id
-                            node.id = format!("n{}", value);
-                        } else if value.trim().ends_with('[') {
-                            // Start dict attribute
-                            state = CurrentState::NodeObject;
-                            dict_key_value = name.to_string();
-                        } else {
-                            // Update the global node object with the new value
-                            update_element(
-                                &mut node.data,
-                                &mut keys,
-                                value,
-                                GraphmlElems::Node,
-                                name,
-                            );
-                        }
+                    None => integrity.add_node(&node.id, || location(position)),
+                }
+                if set_node_id(&mut node, &mut ids, keys) {
+                    stats.warnings.push(format!(
+                        "Node {} at {} is named after an edge to it, its name is kept as data",
+                        node.id,
+                        location(position)
+                    ));
+                }
+                if let Some(counts) = counts.as_deref_mut() {
+                    count_values(counts, &node.data);
+                }
+                if let Some(summary) = summary.as_deref_mut() {
+                    summary.set_directed(graph.directed);
+                    summary.add_node(data_items(&node.data));
+                }
+                if node.gid.is_some() || node.is_group {
+                    if let (Some(gml_id), Some(gid)) = (gml_id, node.gid) {
+                        groups.parents.insert(gml_id, gid);
                     }
-                    CurrentState::Edge => {
-                        if name == "source" {
-                            // add a default n in front of the source to match the node id
-                            edge.source = format!("n{}", value);
-                        } else if name == "target" {
-                            // add a default n in front of the target to match the node id
-                            edge.target = format!("n{}", value);
-                        } else if value.trim().ends_with('[') {
-                            // Start dict attribute
-                            state = CurrentState::EdgeObject;
-                            dict_key_value = name.to_string();
-                        } else {
-                            // Update global edge object with the new value
-                            update_element(
-                                &mut edge.data,
-                                &mut keys,
-                                value,
-                                GraphmlElems::Edge,
-                                name,
-                            );
-                        }
+                    groups.nodes.push(Node { gml_id, ..node });
+                } else {
+                    add_node(&mut xml_writer, &node, &defaults)?;
+                }
+                stats.nodes += 1;
+            }
+            Item::Edge(parsed) => {
+                // Add edge, a multigraph edge without id is identified by its key
+                let mut edge = Edge {
+                    id: parsed.id.map(|id| ids.edge_id(&id)),
+                    directed: parsed.directed,
+                    source: String::new(),
+                    target: String::new(),
+                    source_gml_id: None,
+                    target_gml_id: None,
+                    data: Default::default(),
+                };
+                for (end, parsed_end) in [("source", &parsed.source), ("target", &parsed.target)] {
+                    let Some(parsed_end) = parsed_end else {
+                        integrity.add_problem(format!(
+                            "Edge without {} at {}",
+                            end,
+                            location(parsed.position)
+                        ));
+                        continue;
+                    };
+                    integrity.add_reference(&parsed_end.text, || location(parsed_end.position));
+                    let id = edge_end_id(parsed_end, &mut ids, names.is_none());
+                    if end == "source" {
+                        edge.source = id;
+                        edge.source_gml_id = parsed_end.gml_id;
+                    } else {
+                        edge.target = id;
+                        edge.target_gml_id = parsed_end.gml_id;
                     }
-                    CurrentState::NodeObject | CurrentState::EdgeObject => {
-                        if name == list_item_staging {
-                            // todo: use a dict here instead, and check if key in dict
-                            // when all the names are the same it's a list
-                            let value_object = {
-                                if value.parse::<f64>().is_ok() {
-                                    Value::Number(Number::from_str(value).expect("Error"))
-                                } else {
-                                    Value::from(value)
-                                }
-                            };
-                            // Parse the value into the correct format
-                            let key_value = inner_dict.get_mut(name).expect("Issue retrieving key");
-                            if key_value.is_array() {
-                                // if the previous value was a string, add the new value to a list
-                                let new_vect = key_value.as_array_mut().unwrap();
-                                new_vect.push(value_object);
-                                *key_value = json!(new_vect);
-                            } else {
-                                // otherwise create a list from the old value and append the new value
-                                *key_value = json!([key_value, value_object]);
-                            }
-                        } else {
-                            // add attributes to the dict currently being built
-                            list_item_staging = name.to_string();
-                            // check if it can be parsed as a number, otherwise use string
-                            match value.parse::<f64>() {
-                                Ok(_) => inner_dict.insert(
-                                    name.to_string(),
-                                    serde_json::Value::Number(
-                                        Number::from_str(value).expect("Error parsing string"),
-                                    ),
-                                ),
-                                Err(_) => inner_dict.insert(
-                                    name.to_string(),
-                                    Value::from_str(value).expect("Error parsing string"),
-                                ),
-                            };
-                        }
+                }
+                for (name, value) in &parsed.attrs {
+                    update_element(
+                        &mut edge.data,
+                        keys,
+                        &raw_value(value),
+                        GraphmlElems::Edge,
+                        name,
+                    );
+                }
+                if let Some(key) = parsed.key {
+                    match edge.id {
+                        Some(_) => update_element(
+                            &mut edge.data,
+                            keys,
+                            &raw_value(&key),
+                            GraphmlElems::Edge,
+                            "key",
+                        ),
+                        None => edge.id = Some(ids.edge_id(key.text().unwrap_or_default())),
                     }
                 }
+                // Only the directions that differ from the edge default are written
+                edge.directed = edge
+                    .directed
+                    .or(merged_directed)
+                    .filter(|&directed| directed != graph.directed.unwrap_or(false));
+                if let Some(counts) = counts.as_deref_mut() {
+                    count_values(counts, &edge.data);
+                }
+                if let Some(summary) = summary.as_deref_mut() {
+                    let directed = edge.directed.or(graph.directed).unwrap_or(false);
+                    summary.set_directed(graph.directed);
+                    summary.add_edge(&edge.source, &edge.target, directed, data_items(&edge.data));
+                }
+                match groups.edge_group(&edge) {
+                    Some(group) => groups.edges.entry(group).or_default().push(edge),
+                    None => add_edge(&mut xml_writer, &edge, &defaults)?,
+                }
+                stats.edges += 1;
+            }
+            Item::GraphEnd => {
+                // graph completed, add its nested graphs and close it unless the graphs are merged
+                if !graph_info_added {
+                    add_graph_info(&mut xml_writer, &graph)?;
+                    graph_info_added = true;
+                }
+                add_data(&mut xml_writer, &graph.late_data, &HashMap::new())?;
+                graph.late_data.clear();
+                let graph_groups = std::mem::take(&mut groups);
+                if graphs == MultipleGraphs::Separate {
+                    add_graph_end(&mut xml_writer, graph_groups, keys, &graph, &defaults)?;
+                    graph_info_added = false;
+                } else {
+                    add_groups(&mut xml_writer, graph_groups, keys, &graph, &defaults)?;
+                }
+                integrity.end_graph();
+                merged_directed = None;
             }
         }
     }
 
-    if !graph_info_added {
-        // Add graph data if not added (ie, when no nodes are present)
-        add_graph_info(&mut xml_writer, &graph);
+    if !graph_info_added && graph_index == 0 {
+        // Add graph data if not added (ie, when no graph is present)
+        add_graph_info(&mut xml_writer, &graph)?;
+        graph_info_added = true;
+    }
+    if graph_info_added {
+        // Close the last graph when it is still open (ie. merged graphs)
+        add_graph_end(&mut xml_writer, groups, keys, &graph, &defaults)?;
     }
 
     // Add remaining elements
-    add_footer(&mut xml_writer);
+    add_footer(&mut xml_writer)?;
     // Flush the remaining buffer - could also close the scope
-    xml_writer.inner().flush().ok();
-
-    // Write the  header, keys and graph info into another file and merge the result into the final file
-    let writer = BufWriter::new(output_file.try_clone().expect(""));
-    let mut new_xml_writer = Writer::new_with_indent(writer, b' ', 2);
-    add_header(&mut new_xml_writer);
-    add_keys(&mut new_xml_writer, &keys);
-    new_xml_writer.write(LINE_BREAK).ok();
-    new_xml_writer.inner().flush().ok();
-
-    // Merge the previous file
-    let mut src = File::open(&tmp_path).expect("Error opening source file");
-    copy(&mut src, &mut output_file).expect("Error copying file");
-
-    // Remove the temp file
-    fs::remove_file(&tmp_path).expect("Issue deleting temp file");
+    xml_writer.inner().flush()?;
+    integrity.finish(&mut stats)?;
+    if let Some(names) = names
+        && !known_names
+    {
+        names.push(ids);
+    }
+    Ok(stats)
+}
+
+fn write_header<W: Write>(
+    output: W,
+    keys: &BTreeMap<KeyAttributes, KeyValues>,
+) -> Result<W, ConvertError> {
+    // Write the xml declaration, graphml element and keys, and give back the output to write the graph
+    let mut xml_writer = Writer::new_with_indent(output, b' ', 2);
+    write_graphml_header(&mut xml_writer)?;
+    add_keys(&mut xml_writer, keys)?;
+    xml_writer.write(LINE_BREAK)?;
+    Ok(xml_writer.into_inner())
+}
+
+pub fn convert<R: BufRead, W: Write>(input: R, output: W) -> Result<ConversionStats, ConvertError> {
+    // Convert a gml input to graphml using a bufreader and xml bufwriter
+    convert_with_options(input, output, &Options::default())
+}
+
+pub fn convert_with_options<R: BufRead, W: Write>(
+    mut input: R,
+    output: W,
+    options: &Options,
+) -> Result<ConversionStats, ConvertError> {
+    // The keys are only known once the whole graph is read, so the graph is written to an anonymous temp file
+    // first and copied to the output after the header and keys.
+    // Todo: check if instantiating a bufwriter with a bigger capacity makes it faster for large files
+    let mut tmp_file = tempfile::tempfile()?;
+    if options.write_defaults {
+        // Values equal to the defaults are omitted, which needs two passes: buffer the input instead
+        copy(&mut input, &mut tmp_file)?;
+        tmp_file.seek(SeekFrom::Start(0))?;
+        return convert_seekable_with_options(BufReader::new(tmp_file), output, options);
+    }
+    let mut keys = BTreeMap::new();
+    let stats = write_graph_body(
+        input,
+        BufWriter::new(&tmp_file),
+        &mut keys,
+        None,
+        options,
+        None,
+        None,
+    )?;
+
+    // Write the header and keys, then merge the graph from the temp file
+    let mut writer = write_header(BufWriter::new(output), &keys)?;
+    tmp_file.seek(SeekFrom::Start(0))?;
+    copy(&mut tmp_file, &mut writer)?;
+    writer.flush()?;
+    Ok(stats)
+}
+
+pub fn convert_seekable<R: BufRead + Seek, W: Write>(
+    input: R,
+    output: W,
+) -> Result<ConversionStats, ConvertError> {
+    // Convert a seekable gml input to graphml without a temp file
+    convert_seekable_with_options(input, output, &Options::default())
+}
+
+pub fn convert_seekable_with_options<R: BufRead + Seek, W: Write>(
+    mut input: R,
+    output: W,
+    options: &Options,
+) -> Result<ConversionStats, ConvertError> {
+    // Read the input twice: a first pass only collects the keys (and their defaults) and the node names, then the
+    // header and keys are written followed by the graph, with the names of the nodes declared after their edges.
+    // Memory use stays bounded as only the keys and node names are kept between both passes (and the value counts
+    // for defaults).
+    let mut keys = BTreeMap::new();
+    let mut counts = ValueCounts::new();
+    let start = input.stream_position()?;
+    let counting = options.write_defaults.then_some(&mut counts);
+    let mut names = Vec::new();
+    let first_pass = write_graph_body(
+        &mut input,
+        io::sink(),
+        &mut keys,
+        counting,
+        options,
+        None,
+        Some(&mut names),
+    )?;
+    if options.write_defaults {
+        set_defaults(&mut keys, &counts, &first_pass);
+    }
+
+    input.seek(SeekFrom::Start(start))?;
+    let mut writer = write_header(BufWriter::new(output), &keys)?;
+    let stats = write_graph_body(
+        input,
+        &mut writer,
+        &mut keys,
+        None,
+        options,
+        None,
+        Some(&mut names),
+    )?;
+    writer.flush()?;
+    Ok(stats)
+}
+
+pub fn summarize<R: BufRead>(input: R) -> Result<GraphSummary, ConvertError> {
+    // Summarize a gml input, with the key types inferred as for the conversion, without writing anything
+    let mut keys = BTreeMap::new();
+    let mut summary = GraphSummary::new();
+    write_graph_body(
+        input,
+        io::sink(),
+        &mut keys,
+        None,
+        &Options::default(),
+        Some(&mut summary),
+        None,
+    )?;
+    for (attributes, values) in &keys {
+        if attributes.for_elem != GraphmlElems::Graph {
+            summary.add_key(
+                attributes.for_elem.value(),
+                &values.id,
+                &attributes.attr_name,
+                values.attr_type.value().unwrap_or_default(),
+            );
+        }
+    }
+    Ok(summary)
+}
+
+pub fn export_to_graphml(
+    input_gml: &Path,
+    output_path: &Path,
+) -> Result<ConversionStats, ConvertError> {
+    // Convert the gml file at the input path to a graphml file at the output path
+    // Files can be read twice, so no temp file is needed
+    let input_file = File::open(input_gml)?;
+    let output_file = File::create(output_path)?;
+    convert_seekable(BufReader::new(input_file), output_file)
 }
@@ -0,0 +1,114 @@
+// GML writer shared by the gml event and model writer and the graphml to gml converter
+// Strings are quoted with html entities, as GML has no escape characters. Json values (the lists and maps of the
+// data values and attributes) are written as GML lists: maps as nested lists, lists as the same key repeated for
+// each item, and lists nested in lists (which GML can't hold) as json strings.
+
+use std::io::{self, Write};
+
+use serde_json::Value;
+
+use crate::gml_parser::MAX_DEPTH;
+use crate::graph::{AttrValue, Attrs};
+
+pub const INDENT_2: &str = "  ";
+pub const LINE_BREAK: &[u8] = b"\n";
+
+pub fn gml_string(value: &str) -> String {
+    // Quote a string for gml, which has no escape characters and uses html entities instead
+    format!("\"{}\"", value.replace('&', "&amp;").replace('"', "&quot;"))
+}
+
+pub fn write_line<W: Write>(
+    writer: &mut W,
+    indent: &str,
+    label: &str,
+    value: &str,
+) -> io::Result<()> {
+    writer.write_all(format!("{}{} {}", indent, label, value).as_bytes())?;
+    writer.write_all(LINE_BREAK)
+}
+
+pub fn write_value<W: Write>(
+    writer: &mut W,
+    json: &Value,
+    label: &str,
+    indent: &str,
+) -> io::Result<()> {
+    // Write serde value (list, dict, etc) recursively, with one more indent level for each nested dict
+    // a [
+    //   y 2
+    //   z 1
+    //   dict [
+    //     a 1
+    //     b 2
+    //   ]
+    //   list 1
+    //   list 2
+    // ]
+    match json {
+        // The indent is the nesting level, stop before deeper values can overflow the stack
+        Value::Array(_) | Value::Object(_) if indent.len() / INDENT_2.len() > MAX_DEPTH => {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Values nested deeper than {} levels", MAX_DEPTH),
+            ));
+        }
+        Value::Array(array) if array.is_empty() => {
+            // Write value is string (ie. "[]" -> "[]" and not a list in GML
+            write_line(writer, indent, label, &gml_string("[]"))?;
+        }
+        Value::Array(array) => {
+            // A list is written as the same label repeated for each item
+            for item in array {
+                if item.is_array() {
+                    // GML has no way to nest a list directly in a list, keep the inner list as json
+                    write_line(writer, indent, label, &gml_string(&item.to_string()))?;
+                } else {
+                    write_value(writer, item, label, indent)?;
+                }
+            }
+        }
+        Value::Object(object) => {
+            writer.write_all(format!("{}{} [", indent, label).as_bytes())?;
+            writer.write_all(LINE_BREAK)?;
+            let inner_indent = format!("{}{}", indent, INDENT_2);
+            for (key, value) in object {
+                write_value(writer, value, key, &inner_indent)?;
+            }
+            // Close dict
+            writer.write_all(format!("{}]", indent).as_bytes())?;
+            writer.write_all(LINE_BREAK)?;
+        }
+        Value::String(value) => write_line(writer, indent, label, &gml_string(value))?,
+        Value::Number(value) => write_line(writer, indent, label, &value.to_string())?,
+        // GML has no boolean type, use integers like most gml writers
+        Value::Bool(value) => write_line(writer, indent, label, if *value { "1" } else { "0" })?,
+        Value::Null => {
+            // Omit null values like empty data values
+        }
+    }
+    Ok(())
+}
+
+pub fn write_attr<W: Write>(
+    writer: &mut W,
+    value: &AttrValue,
+    label: &str,
+    indent: &str,
+) -> io::Result<()> {
+    // Write an attribute like a json data value, reals always have a decimal (ie. 1.0) and the ones GML can't
+    // represent are kept as strings
+    match value {
+        AttrValue::Float(value) if !value.is_finite() => {
+            write_line(writer, indent, label, &gml_string(&value.to_string()))
+        }
+        value => write_value(writer, &value.to_json(), label, indent),
+    }
+}
+
+pub fn write_attrs<W: Write>(writer: &mut W, attrs: &Attrs, indent: &str) -> io::Result<()> {
+    for (name, value) in attrs {
+        write_attr(writer, value, name, indent)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,298 @@
+// In memory graph model, for graphs that fit in memory and need to be changed between reading and writing, and the
+// graph events the format readers and writers stream for graphs that don't.
+// Node ids are GraphML style string ids: gml integer ids are read as n<id> (ie. 1 -> n1) and written back as
+// integers, like the converters do.
+
+use std::collections::{BTreeMap, VecDeque};
+
+use serde_json::{Map, Number, Value};
+
+use crate::error::ConvertError;
+
+// Attributes of a graph, node or edge by name
+pub type Attrs = BTreeMap<String, AttrValue>;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum AttrValue {
+    Int(i64),
+    Float(f64),
+    String(String),
+    Bool(bool),
+    List(Vec<AttrValue>),
+    Map(Attrs),
+}
+
+impl AttrValue {
+    pub fn to_json(&self) -> Value {
+        // Json value of an attribute, used for lists and maps in GraphML
+        match self {
+            AttrValue::Int(value) => Value::from(*value),
+            AttrValue::Float(value) => Number::from_f64(*value).map_or(Value::Null, Value::Number),
+            AttrValue::String(value) => Value::from(value.as_str()),
+            AttrValue::Bool(value) => Value::from(*value),
+            AttrValue::List(values) => {
+                Value::Array(values.iter().map(AttrValue::to_json).collect())
+            }
+            AttrValue::Map(attrs) => Value::Object(
+                attrs
+                    .iter()
+                    .map(|(name, value)| (name.clone(), value.to_json()))
+                    .collect::<Map<String, Value>>(),
+            ),
+        }
+    }
+
+    pub fn from_json(value: &Value) -> Self {
+        // Attribute of a json value, null has no attribute equivalent and becomes an empty string
+        match value {
+            Value::Null => AttrValue::String(String::new()),
+            Value::Bool(value) => AttrValue::Bool(*value),
+            Value::Number(number) => match number.as_i64() {
+                Some(value) => AttrValue::Int(value),
+                None => AttrValue::Float(number.as_f64().unwrap_or_default()),
+            },
+            Value::String(value) => AttrValue::String(value.clone()),
+            Value::Array(values) => {
+                AttrValue::List(values.iter().map(AttrValue::from_json).collect())
+            }
+            Value::Object(object) => AttrValue::Map(
+                object
+                    .iter()
+                    .map(|(name, value)| (name.clone(), AttrValue::from_json(value)))
+                    .collect(),
+            ),
+        }
+    }
+}
+
+// Element of a graph as read from a file, in file order: a GraphStart, its nodes and edges, then a GraphEnd
+// for each graph of the file
+#[derive(Debug, Clone, PartialEq)]
+pub enum GraphEvent {
+    GraphStart {
+        directed: Option<bool>,
+        attrs: Attrs,
+    },
+    Node {
+        id: String,
+        attrs: Attrs,
+    },
+    Edge {
+        id: Option<String>,
+        source: String,
+        target: String,
+        // Direction of the edge when it differs from the graph one
+        directed: Option<bool>,
+        attrs: Attrs,
+    },
+    GraphEnd {
+        // Graph attributes read after the first node or edge of the graph
+        attrs: Attrs,
+    },
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Node {
+    pub id: String,
+    pub attrs: Attrs,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Edge {
+    pub id: Option<String>,
+    pub source: String,
+    pub target: String,
+    // Direction of the edge when it differs from the graph one (ie. in a mixed graph)
+    pub directed: Option<bool>,
+    pub attrs: Attrs,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Graph {
+    // Whether the edges are directed by default, None when the file doesn't say
+    pub directed: Option<bool>,
+    pub attrs: Attrs,
+    pub nodes: Vec<Node>,
+    pub edges: Vec<Edge>,
+}
+
+impl Graph {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn node(&self, id: &str) -> Option<&Node> {
+        self.nodes.iter().find(|node| node.id == id)
+    }
+
+    pub fn node_mut(&mut self, id: &str) -> Option<&mut Node> {
+        self.nodes.iter_mut().find(|node| node.id == id)
+    }
+
+    pub fn add_node(&mut self, id: &str) -> &mut Node {
+        self.nodes.push(Node {
+            id: id.to_string(),
+            attrs: Attrs::new(),
+        });
+        self.nodes.last_mut().expect("node was just added")
+    }
+
+    pub fn add_edge(&mut self, source: &str, target: &str) -> &mut Edge {
+        self.edges.push(Edge {
+            source: source.to_string(),
+            target: target.to_string(),
+            ..Default::default()
+        });
+        self.edges.last_mut().expect("edge was just added")
+    }
+
+    pub fn events(&self) -> impl Iterator<Item = GraphEvent> + '_ {
+        // Stream the graph to an event writer
+        let start = GraphEvent::GraphStart {
+            directed: self.directed,
+            attrs: self.attrs.clone(),
+        };
+        let nodes = self.nodes.iter().map(|node| GraphEvent::Node {
+            id: node.id.clone(),
+            attrs: node.attrs.clone(),
+        });
+        let edges = self.edges.iter().map(|edge| GraphEvent::Edge {
+            id: edge.id.clone(),
+            source: edge.source.clone(),
+            target: edge.target.clone(),
+            directed: edge.directed,
+            attrs: edge.attrs.clone(),
+        });
+        std::iter::once(start)
+            .chain(nodes)
+            .chain(edges)
+            .chain(std::iter::once(GraphEvent::GraphEnd {
+                attrs: Attrs::new(),
+            }))
+    }
+
+    pub fn read_events<I>(events: &mut I) -> Result<Option<Self>, ConvertError>
+    where
+        I: Iterator<Item = Result<GraphEvent, ConvertError>>,
+    {
+        // Build the next graph of an event stream, None when there is no graph left
+        let mut graph = None;
+        for event in events {
+            match event? {
+                GraphEvent::GraphStart { directed, attrs } => {
+                    graph = Some(Graph {
+                        directed,
+                        attrs,
+                        ..Default::default()
+                    });
+                }
+                GraphEvent::Node { id, attrs } => {
+                    if let Some(graph) = graph.as_mut() {
+                        graph.nodes.push(Node { id, attrs });
+                    }
+                }
+                GraphEvent::Edge {
+                    id,
+                    source,
+                    target,
+                    directed,
+                    attrs,
+                } => {
+                    if let Some(graph) = graph.as_mut() {
+                        graph.edges.push(Edge {
+                            id,
+                            source,
+                            target,
+                            directed,
+                            attrs,
+                        });
+                    }
+                }
+                GraphEvent::GraphEnd { attrs } => {
+                    if let Some(graph) = graph.as_mut() {
+                        graph.attrs.extend(attrs);
+                    }
+                    break;
+                }
+            }
+        }
+        Ok(graph)
+    }
+}
+
+// Reader of the graph events of a format, streamed by Events
+pub trait EventSource {
+    // Read the next events of the input into the queue, returns false at the end of the input
+    fn read_events(&mut self, events: &mut VecDeque<GraphEvent>) -> Result<bool, ConvertError>;
+}
+
+// Events of a format reader, which stop at its first error as the rest of the input can't be read reliably
+pub struct Events<S: EventSource> {
+    source: S,
+    events: VecDeque<GraphEvent>,
+    failed: bool,
+}
+
+impl<S: EventSource> Events<S> {
+    pub fn new(source: S) -> Self {
+        Events {
+            source,
+            events: VecDeque::new(),
+            failed: false,
+        }
+    }
+
+    pub fn source(&self) -> &S {
+        &self.source
+    }
+}
+
+impl<S: EventSource> Iterator for Events<S> {
+    type Item = Result<GraphEvent, ConvertError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.events.is_empty() && !self.failed {
+            match self.source.read_events(&mut self.events) {
+                Ok(true) => {}
+                Ok(false) => return None,
+                Err(e) => {
+                    self.failed = true;
+                    return Some(Err(e));
+                }
+            }
+        }
+        self.events.pop_front().map(Ok)
+    }
+}
+
+// Edge default and attributes of a graph being read. Its GraphStart is only returned before its first node or edge
+// (or its end) with the attributes read until then, and the attributes read after it are returned with its GraphEnd.
+#[derive(Debug, Default)]
+pub struct GraphHeader {
+    pub started: bool,
+    pub directed: Option<bool>,
+    pub attrs: Attrs,
+}
+
+impl GraphHeader {
+    pub fn push(&mut self, event: GraphEvent, events: &mut VecDeque<GraphEvent>) {
+        // Queue a node, edge or graph end, after the GraphStart when the graph isn't started yet
+        if !self.started {
+            self.started = true;
+            events.push_back(GraphEvent::GraphStart {
+                directed: self.directed,
+                attrs: std::mem::take(&mut self.attrs),
+            });
+        }
+        events.push_back(event);
+    }
+
+    pub fn end(&mut self, events: &mut VecDeque<GraphEvent>) {
+        let attrs = if self.started {
+            std::mem::take(&mut self.attrs)
+        } else {
+            Attrs::new()
+        };
+        self.push(GraphEvent::GraphEnd { attrs }, events);
+    }
+}
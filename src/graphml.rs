@@ -0,0 +1,588 @@
+// GraphML reader and writer of graph events and of the in memory graph model
+// The reader streams the items of the GraphML parser the converter uses, and reads data values like it: json lists
+// and maps (how lists and maps are written, in string keys) are read as lists and maps, and any other value from
+// the attr.type of its key, as a string without one. Key defaults are added to the nodes,
+// edges and hyperedges without a value. Descriptions, locators and ports become desc, locator and port attributes,
+// the document description a comment attribute of the next graph, and hyperedges follow the hyperedge policy like
+// in the converter. Nested graphs are flattened into their parent graph.
+
+use std::collections::{HashMap, VecDeque};
+use std::fs::File;
+use std::io::{self, BufRead, BufWriter, Seek, SeekFrom, Write};
+
+use quick_xml::Writer;
+use quick_xml::events::{BytesEnd, BytesStart, BytesText, Event};
+
+use crate::error::ConvertError;
+use crate::format::{self, GraphConverter, GraphReader, GraphWriter};
+use crate::graph::{self, AttrValue, Attrs, Edge, EventSource, Graph, GraphEvent, GraphHeader};
+use crate::graphml_parser::{Element, Item, Parser};
+use crate::graphml_to_gml::{self, HyperedgePolicy};
+use crate::stats::ConversionStats;
+use crate::xml::{Keys, write_graphml_header};
+
+const LINE_BREAK: &[u8] = b"\n";
+
+fn insert_port(attrs: &mut Attrs, port: Attrs) {
+    // Add a port to its node or parent port, several ports are kept as a list
+    let port = AttrValue::Map(port);
+    match attrs.get_mut("port") {
+        Some(AttrValue::List(ports)) => ports.push(port),
+        Some(previous) => *previous = AttrValue::List(vec![previous.clone(), port]),
+        None => {
+            attrs.insert("port".to_string(), port);
+        }
+    }
+}
+
+fn port_attrs(source: Option<String>, target: Option<String>) -> Option<AttrValue> {
+    // Port map of an edge connected to node ports: port [ source "p0" target "p1" ]
+    let ports: Attrs = [("source", source), ("target", target)]
+        .into_iter()
+        .filter_map(|(name, port)| Some((name.to_string(), AttrValue::String(port?))))
+        .collect();
+    (!ports.is_empty()).then_some(AttrValue::Map(ports))
+}
+
+#[derive(Debug, Default)]
+struct Hyperedge {
+    id: Option<String>,
+    // Nodes, ports and whether the hyperedge goes into the node
+    endpoints: Vec<(String, Option<String>, bool)>,
+    attrs: Attrs,
+}
+
+pub struct EventReader<R: BufRead> {
+    parser: Parser<R>,
+    hyperedges: HyperedgePolicy,
+    // Default values of the keys by id
+    defaults: HashMap<String, AttrValue>,
+    // Edge default and attributes of the graph being read
+    header: GraphHeader,
+    // Attributes of the document (ie. its description), added to the next graph
+    document: Attrs,
+    // Nodes being read: the nodes containing the nested graphs being read, then the current node. A node is
+    // returned at its end, after the nodes of its nested graph.
+    nodes: Vec<(String, Attrs)>,
+    edge: Option<Edge>,
+    // Ports of the current node, from the outermost to the innermost one
+    ports: Vec<Attrs>,
+    hyperedge: Option<Hyperedge>,
+    // Number of hyperedges read, to name the ones without an id
+    hyperedge_count: usize,
+}
+
+impl<R: BufRead> EventReader<R> {
+    pub fn new(input: R, hyperedges: HyperedgePolicy) -> Self {
+        EventReader {
+            parser: Parser::new(input),
+            hyperedges,
+            defaults: HashMap::new(),
+            header: GraphHeader::default(),
+            document: Attrs::new(),
+            nodes: Vec::new(),
+            edge: None,
+            ports: Vec::new(),
+            hyperedge: None,
+            hyperedge_count: 0,
+        }
+    }
+
+    fn fill_defaults(&self, attrs: &mut Attrs, element: Element) {
+        // Add the default value of the keys for the element that it has no value for
+        let keys = self.parser.keys();
+        for (id, value) in &self.defaults {
+            if let Some(key) = keys.get(id)
+                && key.applies_to(element)
+                && !attrs.contains_key(&key.attr_name)
+            {
+                attrs.insert(key.attr_name.clone(), value.clone());
+            }
+        }
+    }
+
+    fn element_attrs(&mut self, element: Option<Element>) -> Option<&mut Attrs> {
+        // Attributes of the innermost element being read. The data of a nested graph is kept on its node, and the
+        // document data on the next graph. Endpoints have no attributes.
+        match element {
+            Some(Element::Edge) => self.edge.as_mut().map(|edge| &mut edge.attrs),
+            Some(Element::Node) => self.nodes.last_mut().map(|(_, attrs)| attrs),
+            Some(Element::Graph) => match self.nodes.last_mut() {
+                Some((_, attrs)) => Some(attrs),
+                None => Some(&mut self.header.attrs),
+            },
+            Some(Element::Hyperedge) => self
+                .hyperedge
+                .as_mut()
+                .map(|hyperedge| &mut hyperedge.attrs),
+            Some(Element::Port) => self.ports.last_mut(),
+            Some(Element::Endpoint) => None,
+            None => Some(&mut self.document),
+        }
+    }
+
+    fn push_hyperedge(&mut self, mut hyperedge: Hyperedge, events: &mut VecDeque<GraphEvent>) {
+        // Add a hyperedge as a star: a node holding the hyperedge data, with an edge to each endpoint
+        self.fill_defaults(&mut hyperedge.attrs, Element::Hyperedge);
+        let center = hyperedge
+            .id
+            .unwrap_or_else(|| format!("hyperedge{}", self.hyperedge_count));
+        let mut attrs = hyperedge.attrs;
+        attrs.insert("hyperedge".to_string(), AttrValue::String(center.clone()));
+        let node = GraphEvent::Node {
+            id: center.clone(),
+            attrs,
+        };
+        self.header.push(node, events);
+        for (node, port, incoming) in hyperedge.endpoints {
+            let (source, target, ports) = if incoming {
+                (center.clone(), node, port_attrs(None, port))
+            } else {
+                (node, center.clone(), port_attrs(port, None))
+            };
+            let edge = GraphEvent::Edge {
+                id: None,
+                source,
+                target,
+                directed: None,
+                attrs: ports
+                    .map(|ports| ("port".to_string(), ports))
+                    .into_iter()
+                    .collect(),
+            };
+            self.header.push(edge, events);
+        }
+    }
+}
+
+impl<R: BufRead> EventSource for EventReader<R> {
+    fn read_events(&mut self, events: &mut VecDeque<GraphEvent>) -> Result<bool, ConvertError> {
+        loop {
+            let Some(item) = self.parser.next_item()? else {
+                return Ok(false);
+            };
+            let position = self.parser.position();
+            match item {
+                Item::Key { id } => {
+                    let key = &self.parser.keys()[&id];
+                    if let Some(default) = &key.default
+                        && let Some(value) = key.value(default, position)?
+                    {
+                        self.defaults.insert(id, value.into_attr());
+                    }
+                }
+                // Nested graphs are flattened, their nodes are returned before the node containing them
+                Item::GraphStart { nested: true, .. } | Item::GraphEnd { nested: true } => {}
+                Item::GraphStart { directed, .. } => {
+                    self.header = GraphHeader {
+                        directed,
+                        attrs: std::mem::take(&mut self.document),
+                        ..Default::default()
+                    };
+                }
+                Item::GraphEnd { nested: false } => {
+                    // The data read after the graph started is returned with its end
+                    self.header.end(events);
+                    return Ok(true);
+                }
+                Item::NodeStart { id } => self.nodes.push((id, Attrs::new())),
+                Item::NodeEnd => {
+                    if let Some((id, mut attrs)) = self.nodes.pop() {
+                        self.fill_defaults(&mut attrs, Element::Node);
+                        self.header.push(GraphEvent::Node { id, attrs }, events);
+                        return Ok(true);
+                    }
+                }
+                Item::EdgeStart {
+                    id,
+                    source,
+                    target,
+                    directed,
+                    source_port,
+                    target_port,
+                } => {
+                    // The edge direction is only kept when it differs from the graph one
+                    let graph_directed = self.header.directed.unwrap_or(false);
+                    self.edge = Some(Edge {
+                        id,
+                        source,
+                        target,
+                        directed: directed.filter(|&value| value != graph_directed),
+                        attrs: port_attrs(source_port, target_port)
+                            .map(|ports| ("port".to_string(), ports))
+                            .into_iter()
+                            .collect(),
+                    });
+                }
+                Item::EdgeEnd => {
+                    if let Some(mut edge) = self.edge.take() {
+                        self.fill_defaults(&mut edge.attrs, Element::Edge);
+                        let event = GraphEvent::Edge {
+                            id: edge.id,
+                            source: edge.source,
+                            target: edge.target,
+                            directed: edge.directed,
+                            attrs: edge.attrs,
+                        };
+                        self.header.push(event, events);
+                        return Ok(true);
+                    }
+                }
+                Item::PortStart { name } => {
+                    self.ports
+                        .push([("name".to_string(), AttrValue::String(name))].into());
+                }
+                Item::PortEnd => {
+                    let port = self.ports.pop().unwrap_or_default();
+                    let parent = match self.ports.last_mut() {
+                        Some(parent) => Some(parent),
+                        None => self.nodes.last_mut().map(|(_, attrs)| attrs),
+                    };
+                    if let Some(parent) = parent {
+                        insert_port(parent, port);
+                    }
+                }
+                Item::HyperedgeStart { id } => {
+                    if self.hyperedges == HyperedgePolicy::Error {
+                        return Err(ConvertError::Graphml {
+                            position,
+                            message: "Hyperedges can't be read as graph events".to_string(),
+                        });
+                    }
+                    self.hyperedge = Some(Hyperedge {
+                        id,
+                        ..Default::default()
+                    });
+                }
+                Item::Endpoint {
+                    node,
+                    port,
+                    incoming,
+                } => {
+                    if let Some(hyperedge) = self.hyperedge.as_mut() {
+                        hyperedge.endpoints.push((node, port, incoming));
+                    }
+                }
+                Item::HyperedgeEnd => {
+                    self.hyperedge_count += 1;
+                    if let Some(hyperedge) = self.hyperedge.take()
+                        && self.hyperedges == HyperedgePolicy::Star
+                    {
+                        self.push_hyperedge(hyperedge, events);
+                        return Ok(true);
+                    }
+                }
+                Item::Locator { element, href } => {
+                    if let Some(attrs) = self.element_attrs(element) {
+                        attrs.insert("locator".to_string(), AttrValue::String(href));
+                    }
+                }
+                Item::Desc { element, text } => {
+                    let text = text.trim();
+                    // The document description is kept as a comment
+                    let name = if element.is_none() { "comment" } else { "desc" };
+                    if !text.is_empty()
+                        && let Some(attrs) = self.element_attrs(element)
+                    {
+                        attrs.insert(name.to_string(), AttrValue::String(text.to_string()));
+                    }
+                }
+                Item::Data { element, key, text } => {
+                    let key =
+                        self.parser
+                            .keys()
+                            .get(&key)
+                            .ok_or_else(|| ConvertError::Graphml {
+                                position,
+                                message: format!("Undeclared key {:?}", key),
+                            })?;
+                    let name = key.attr_name.clone();
+                    if let Some(value) = key.value(&text, position)?
+                        && let Some(attrs) = self.element_attrs(element)
+                    {
+                        attrs.insert(name, value.into_attr());
+                    }
+                }
+                // Elements of GraphML extensions (ie. yEd) are left out
+                Item::Unsupported { .. } => {}
+            }
+        }
+    }
+}
+
+pub type Events<R> = graph::Events<EventReader<R>>;
+
+pub fn events<R: BufRead>(input: R) -> Events<R> {
+    // Stream the graphs of a graphml input as events, without keeping them in memory
+    events_with_hyperedges(input, HyperedgePolicy::default())
+}
+
+pub fn events_with_hyperedges<R: BufRead>(input: R, hyperedges: HyperedgePolicy) -> Events<R> {
+    // Stream the graphs of a graphml input as events, with the given handling of the hyperedges
+    graph::Events::new(EventReader::new(input, hyperedges))
+}
+
+pub fn read<R: BufRead>(input: R) -> Result<Graph, ConvertError> {
+    // Read the first graph of a graphml input, with the graphs nested in its nodes flattened into it
+    let mut events = events(input);
+    Graph::read_events(&mut events)?.ok_or_else(|| ConvertError::Graphml {
+        position: events.source().parser.position(),
+        message: "No graph found".to_string(),
+    })
+}
+
+pub fn data_text(value: &AttrValue) -> String {
+    match value {
+        AttrValue::Int(value) => value.to_string(),
+        AttrValue::Float(value) => format!("{:?}", value),
+        AttrValue::Bool(value) => value.to_string(),
+        AttrValue::String(value) => value.to_string(),
+        AttrValue::List(_) | AttrValue::Map(_) => value.to_json().to_string(),
+    }
+}
+
+fn add_keys(keys: &mut Keys, element: &'static str, attrs: &Attrs) {
+    // Keys holding a list or a map are strings, as their values are json
+    for (name, value) in attrs {
+        keys.add(element, name, value);
+    }
+}
+
+fn write_element<W: Write>(
+    writer: &mut Writer<W>,
+    elem: BytesStart,
+    element: &'static str,
+    attrs: &Attrs,
+    keys: &Keys,
+) -> Result<(), ConvertError> {
+    // Write an element with its data: <node id="n1"><data key="d0">1.0</data></node>
+    writer.write_event(Event::Start(elem))?;
+    write_data(writer, element, attrs, keys)?;
+    writer.write_event(Event::End(BytesEnd::borrowed(element.as_bytes())))?;
+    Ok(())
+}
+
+fn write_data<W: Write>(
+    writer: &mut Writer<W>,
+    element: &'static str,
+    attrs: &Attrs,
+    keys: &Keys,
+) -> Result<(), ConvertError> {
+    for (name, value) in attrs {
+        let mut data = BytesStart::borrowed_name(b"data");
+        data.push_attribute(("key", format!("d{}", keys.index(element, name)).as_str()));
+        writer.write_event(Event::Start(data))?;
+        writer.write_event(Event::Text(BytesText::from_plain_str(&data_text(value))))?;
+        writer.write_event(Event::End(BytesEnd::borrowed(b"data")))?;
+    }
+    Ok(())
+}
+
+fn write_graph_start<W: Write>(
+    writer: &mut Writer<W>,
+    directed: Option<bool>,
+    attrs: &Attrs,
+    keys: &Keys,
+) -> Result<(), ConvertError> {
+    // Open a graph with its edge default and data, the graph is closed after its nodes and edges
+    let mut elem = BytesStart::borrowed_name(b"graph");
+    match directed {
+        Some(true) => elem.push_attribute(("edgedefault", "directed")),
+        Some(false) => elem.push_attribute(("edgedefault", "undirected")),
+        None => {}
+    }
+    writer.write_event(Event::Start(elem))?;
+    write_data(writer, "graph", attrs, keys)
+}
+
+fn write_node<W: Write>(
+    writer: &mut Writer<W>,
+    id: &str,
+    attrs: &Attrs,
+    keys: &Keys,
+) -> Result<(), ConvertError> {
+    let mut elem = BytesStart::borrowed_name(b"node");
+    elem.push_attribute(("id", id));
+    write_element(writer, elem, "node", attrs, keys)
+}
+
+fn write_edge<W: Write>(
+    writer: &mut Writer<W>,
+    id: Option<&str>,
+    (source, target): (&str, &str),
+    directed: Option<bool>,
+    attrs: &Attrs,
+    keys: &Keys,
+) -> Result<(), ConvertError> {
+    let mut elem = BytesStart::borrowed_name(b"edge");
+    if let Some(id) = id {
+        elem.push_attribute(("id", id));
+    }
+    elem.push_attribute(("source", source));
+    elem.push_attribute(("target", target));
+    if let Some(directed) = directed {
+        elem.push_attribute(("directed", if directed { "true" } else { "false" }));
+    }
+    write_element(writer, elem, "edge", attrs, keys)
+}
+
+fn write_header<W: Write>(output: &mut W, keys: &Keys) -> Result<(), ConvertError> {
+    // Write the header and the keys, the graphs follow with their own indentation like in the gml converter output
+    let mut writer = Writer::new_with_indent(output, b' ', 2);
+    write_graphml_header(&mut writer)?;
+    for (index, (element, name, attr_type)) in keys.keys.iter().enumerate() {
+        let mut elem = BytesStart::borrowed_name(b"key");
+        elem.push_attribute(("id", format!("d{}", index).as_str()));
+        elem.push_attribute(("for", *element));
+        elem.push_attribute(("attr.name", name.as_str()));
+        if let Some(attr_type) = attr_type.value() {
+            elem.push_attribute(("attr.type", attr_type));
+        }
+        writer.write_event(Event::Empty(elem))?;
+    }
+    writer.write(LINE_BREAK)?;
+    Ok(())
+}
+
+pub struct EventWriter<W: Write> {
+    output: W,
+    // The keys are only known once every event is written, so the graphs are written to an anonymous temp file
+    // first and copied to the output after the header and keys, like the gml converter does
+    body: Writer<BufWriter<File>>,
+    keys: Keys,
+}
+
+impl<W: Write> EventWriter<W> {
+    pub fn new(output: W) -> Result<Self, ConvertError> {
+        Ok(EventWriter {
+            output,
+            body: Writer::new_with_indent(BufWriter::new(tempfile::tempfile()?), b' ', 2),
+            keys: Keys::default(),
+        })
+    }
+
+    pub fn write(&mut self, event: &GraphEvent) -> Result<(), ConvertError> {
+        let writer = &mut self.body;
+        match event {
+            GraphEvent::GraphStart { directed, attrs } => {
+                add_keys(&mut self.keys, "graph", attrs);
+                write_graph_start(writer, *directed, attrs, &self.keys)?;
+            }
+            GraphEvent::Node { id, attrs } => {
+                add_keys(&mut self.keys, "node", attrs);
+                write_node(writer, id, attrs, &self.keys)?;
+            }
+            GraphEvent::Edge {
+                id,
+                source,
+                target,
+                directed,
+                attrs,
+            } => {
+                add_keys(&mut self.keys, "edge", attrs);
+                write_edge(
+                    writer,
+                    id.as_deref(),
+                    (source, target),
+                    *directed,
+                    attrs,
+                    &self.keys,
+                )?;
+            }
+            GraphEvent::GraphEnd { attrs } => {
+                add_keys(&mut self.keys, "graph", attrs);
+                write_data(writer, "graph", attrs, &self.keys)?;
+                writer.write_event(Event::End(BytesEnd::borrowed(b"graph")))?;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn finish(mut self) -> Result<W, ConvertError> {
+        // Write the header and keys, then copy the graphs from the temp file
+        self.body
+            .write_event(Event::End(BytesEnd::borrowed(b"graphml")))?;
+        let mut body = self
+            .body
+            .into_inner()
+            .into_inner()
+            .map_err(|e| e.into_error())?;
+        body.seek(SeekFrom::Start(0))?;
+
+        write_header(&mut self.output, &self.keys)?;
+        io::copy(&mut body, &mut self.output)?;
+        self.output.flush()?;
+        Ok(self.output)
+    }
+}
+
+pub fn write<W: Write>(graph: &Graph, mut output: W) -> Result<(), ConvertError> {
+    // Write a graph with a key for each attribute name of the graph, nodes and edges. The keys are known from
+    // the graph, so it is written directly without a temp file.
+    let mut keys = Keys::default();
+    add_keys(&mut keys, "graph", &graph.attrs);
+    for node in &graph.nodes {
+        add_keys(&mut keys, "node", &node.attrs);
+    }
+    for edge in &graph.edges {
+        add_keys(&mut keys, "edge", &edge.attrs);
+    }
+    write_header(&mut output, &keys)?;
+
+    let mut writer = Writer::new_with_indent(&mut output, b' ', 2);
+    write_graph_start(&mut writer, graph.directed, &graph.attrs, &keys)?;
+    for node in &graph.nodes {
+        write_node(&mut writer, &node.id, &node.attrs, &keys)?;
+    }
+    for edge in &graph.edges {
+        write_edge(
+            &mut writer,
+            edge.id.as_deref(),
+            (&edge.source, &edge.target),
+            edge.directed,
+            &edge.attrs,
+            &keys,
+        )?;
+    }
+    writer.write_event(Event::End(BytesEnd::borrowed(b"graph")))?;
+    writer.write_event(Event::End(BytesEnd::borrowed(b"graphml")))?;
+    output.flush()?;
+    Ok(())
+}
+
+// GraphML in the format registry, with the options of its converter to gml. The reader follows its hyperedge policy.
+#[derive(Debug, Clone, Default)]
+pub struct Graphml {
+    pub options: graphml_to_gml::Options,
+}
+
+impl GraphReader for Graphml {
+    fn read_events<'a>(&self, input: Box<dyn BufRead + 'a>) -> format::Events<'a> {
+        Box::new(events_with_hyperedges(input, self.options.hyperedges))
+    }
+}
+
+impl GraphConverter for Graphml {
+    fn convert<'a>(
+        &self,
+        input: Box<dyn BufRead + 'a>,
+        output: &mut dyn Write,
+    ) -> Result<ConversionStats, ConvertError> {
+        graphml_to_gml::convert_with_options(input, output, &self.options)
+    }
+}
+
+impl GraphWriter for Graphml {
+    fn write_events(
+        &self,
+        events: &mut format::Events<'_>,
+        output: &mut dyn Write,
+    ) -> Result<(), ConvertError> {
+        let mut writer = EventWriter::new(output)?;
+        for event in events {
+            writer.write(&event?)?;
+        }
+        writer.finish()?;
+        Ok(())
+    }
+}
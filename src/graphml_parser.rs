@@ -0,0 +1,419 @@
+// Streaming GraphML parser shared by the graphml event reader and the graphml to gml converter
+// Reads the xml events of the input and returns the GraphML items in document order: the start and end of the
+// graphs, nodes, edges, hyperedges and ports, the endpoints and locators, and the text of the data and
+// descriptions with the element they belong to. Key declarations are kept with their default value. Elements that
+// aren't GraphML (or not where they are found) are returned as unsupported and skipped with their content, as are
+// the elements inside data values (ie. yEd graphics).
+
+use std::collections::HashMap;
+use std::io::BufRead;
+
+use quick_xml::Reader;
+use quick_xml::events::Event;
+
+use crate::attr_types::{GraphmlAttributeTypes, parse_json};
+use crate::error::ConvertError;
+use crate::graph::AttrValue;
+use crate::xml::{Dialect, get_attribute, required_attribute};
+
+// GraphML elements holding data
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Element {
+    Graph,
+    Node,
+    Edge,
+    Hyperedge,
+    Port,
+    Endpoint,
+}
+
+#[derive(Debug, Clone)]
+pub struct Key {
+    // Name of the attribute, the key id when the key has no attr.name
+    pub attr_name: String,
+    // Json for the keys without an attr.type, whose values are strings when they aren't json lists or maps
+    pub attr_type: GraphmlAttributeTypes,
+    // Element the key is for, None for all elements
+    pub domain: Option<Element>,
+    // Value for the elements without a data value for the key
+    pub default: Option<String>,
+}
+
+// Value read for a key: a serialized list or map, with the order of its json keys, or a value of the key type
+#[derive(Debug, Clone, PartialEq)]
+pub enum KeyValue {
+    Json(serde_json::Value),
+    Attr(AttrValue),
+}
+
+impl KeyValue {
+    pub fn into_attr(self) -> AttrValue {
+        match self {
+            KeyValue::Json(json) => AttrValue::from_json(&json),
+            KeyValue::Attr(value) => value,
+        }
+    }
+}
+
+impl Key {
+    pub fn applies_to(&self, element: Element) -> bool {
+        self.domain.is_none_or(|domain| domain == element)
+    }
+
+    pub fn value(&self, text: &str, position: usize) -> Result<Option<KeyValue>, ConvertError> {
+        // Value of a data element or default of the key, read the same way by the reader and the converter.
+        // Empty values are left out, serialized lists and maps are read whatever the key type, and other values
+        // are read from the key type.
+        if text.is_empty() || text == "\"\"" {
+            return Ok(None);
+        }
+        let invalid = |message| ConvertError::Graphml { position, message };
+        if let Some(json) = parse_json(text).map_err(invalid)? {
+            return Ok(Some(KeyValue::Json(json)));
+        }
+        match self.attr_type.parse(text) {
+            Some(value) => Ok(Some(KeyValue::Attr(value))),
+            None => Err(invalid(format!(
+                "Invalid {} value {:?}",
+                self.attr_name, text
+            ))),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Item {
+    // End of a key declaration, with its default value
+    Key {
+        id: String,
+    },
+    // Graph, with its edge default (None when missing). A nested graph is inside a node.
+    GraphStart {
+        id: Option<String>,
+        directed: Option<bool>,
+        nested: bool,
+    },
+    GraphEnd {
+        nested: bool,
+    },
+    NodeStart {
+        id: String,
+    },
+    NodeEnd,
+    EdgeStart {
+        id: Option<String>,
+        source: String,
+        target: String,
+        // Direction of the edge, when it has a directed attribute
+        directed: Option<bool>,
+        source_port: Option<String>,
+        target_port: Option<String>,
+    },
+    EdgeEnd,
+    HyperedgeStart {
+        id: Option<String>,
+    },
+    HyperedgeEnd,
+    Endpoint {
+        node: String,
+        port: Option<String>,
+        // Whether the hyperedge goes into the node (type="in")
+        incoming: bool,
+    },
+    PortStart {
+        name: String,
+    },
+    PortEnd,
+    // Link to a graph or node defined in another document: <locator xlink:href="other.graphml"/>
+    Locator {
+        element: Option<Element>,
+        href: String,
+    },
+    // Description of the innermost element being read, None for the document
+    Desc {
+        element: Option<Element>,
+        text: String,
+    },
+    // Data value of the innermost element being read, None for the document. The key may be undeclared.
+    Data {
+        element: Option<Element>,
+        key: String,
+        text: String,
+    },
+    Unsupported {
+        name: String,
+    },
+}
+
+// Element whose text is being read
+#[derive(Debug, Clone)]
+enum Text {
+    Data {
+        element: Option<Element>,
+        key: String,
+    },
+    Desc {
+        element: Option<Element>,
+    },
+    Default,
+}
+
+fn edge_direction(value: Option<String>, position: usize) -> Result<Option<bool>, ConvertError> {
+    match value.as_deref() {
+        Some("true") => Ok(Some(true)),
+        Some("false") => Ok(Some(false)),
+        Some(value) => Err(ConvertError::Graphml {
+            position,
+            message: format!("Invalid edge directed value {:?}", value),
+        }),
+        None => Ok(None),
+    }
+}
+
+fn read_key(
+    element: &quick_xml::events::BytesStart,
+    position: usize,
+) -> Result<(String, Key), ConvertError> {
+    // Read a key definition: <key id="d0" for="node" attr.name="label" attr.type="string"/>
+    let key_id = required_attribute(element, b"id", position, Dialect::Graphml)?;
+    let attr_type = match get_attribute(element, b"attr.type", position)? {
+        None => GraphmlAttributeTypes::Json,
+        Some(name) => {
+            GraphmlAttributeTypes::from_name(&name).ok_or_else(|| ConvertError::Graphml {
+                position,
+                message: format!("Unrecognized attr.type {:?} for key {:?}", name, key_id),
+            })?
+        }
+    };
+    let domain = match get_attribute(element, b"for", position)?.as_deref() {
+        Some("graph") => Some(Element::Graph),
+        Some("node") => Some(Element::Node),
+        Some("edge") => Some(Element::Edge),
+        Some("hyperedge") => Some(Element::Hyperedge),
+        Some("port") => Some(Element::Port),
+        Some("endpoint") => Some(Element::Endpoint),
+        Some("all") | None => None,
+        Some(other) => {
+            return Err(ConvertError::Graphml {
+                position,
+                message: format!("Unsupported for value {:?} for key {:?}", other, key_id),
+            });
+        }
+    };
+    let key = Key {
+        // Keys without a name are named after their id
+        attr_name: get_attribute(element, b"attr.name", position)?
+            .unwrap_or_else(|| key_id.clone()),
+        attr_type,
+        domain,
+        default: None,
+    };
+    Ok((key_id, key))
+}
+
+pub struct Parser<R: BufRead> {
+    reader: Reader<R>,
+    buf: Vec<u8>,
+    keys: HashMap<String, Key>,
+    // Key being declared, until its end tag which can come after a default value
+    key: Option<(String, Key)>,
+    // GraphML elements being read, from the outermost one
+    elements: Vec<Element>,
+    // Element whose text is being read and its text
+    text: Option<(Text, String)>,
+    // Depth of the elements being skipped
+    skipped: usize,
+    // Position of the start tag of the last item returned
+    position: usize,
+}
+
+impl<R: BufRead> Parser<R> {
+    pub fn new(input: R) -> Self {
+        let mut reader = Reader::from_reader(input);
+        // Handle empty tags (ie. <node id="n1"/> or <key .../>) like a start tag followed by an end tag
+        reader.expand_empty_elements(true);
+        Parser {
+            reader,
+            buf: Vec::new(),
+            keys: HashMap::new(),
+            key: None,
+            elements: Vec::new(),
+            text: None,
+            skipped: 0,
+            position: 0,
+        }
+    }
+
+    pub fn keys(&self) -> &HashMap<String, Key> {
+        // Keys declared so far by id
+        &self.keys
+    }
+
+    pub fn position(&self) -> usize {
+        // Byte position of the last item returned, for the errors and warnings about it
+        self.position
+    }
+
+    pub fn next_item(&mut self) -> Result<Option<Item>, ConvertError> {
+        loop {
+            self.buf.clear();
+            let event = self.reader.read_event(&mut self.buf);
+            let position = self.reader.buffer_position();
+            match event {
+                // Elements inside skipped ones or inside a data value
+                Ok(Event::Start(_)) if self.skipped > 0 || self.text.is_some() => {
+                    self.skipped += 1;
+                }
+                Ok(Event::Start(ref e)) => {
+                    self.position = position;
+                    let element = self.elements.last().copied();
+                    let (item, opened) = match (e.name(), element) {
+                        (b"graphml", _) => continue,
+                        (b"key", _) => {
+                            self.key = Some(read_key(e, position)?);
+                            continue;
+                        }
+                        (b"default", _) if self.key.is_some() => {
+                            self.text = Some((Text::Default, String::new()));
+                            continue;
+                        }
+                        (b"desc", _) if self.key.is_some() => {
+                            // Key descriptions are left out
+                            self.skipped += 1;
+                            continue;
+                        }
+                        (b"desc", element) => {
+                            self.text = Some((Text::Desc { element }, String::new()));
+                            continue;
+                        }
+                        (b"data", element) => {
+                            let key = required_attribute(e, b"key", position, Dialect::Graphml)?;
+                            self.text = Some((Text::Data { element, key }, String::new()));
+                            continue;
+                        }
+                        (b"graph", element) => {
+                            let item = Item::GraphStart {
+                                id: get_attribute(e, b"id", position)?,
+                                directed: get_attribute(e, b"edgedefault", position)?
+                                    .map(|value| value == "directed"),
+                                nested: element == Some(Element::Node),
+                            };
+                            (item, Some(Element::Graph))
+                        }
+                        (b"node", _) => {
+                            let id = required_attribute(e, b"id", position, Dialect::Graphml)?;
+                            (Item::NodeStart { id }, Some(Element::Node))
+                        }
+                        (b"edge", _) => {
+                            let item = Item::EdgeStart {
+                                id: get_attribute(e, b"id", position)?,
+                                source: required_attribute(
+                                    e,
+                                    b"source",
+                                    position,
+                                    Dialect::Graphml,
+                                )?,
+                                target: required_attribute(
+                                    e,
+                                    b"target",
+                                    position,
+                                    Dialect::Graphml,
+                                )?,
+                                directed: edge_direction(
+                                    get_attribute(e, b"directed", position)?,
+                                    position,
+                                )?,
+                                source_port: get_attribute(e, b"sourceport", position)?,
+                                target_port: get_attribute(e, b"targetport", position)?,
+                            };
+                            (item, Some(Element::Edge))
+                        }
+                        (b"hyperedge", _) => {
+                            let id = get_attribute(e, b"id", position)?;
+                            (Item::HyperedgeStart { id }, Some(Element::Hyperedge))
+                        }
+                        (b"endpoint", Some(Element::Hyperedge)) => {
+                            let item = Item::Endpoint {
+                                node: required_attribute(e, b"node", position, Dialect::Graphml)?,
+                                port: get_attribute(e, b"port", position)?,
+                                incoming: get_attribute(e, b"type", position)?.as_deref()
+                                    == Some("in"),
+                            };
+                            (item, Some(Element::Endpoint))
+                        }
+                        (b"port", Some(Element::Node | Element::Port)) => {
+                            let name = required_attribute(e, b"name", position, Dialect::Graphml)?;
+                            (Item::PortStart { name }, Some(Element::Port))
+                        }
+                        (b"locator", None | Some(Element::Graph | Element::Node)) => {
+                            let href =
+                                required_attribute(e, b"xlink:href", position, Dialect::Graphml)?;
+                            (Item::Locator { element, href }, None)
+                        }
+                        (name, _) => {
+                            let name = String::from_utf8_lossy(name).to_string();
+                            (Item::Unsupported { name }, None)
+                        }
+                    };
+                    match opened {
+                        Some(element) => self.elements.push(element),
+                        // Locators and unsupported elements are skipped with their content
+                        None => self.skipped += 1,
+                    }
+                    return Ok(Some(item));
+                }
+                Ok(Event::End(_)) if self.skipped > 0 => self.skipped -= 1,
+                Ok(Event::End(ref e)) => {
+                    if let Some((text_of, text)) = self.text.take() {
+                        match text_of {
+                            Text::Data { element, key } => {
+                                return Ok(Some(Item::Data { element, key, text }));
+                            }
+                            Text::Desc { element } => {
+                                return Ok(Some(Item::Desc { element, text }));
+                            }
+                            Text::Default => {
+                                if let Some((_, key)) = self.key.as_mut()
+                                    && !text.is_empty()
+                                {
+                                    key.default = Some(text);
+                                }
+                                continue;
+                            }
+                        }
+                    }
+                    if e.name() == b"key" {
+                        if let Some((id, key)) = self.key.take() {
+                            self.keys.insert(id.clone(), key);
+                            return Ok(Some(Item::Key { id }));
+                        }
+                        continue;
+                    }
+                    let item = match self.elements.pop() {
+                        Some(Element::Graph) => Item::GraphEnd {
+                            nested: !self.elements.is_empty(),
+                        },
+                        Some(Element::Node) => Item::NodeEnd,
+                        Some(Element::Edge) => Item::EdgeEnd,
+                        Some(Element::Hyperedge) => Item::HyperedgeEnd,
+                        Some(Element::Port) => Item::PortEnd,
+                        Some(Element::Endpoint) | None => continue,
+                    };
+                    return Ok(Some(item));
+                }
+                // unescape and decode the text event using the reader encoding
+                Ok(Event::Text(ref e)) | Ok(Event::CData(ref e)) if self.skipped == 0 => {
+                    if let Some((_, text)) = self.text.as_mut() {
+                        let value = e
+                            .unescape_and_decode(&self.reader)
+                            .map_err(|source| ConvertError::Xml { position, source })?;
+                        text.push_str(&value);
+                    }
+                }
+                Ok(Event::Eof) => return Ok(None),
+                Err(source) => return Err(ConvertError::Xml { position, source }),
+                _ => {}
+            }
+        }
+    }
+}
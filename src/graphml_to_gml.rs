@@ -2,279 +2,420 @@
 // Note: This takes a decision to remove keys that have no values, ie. <data key="v"></data> will be omitted in the final gml
 // Todo: use COW with [u8] instead of converting to string and back when writing
 
-use quick_xml::Reader;
-use quick_xml::events::Event;
-use quick_xml::events::attributes::Attributes;
-use serde_json::Value;
+use serde_json::{Map, Value};
 use std::collections::HashMap;
-use std::fs::File;
-use std::io::{BufWriter, Write};
-use std::path::Path;
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
 use std::str;
 
+use crate::error::ConvertError;
+use crate::gml_writer::{INDENT_2, LINE_BREAK, gml_string, write_line, write_value};
+use crate::graph::AttrValue;
+use crate::graphml_parser::{Element, Item, Key, KeyValue, Parser};
+use crate::integrity::Integrity;
+use crate::multi_graph::{MultipleGraphs, graph_prefix};
+use crate::node_ids::{GmlIds, ORIGINAL_ID_ATTRIBUTE, parse_graphml_edge_id};
+use crate::stats::ConversionStats;
+use crate::summary::GraphSummary;
+
+// Data value of an element: a GML number written as is, or a json string, list or dict written as GML strings
+// and lists
+#[derive(Debug, Clone, PartialEq)]
+enum DataValue {
+    Number(String),
+    Json(Value),
+}
+
+impl From<KeyValue> for DataValue {
+    fn from(value: KeyValue) -> Self {
+        // GML value of a data value like the gml writer writes it: reals always have a decimal (ie. 1 -> 1.0),
+        // booleans are 1 or 0, and the reals GML can't represent are kept as strings
+        match value {
+            KeyValue::Json(json) => DataValue::Json(json),
+            KeyValue::Attr(AttrValue::Int(number)) => DataValue::Number(number.to_string()),
+            KeyValue::Attr(AttrValue::Float(number)) if number.is_finite() => {
+                DataValue::Number(format!("{:?}", number))
+            }
+            KeyValue::Attr(AttrValue::Float(number)) => {
+                DataValue::Json(Value::from(number.to_string()))
+            }
+            KeyValue::Attr(AttrValue::Bool(value)) => {
+                DataValue::Number(if value { "1" } else { "0" }.to_string())
+            }
+            KeyValue::Attr(value) => DataValue::Json(value.to_json()),
+        }
+    }
+}
+
+impl DataValue {
+    fn to_json(&self) -> Value {
+        // Json value of the data, for the data of the ports
+        match self {
+            DataValue::Number(number) => {
+                serde_json::from_str(number).unwrap_or_else(|_| Value::from(number.as_str()))
+            }
+            DataValue::Json(value) => value.clone(),
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 struct Node {
-    id: u32,
-    data: Vec<(String, String)>,
+    id: i64,
+    // Original GraphML id, when it can't be derived from the GML id
+    name: Option<String>,
+    data: Vec<(String, DataValue)>,
 }
 
 #[derive(Debug, Clone)]
-//struct Edge<'a> {
 struct Edge {
-    source: u32,
-    target: u32, //Cow<'a, [u8]>, //String,
-    data: Vec<(String, String)>,
+    // Edge id, or parallel edge key in a multigraph, as a gml attribute name and value
+    id: Option<(&'static str, String)>,
+    // Direction of the edge, when it differs from the graph edge default (ie. in a mixed graph)
+    directed: Option<bool>,
+    source: i64,
+    target: i64,
+    data: Vec<(String, DataValue)>,
 }
 
 #[derive(Debug, Clone)]
 struct GraphInfo {
     directed: Option<bool>,
-    data: Vec<(String, String)>,
+    data: Vec<(String, DataValue)>,
+    // Data read after the graph start was written (ie. after the first node), written before the graph end
+    late_data: Vec<(String, DataValue)>,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash, Copy)]
-enum CurrentState {
-    Graph,
-    Node,
-    Edge,
+impl GraphInfo {
+    fn add_data(&mut self, data: (String, DataValue), started: bool) {
+        if started {
+            self.late_data.push(data);
+        } else {
+            self.data.push(data);
+        }
+    }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
-enum ValueTypes {
-    String,
-    Double,
-    Float,
-    Int,
+#[derive(Debug, Clone)]
+struct Endpoint {
+    node: i64,
+    port: Option<String>,
+    // Whether the hyperedge goes into the node (type="in")
+    incoming: bool,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
-enum ForTypes {
-    Edge,
-    Node,
-    Graph,
+#[derive(Debug, Clone)]
+struct Hyperedge {
+    id: Option<String>,
+    endpoints: Vec<Endpoint>,
+    data: Vec<(String, DataValue)>,
 }
 
-#[derive(Debug, Clone)]
-struct Key {
-    attr_name: String,
-    attr_type: ValueTypes,
-    for_type: ForTypes,
+// What to do with the GraphML hyperedges, which GML has no equivalent for
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum HyperedgePolicy {
+    // Add a node for each hyperedge, with an edge to each of its endpoints
+    #[default]
+    Star,
+    // Leave the hyperedges out
+    Skip,
+    // Stop the conversion with an error
+    Error,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Options {
+    // Add the key default values to the nodes and edges that have no data value for the key
+    pub fill_defaults: bool,
+    pub hyperedges: HyperedgePolicy,
+    // Write the graphs of the document as several gml graphs or merge them
+    pub graphs: MultipleGraphs,
+    // Fail on referential integrity problems (ie. edges to undeclared nodes) instead of warning about them
+    pub strict: bool,
 }
 
-const INDENT_2: &str = "  ";
 const INDENT_4: &str = "    ";
-const LINE_BREAK: &[u8] = b"\n";
 
-fn write_graph_start(writer: &mut BufWriter<&File>, graph: &GraphInfo) {
+fn write_graph_start<W: Write>(writer: &mut W, graph: &GraphInfo) -> io::Result<()> {
     // write graph specific content first then graph data, like nodes and edges
-    writer.write(b"graph [").ok();
-    writer.write(LINE_BREAK).ok();
+    writer.write_all(b"graph [")?;
+    writer.write_all(LINE_BREAK)?;
     if let Some(value) = graph.directed {
-        writer
-            .write(format!("{}directed {}", INDENT_2, value as i8).as_bytes())
-            .ok();
-        writer.write(LINE_BREAK).ok();
+        writer.write_all(format!("{}directed {}", INDENT_2, value as i8).as_bytes())?;
+        writer.write_all(LINE_BREAK)?;
     }
-    write_data_items(writer, &graph.data, INDENT_2);
+    write_data_items(writer, &graph.data, INDENT_2)
     // Note, not closing yet, as this will be done at the end
 }
 
-fn write_graph_end(writer: &mut BufWriter<&File>) {
-    // Close graph after all nodes and edges have been written
-    writer.write(b"]").ok();
+fn write_graph_end<W: Write>(writer: &mut W, graph: &GraphInfo) -> io::Result<()> {
+    // Close graph after all nodes and edges have been written, with the graph data read after them
+    write_data_items(writer, &graph.late_data, INDENT_2)?;
+    writer.write_all(b"]")
 }
 
-fn write_edge(writer: &mut BufWriter<&File>, edge: &Edge) {
+fn write_edge<W: Write>(writer: &mut W, edge: &Edge) -> io::Result<()> {
     // write edge specific content first then edge data
-    writer.write(format!("{}edge [", INDENT_2).as_bytes()).ok();
-    writer.write_all(LINE_BREAK).expect("");
-    writer
-        .write(format!("{}source {}", INDENT_4, edge.source).as_ref())
-        .ok();
-    writer.write_all(LINE_BREAK).expect("");
-    writer
-        .write(format!("{}target {}", INDENT_4, edge.target).as_ref())
-        .ok();
-    writer.write_all(LINE_BREAK).expect("");
+    writer.write_all(format!("{}edge [", INDENT_2).as_bytes())?;
+    writer.write_all(LINE_BREAK)?;
+    if let Some(("id", id)) = &edge.id {
+        write_line(writer, INDENT_4, "id", id)?;
+    }
+    writer.write_all(format!("{}source {}", INDENT_4, edge.source).as_ref())?;
+    writer.write_all(LINE_BREAK)?;
+    writer.write_all(format!("{}target {}", INDENT_4, edge.target).as_ref())?;
+    writer.write_all(LINE_BREAK)?;
+    if let Some(directed) = edge.directed {
+        write_line(writer, INDENT_4, "directed", &(directed as i8).to_string())?;
+    }
+    if let Some(("key", key)) = &edge.id {
+        write_line(writer, INDENT_4, "key", key)?;
+    }
     // Add data in a loop
-    write_data_items(writer, &edge.data, INDENT_4);
+    write_data_items(writer, &edge.data, INDENT_4)?;
     // Close node
-    writer.write(format!("{}]", INDENT_2).as_bytes()).ok();
-    writer.write_all(LINE_BREAK).expect("");
+    writer.write_all(format!("{}]", INDENT_2).as_bytes())?;
+    writer.write_all(LINE_BREAK)
 }
 
-fn write_node(writer: &mut BufWriter<&File>, node: &Node) {
+fn write_node<W: Write>(writer: &mut W, node: &Node) -> io::Result<()> {
     // write node specific content first then node data
-    writer.write(format!("{}node [", INDENT_2).as_bytes()).ok();
-    writer.write_all(LINE_BREAK).expect("");
-    writer
-        .write(format!("{}id {}", INDENT_4, node.id).as_ref())
-        .ok();
-    writer.write_all(LINE_BREAK).expect("");
+    writer.write_all(format!("{}node [", INDENT_2).as_bytes())?;
+    writer.write_all(LINE_BREAK)?;
+    writer.write_all(format!("{}id {}", INDENT_4, node.id).as_ref())?;
+    writer.write_all(LINE_BREAK)?;
+    if let Some(name) = &node.name {
+        write_line(writer, INDENT_4, ORIGINAL_ID_ATTRIBUTE, &gml_string(name))?;
+    }
     // Add data in a loop
-    write_data_items(writer, &node.data, INDENT_4);
+    write_data_items(writer, &node.data, INDENT_4)?;
     // Close node
-    writer.write(format!("{}]", INDENT_2).as_bytes()).ok();
-    writer.write_all(LINE_BREAK).expect("");
+    writer.write_all(format!("{}]", INDENT_2).as_bytes())?;
+    writer.write_all(LINE_BREAK)
 }
 
-fn write_data_items(writer: &mut BufWriter<&File>, data: &[(String, String)], indent: &str) {
-    // Write data items, while checking if the value is encoded json to unpack into lists and dicts
+fn write_data_items<W: Write>(
+    writer: &mut W,
+    data: &[(String, DataValue)],
+    indent: &str,
+) -> io::Result<()> {
+    // Write data items, the json values are unpacked into GML lists
     for (key, value) in data.iter() {
-        // check if the value is a dict or a list, which means it starts with "{ or "[ and ends with }" or ["
-        let deserialized_value = {
-            if (value.starts_with("\"[") && value.ends_with("]\""))
-                || (value.starts_with("\"{") && value.ends_with("}\""))
-            {
-                // Remove outside quotes if that is the case
-                let mut chars = value.chars();
-                chars.next();
-                chars.next_back();
-                chars.as_str()
-            } else {
-                value
-            }
-        };
+        match value {
+            DataValue::Number(number) => write_line(writer, indent, key, number)?,
+            DataValue::Json(json) => write_value(writer, json, key, indent)?,
+        }
+    }
+    Ok(())
+}
 
-        // try parsing json and if it fails just add the whole string
-        match serde_json::from_str(deserialized_value) {
-            Result::Ok(map) => {
-                write_value(writer, &map, key, indent);
-            }
-            Result::Err(_) => {
-                let new_value = format!("{}{} {}", indent, key, deserialized_value);
-                writer.write(new_value.as_bytes()).ok();
-                writer.write_all(LINE_BREAK).expect("");
-            }
-        };
+fn edge_id(id: &str, multigraph: bool) -> (&'static str, String) {
+    // Get the gml edge id from a GraphML edge id (ie. e1 -> id 1), other ids of a multigraph are its
+    // parallel edge keys (ie. 0 -> key 0) as networkx writes them
+    match parse_graphml_edge_id(id) {
+        Some(gml_id) => ("id", gml_id.to_string()),
+        None if multigraph => match id.parse::<i64>() {
+            Ok(key) => ("key", key.to_string()),
+            Err(_) => ("key", gml_string(id)),
+        },
+        None => ("id", gml_string(id)),
     }
 }
 
-fn get_value_with_increment(key: &str, item: &Value) -> String {
-    // Generate a string with the values in the correct format with an indent
-    if item.is_f64() {
-        format!("{}{} {:?}", INDENT_4, key, item.as_f64().expect(""))
-    } else if item.is_i64() {
-        format!("{}{} {:?}", INDENT_4, key, item.as_i64().expect(""))
-    } else if item.is_string() {
-        format!("{}{} {:?}", INDENT_4, key, item.as_str().expect(""))
-    } else {
-        panic!("Could not decipher value type");
+fn checked_value(
+    value: &str,
+    key: &Key,
+    position: usize,
+    options: &Options,
+    integrity: &mut Integrity,
+) -> Result<Option<DataValue>, ConvertError> {
+    // Convert a data value from its key type like the graphml reader. In strict mode a value of another type is a
+    // problem like the integrity ones, so that a validation lists every problem, and the value is left out.
+    match key.value(value, position) {
+        Ok(value) => Ok(value.map(DataValue::from)),
+        Err(ConvertError::Graphml { message, .. }) if options.strict => {
+            integrity.add_problem(format!("{} at position {}", message, position));
+            Ok(None)
+        }
+        Err(e) => Err(e),
     }
 }
 
-fn write_value(writer: &mut BufWriter<&File>, json: &Value, label: &str, indent: &str) {
-    // Write serde value (list, dict, etc), with possible inside lists
-    // todo: this should technically be recursive...
-    // a [
-    //   y 2
-    //   z 1
-    //   dict [
-    //       a 1
-    //       b 2
-    //   ]
-    //   list_with_name [
-    //        i 1
-    //        i 2
-    //   ]
-    //   list 1
-    //   list 2
-    //   ]
-
-    match json {
-        json if json.is_array() => {
-            let array = json.as_array().expect("Issue retrieving array");
-            if array.is_empty() {
-                // Write value is string (ie. "[]" -> "[]" and not a list in GML
-                writer
-                    .write(format!("{}{} {}", indent, label, json).as_bytes())
-                    .ok();
-            } else {
-                for (index, value_string) in array.iter().enumerate() {
-                    // add value and incrementation
-                    let key_value = format!("{}{} {}", INDENT_4, label, value_string);
-                    writer.write(key_value.as_bytes()).ok();
-                    if index < array.len() - 1 {
-                        // dont add a line break after the last item
-                        writer.write_all(LINE_BREAK).ok();
-                    }
-                }
-            }
+fn fill_defaults(
+    data: &mut Vec<(String, DataValue)>,
+    keys: &HashMap<String, Key>,
+    defaults: &HashMap<String, DataValue>,
+    element: Element,
+) {
+    // Add the default value of every key for the element that has no value in the element data
+    let mut defaults: Vec<_> = defaults
+        .iter()
+        .filter_map(|(id, value)| Some((id, keys.get(id)?, value)))
+        .filter(|(_, key, _)| key.applies_to(element))
+        .filter(|(_, key, _)| {
+            data.iter()
+                .all(|(data_name, _)| *data_name != key.attr_name)
+        })
+        .collect();
+    // Sort by key id, shorter ids first so that numbered ids keep their order (ie. d2 before d10)
+    defaults.sort_by_key(|(id, _, _)| (id.len(), *id));
+    for (_, key, value) in defaults {
+        data.push((key.attr_name.clone(), value.clone()));
+    }
+}
+
+fn insert_port(ports: &mut Map<String, Value>, port: Value) {
+    // Add a port to its parent port, several ports are kept as an array which is written as repeated port lists
+    match ports.get_mut("port") {
+        Some(Value::Array(array)) => array.push(port),
+        Some(previous) => *previous = Value::Array(vec![previous.take(), port]),
+        None => {
+            ports.insert("port".to_string(), port);
         }
-        json if json.is_object() => {
-            // if the value is a dict
-            let dict_key_value = format!("{}{} [", INDENT_4, label);
-            writer.write(dict_key_value.as_bytes()).ok();
-            writer.write(LINE_BREAK).ok();
-            for (key, value) in json.as_object().expect("Value isn't valid a dict object") {
-                match value {
-                    // Note: This should be recursively calling the top function
-                    value if value.is_array() => {
-                        // add key [ into the lines
-                        for item in value.as_array().expect("").iter() {
-                            // add value and incrementation (2 spaces)
-                            let value_string = get_value_with_increment(key.as_str(), item);
-                            writer
-                                .write(format!("{}{}", INDENT_4, value_string).as_bytes())
-                                .ok();
-                            writer.write(LINE_BREAK).ok();
-                        }
-                    }
-                    _ => {
-                        // value is an object
-                        let value_string = get_value_with_increment(key.as_str(), value);
-                        writer
-                            .write(format!("{}{}", INDENT_4, value_string).as_bytes())
-                            .ok();
-                        writer.write(LINE_BREAK).ok();
-                    }
-                }
+    }
+}
+
+fn edge_ports(source: Option<String>, target: Option<String>) -> Option<(String, DataValue)> {
+    // Get the port list of an edge connected to node ports: port [ source "p0" target "p1" ]
+    let mut ports = Map::new();
+    if let Some(source) = source {
+        ports.insert("source".to_string(), Value::from(source));
+    }
+    if let Some(target) = target {
+        ports.insert("target".to_string(), Value::from(target));
+    }
+    (!ports.is_empty()).then(|| ("port".to_string(), DataValue::Json(Value::Object(ports))))
+}
+
+fn write_hyperedge<W: Write>(
+    writer: &mut W,
+    hyperedge: &Hyperedge,
+    ids: &mut GmlIds,
+    stats: &mut ConversionStats,
+) -> io::Result<()> {
+    // Write a hyperedge as a star: a node holding the hyperedge data, with an edge to each endpoint
+    let hyperedge_id = hyperedge
+        .id
+        .clone()
+        .unwrap_or_else(|| format!("hyperedge{}", stats.nodes));
+    let mut data = vec![(
+        "hyperedge".to_string(),
+        DataValue::Json(Value::from(hyperedge_id)),
+    )];
+    data.extend(hyperedge.data.iter().cloned());
+    let center = Node {
+        id: ids.insert_unnamed(),
+        name: None,
+        data,
+    };
+    write_node(writer, &center)?;
+    stats.nodes += 1;
+    for endpoint in &hyperedge.endpoints {
+        let edge = if endpoint.incoming {
+            Edge {
+                id: None,
+                directed: None,
+                source: center.id,
+                target: endpoint.node,
+                data: edge_ports(None, endpoint.port.clone())
+                    .into_iter()
+                    .collect(),
             }
-            // Close dict
-            writer.write(format!("{}]", INDENT_4).as_bytes()).ok();
-        }
-        _ => {
-            // else value is number or string or an indeterminate json type
-            let value = format!("{}{} {}", indent, label, json);
-            writer.write(value.as_bytes()).ok();
-        }
+        } else {
+            Edge {
+                id: None,
+                directed: None,
+                source: endpoint.node,
+                target: center.id,
+                data: edge_ports(endpoint.port.clone(), None)
+                    .into_iter()
+                    .collect(),
+            }
+        };
+        write_edge(writer, &edge)?;
+        stats.edges += 1;
     }
+    Ok(())
+}
 
-    writer.write(LINE_BREAK).ok();
+fn data_items(data: &[(String, DataValue)]) -> impl Iterator<Item = (&str, &str)> {
+    // Data of an element for the summary, which only reads the numbers
+    data.iter().map(|(name, value)| match value {
+        DataValue::Number(number) => (name.as_str(), number.as_str()),
+        DataValue::Json(_) => (name.as_str(), ""),
+    })
 }
 
-fn get_attribute(attributes: Attributes, search_term: &[u8]) -> Result<String, String> {
-    // Get the select attribute by keyword
-    for attr in attributes {
-        let val = attr.expect("Attribute");
-        if val.key == search_term {
-            let value_as_string = str::from_utf8(val.value.as_ref()).expect("").to_string();
-            return Ok(value_as_string);
-        }
+fn finish_node<W: Write>(
+    writer: &mut W,
+    node: &mut Node,
+    keys: &HashMap<String, Key>,
+    options: &Options,
+    document: &mut Document,
+    stats: &mut ConversionStats,
+) -> io::Result<()> {
+    // Write a completed node and clear its data for the next one
+    if options.fill_defaults {
+        fill_defaults(&mut node.data, keys, &document.defaults, Element::Node);
     }
-    Err("Error: attribute not found".to_string())
+    if let Some(summary) = &mut document.summary {
+        summary.add_node(data_items(&node.data));
+    }
+    write_node(writer, node)?;
+    stats.nodes += 1;
+    node.data.clear();
+    Ok(())
 }
 
-pub fn export_to_gml(input_graphml: &Path, output_path: &Path) {
-    // Export graphml from given path to a gml graph at output path
-    let mut in_data = false;
-    let mut current_data_key = String::new(); //&[u8]; // = "";
-    let mut keys: HashMap<String, Key> = HashMap::new();
+struct Document {
+    // State kept between the graphs of a document
+    // Default values of the keys by id, as GML values
+    defaults: HashMap<String, DataValue>,
+    // Number of top level graphs read
+    graph_index: usize,
+    integrity: Integrity,
+    // Summary of the elements, when summarizing in place of converting
+    summary: Option<GraphSummary>,
+}
+
+impl Document {
+    fn new(options: &Options) -> Self {
+        Document {
+            defaults: HashMap::new(),
+            graph_index: 0,
+            integrity: Integrity::new(options.strict, "n"),
+            summary: None,
+        }
+    }
+}
 
-    let mut reader = Reader::from_file(input_graphml).expect("Issue reading from path");
-    let output_file = File::create(output_path).expect("Unable to create file");
-    let mut writer = BufWriter::new(&output_file);
-    let mut state = CurrentState::Graph;
-    let mut buf = Vec::new();
+fn write_gml<R: BufRead, W: Write>(
+    parser: &mut Parser<R>,
+    writer: &mut W,
+    options: &Options,
+    document: &mut Document,
+    separator: bool,
+) -> Result<Option<ConversionStats>, ConvertError> {
+    // Read the graphml items and write the gml graph, with a line break before it when separator is set.
+    // With separate graphs, only the next graph is read so that it can be written to its own output.
+    // Returns None when there is no graph left in the input.
+    let mut stats = ConversionStats::default();
 
     let mut graph_info_added = false;
 
     let mut current_node = Node {
         id: 0,
+        name: None,
         data: vec![],
     };
 
+    // GML ids assigned to the GraphML node ids
+    let mut ids = GmlIds::new();
+
     let mut current_edge = Edge {
+        id: None,
+        directed: None,
         source: Default::default(),
         target: Default::default(),
         data: Default::default(),
@@ -283,187 +424,486 @@ pub fn export_to_gml(input_graphml: &Path, output_path: &Path) {
     let mut current_graph = GraphInfo {
         directed: None,
         data: vec![],
+        late_data: vec![],
     };
+    // Prefix of the node ids of the graph, when the graphs are merged
+    let mut prefix = String::new();
+    // Edge default of the graph, which differs from the written graph one for merged graphs
+    let mut edge_default = None;
+    // Whether a top level graph end was read
+    let mut graph_read = false;
 
-    loop {
-        match reader.read_event(&mut buf) {
-            Ok(Event::Decl(_)) => {
-                // Ignore the xml declaration
+    // GML ids of the nodes containing the nested graphs being read, written as gid of their nodes
+    let mut groups: Vec<i64> = Vec::new();
+    // Whether the current node has a nested graph and isn't written yet, as the nested graph data is added to it
+    let mut group_pending = false;
+    // Whether the current node is a group that is already written, once its nested graph is read
+    let mut group_closed = false;
+
+    // Ports of the current node, from the outermost to the innermost one
+    let mut ports: Vec<Map<String, Value>> = Vec::new();
+
+    let mut current_hyperedge = Hyperedge {
+        id: None,
+        endpoints: vec![],
+        data: vec![],
+    };
+    let mut hyperedges = 0;
+
+    while let Some(item) = parser.next_item()? {
+        let position = parser.position();
+        let keys = parser.keys();
+        if matches!(
+            item,
+            Item::NodeStart { .. } | Item::EdgeStart { .. } | Item::HyperedgeStart { .. }
+        ) {
+            if !graph_info_added {
+                // Add graph info when entering the first node or edge
+                if separator {
+                    writer.write_all(LINE_BREAK)?;
+                }
+                write_graph_start(writer, &current_graph)?;
+                graph_info_added = true;
             }
-            Ok(Event::Start(ref e)) => {
-                match e.name() {
-                    b"graphml" => {
-                        // ignore graphml tag attributes
-                    }
-                    b"graph" => {
-                        // Get the directed value, with default to false
-                        current_graph.directed =
-                            match get_attribute(e.html_attributes(), b"edgedefault") {
-                                Ok(value) => Some(value == "directed"),
-                                Err(_) => None,
-                            }
-                    }
-                    b"node" => {
-                        if !graph_info_added {
-                            // Add graph info when entering first node
-                            write_graph_start(&mut writer, &current_graph);
-                            graph_info_added = true;
-                        }
-                        let mut id: String = get_attribute(e.html_attributes(), b"id").expect("");
-                        // Filter non numeric chars out - ie, in graphml id is n1 and should be 1 in gml
-                        id = id.chars().filter(|c| c.is_digit(10)).collect();
-                        current_node.id = id.parse::<u32>().expect("Issue Parsing id");
-                        state = CurrentState::Node;
-                    }
-                    b"edge" => {
-                        let mut target = get_attribute(e.html_attributes(), b"target").expect("");
-                        target = target.chars().filter(|c| c.is_digit(10)).collect();
-                        current_edge.target = target.parse::<u32>().expect("Issue Parsing id");
-
-                        let mut source = get_attribute(e.html_attributes(), b"source").expect("");
-                        source = source.chars().filter(|c| c.is_digit(10)).collect();
-                        current_edge.source = source.parse::<u32>().expect("Issue Parsing id");
-                        state = CurrentState::Edge;
+            if group_pending {
+                // Add the group node before its first nested node or edge
+                finish_node(
+                    writer,
+                    &mut current_node,
+                    keys,
+                    options,
+                    document,
+                    &mut stats,
+                )?;
+                group_pending = false;
+            }
+        }
+        match item {
+            Item::Key { id } => {
+                // Check the default value of the key when it is declared
+                let key = &keys[&id];
+                if let Some(default) = &key.default
+                    && let Some(value) =
+                        checked_value(default, key, position, options, &mut document.integrity)?
+                {
+                    document.defaults.insert(id, value);
+                }
+            }
+            Item::GraphStart { nested: true, .. } => {
+                // Nested graph, its nodes are written with the gid of the node containing it
+                current_node.data.insert(
+                    0,
+                    ("isGroup".to_string(), DataValue::Number("1".to_string())),
+                );
+                groups.push(current_node.id);
+                group_pending = true;
+            }
+            Item::GraphStart { id, directed, .. } if graph_info_added => {
+                // Next graph merged into the first one, its ids are namespaced
+                prefix = graph_prefix(document.graph_index, id.as_deref());
+                edge_default = directed;
+            }
+            Item::GraphStart { id, directed, .. } => {
+                if document.graph_index > 0 {
+                    // Separate graph after the first one, read back its namespaced ids
+                    ids = GmlIds::with_prefix(graph_prefix(document.graph_index, id.as_deref()));
+                }
+                current_graph.directed = directed;
+                edge_default = directed;
+                if let Some(summary) = &mut document.summary {
+                    summary.set_directed(directed);
+                }
+            }
+            Item::GraphEnd { nested: true } => {
+                // End of a nested graph, back in the group node which is already written
+                groups.pop();
+                if group_pending {
+                    finish_node(
+                        writer,
+                        &mut current_node,
+                        keys,
+                        options,
+                        document,
+                        &mut stats,
+                    )?;
+                    group_pending = false;
+                }
+                group_closed = true;
+            }
+            Item::GraphEnd { nested: false } => {
+                if !graph_info_added {
+                    // Add graph info it never added (ie, no nodes present)
+                    if separator {
+                        writer.write_all(LINE_BREAK)?;
                     }
+                    write_graph_start(writer, &current_graph)?;
+                    graph_info_added = true;
+                }
+                graph_read = true;
+                document.graph_index += 1;
+                document.integrity.end_graph();
+                if options.graphs == MultipleGraphs::Separate {
+                    write_graph_end(writer, &current_graph)?;
+                    break;
+                }
+            }
+            Item::NodeStart { id } => {
+                let id = format!("{}{}", prefix, id);
+                document
+                    .integrity
+                    .add_node(&id, || format!("position {}", position));
+                current_node.id = ids.get_or_insert(&id);
+                current_node.name = ids
+                    .original_id(&id, current_node.id)
+                    .map(ToString::to_string);
+                if let Some(gid) = groups.last() {
+                    current_node
+                        .data
+                        .push(("gid".to_string(), DataValue::Number(gid.to_string())));
+                }
+            }
+            Item::NodeEnd => {
+                if group_closed {
+                    group_closed = false;
+                    current_node.data.clear();
+                } else {
+                    finish_node(
+                        writer,
+                        &mut current_node,
+                        keys,
+                        options,
+                        document,
+                        &mut stats,
+                    )?;
+                }
+            }
+            Item::EdgeStart {
+                id,
+                source,
+                target,
+                directed,
+                source_port,
+                target_port,
+            } => {
+                let target = format!("{}{}", prefix, target);
+                document
+                    .integrity
+                    .add_reference(&target, || format!("position {}", position));
+                current_edge.target = ids.get_or_insert(&target);
 
-                    b"data" => {
-                        // get the key value when entering a data tag
-                        current_data_key = get_attribute(e.html_attributes(), b"key").expect("");
-                        in_data = true;
-                    }
-                    _ => {
-                        panic!(
-                            "Unsupported tag value {:?}",
-                            str::from_utf8(e.name()).expect("")
-                        )
+                let source = format!("{}{}", prefix, source);
+                document
+                    .integrity
+                    .add_reference(&source, || format!("position {}", position));
+                current_edge.source = ids.get_or_insert(&source);
+                let multigraph = current_graph.data.iter().any(|(name, value)| {
+                    name == "multigraph" && *value == DataValue::Number("1".to_string())
+                });
+                // Only the directions that differ from the edge default are written
+                current_edge.directed = directed
+                    .or(edge_default)
+                    .filter(|&directed| directed != current_graph.directed.unwrap_or(false));
+                current_edge.id = id.map(|id| edge_id(ids.strip_prefix(&id), multigraph));
+                current_edge
+                    .data
+                    .extend(edge_ports(source_port, target_port));
+            }
+            Item::EdgeEnd => {
+                if options.fill_defaults {
+                    fill_defaults(
+                        &mut current_edge.data,
+                        keys,
+                        &document.defaults,
+                        Element::Edge,
+                    );
+                }
+                if let Some(summary) = &mut document.summary {
+                    let directed = current_edge.directed.or(current_graph.directed);
+                    summary.add_edge(
+                        &current_edge.source.to_string(),
+                        &current_edge.target.to_string(),
+                        directed.unwrap_or(false),
+                        data_items(&current_edge.data),
+                    );
+                }
+                write_edge(writer, &current_edge)?;
+                stats.edges += 1;
+                current_edge.data.clear();
+            }
+            Item::PortStart { name } => {
+                // Ports are kept as node lists: port [ name "p0" ]
+                let mut port = Map::new();
+                port.insert("name".to_string(), Value::from(name));
+                ports.push(port);
+            }
+            Item::PortEnd => {
+                let port = Value::Object(ports.pop().unwrap_or_default());
+                match ports.last_mut() {
+                    // Nested port, add it to its parent port
+                    Some(parent) => insert_port(parent, port),
+                    None => current_node
+                        .data
+                        .push(("port".to_string(), DataValue::Json(port))),
+                }
+            }
+            Item::HyperedgeStart { id } => {
+                if options.hyperedges == HyperedgePolicy::Error {
+                    return Err(ConvertError::Graphml {
+                        position,
+                        message: "Hyperedges can't be converted to gml".to_string(),
+                    });
+                }
+                current_hyperedge.id = id;
+            }
+            Item::Endpoint {
+                node,
+                port,
+                incoming,
+            } => {
+                let node = format!("{}{}", prefix, node);
+                document
+                    .integrity
+                    .add_reference(&node, || format!("position {}", position));
+                current_hyperedge.endpoints.push(Endpoint {
+                    node: ids.get_or_insert(&node),
+                    port,
+                    incoming,
+                });
+            }
+            Item::HyperedgeEnd => {
+                hyperedges += 1;
+                if options.hyperedges == HyperedgePolicy::Star {
+                    if options.fill_defaults {
+                        fill_defaults(
+                            &mut current_hyperedge.data,
+                            keys,
+                            &document.defaults,
+                            Element::Hyperedge,
+                        );
                     }
+                    write_hyperedge(writer, &current_hyperedge, &mut ids, &mut stats)?;
                 }
+                current_hyperedge.endpoints.clear();
+                current_hyperedge.data.clear();
             }
-            Ok(Event::End(ref e)) => {
-                match e.name() {
-                    b"graph" => {
-                        if !graph_info_added {
-                            // Add graph info it never added (ie, no nodes present)
-                            // TODO: this should be more robust
-                            write_graph_start(&mut writer, &current_graph);
-                            graph_info_added = true;
+            Item::Locator { element, href } => {
+                // A graph or node defined in another document: <locator xlink:href="other.graphml"/>
+                let locator = ("locator".to_string(), DataValue::Json(Value::from(href)));
+                match element {
+                    Some(Element::Node) => current_node.data.push(locator),
+                    _ if !groups.is_empty() => {
+                        if group_pending {
+                            current_node.data.push(locator);
                         }
-                        write_graph_end(&mut writer);
-                    }
-                    b"node" => {
-                        write_node(&mut writer, &current_node);
-                        state = CurrentState::Graph;
-                        current_node.data.clear();
                     }
-                    b"edge" => {
-                        write_edge(&mut writer, &current_edge);
-                        state = CurrentState::Graph;
-                        current_edge.data.clear();
-                    }
-                    b"data" => {
-                        // Exit data state
-                        in_data = false;
-                    }
-                    _ => (),
+                    _ => current_graph.add_data(locator, graph_info_added),
                 }
             }
-            Ok(Event::Empty(ref e)) => {
-                match e.name() {
-                    b"key" => {
-                        let mut new_key = Key {
-                            attr_name: "".to_string(),
-                            attr_type: ValueTypes::String,
-                            for_type: ForTypes::Edge,
-                        };
-                        let mut key_id = "".to_string();
-                        for attr in e.html_attributes() {
-                            let val = attr.expect("Attribute");
-                            match val.key {
-                                b"attr.name" => {
-                                    new_key.attr_name =
-                                        str::from_utf8(val.value.as_ref()).expect("").to_string()
-                                }
-                                b"id" => {
-                                    key_id =
-                                        str::from_utf8(val.value.as_ref()).expect("").to_string()
-                                }
-                                b"attr.type" => match val.value.as_ref() {
-                                    b"string" => new_key.attr_type = ValueTypes::String,
-                                    b"double" => new_key.attr_type = ValueTypes::Double,
-                                    b"float" => new_key.attr_type = ValueTypes::Float,
-                                    b"int" => new_key.attr_type = ValueTypes::Int,
-                                    _ => panic!("Error: Unrecognized value type!"),
-                                },
-                                b"for" => match val.value.as_ref() {
-                                    b"edge" => new_key.for_type = ForTypes::Edge,
-                                    b"node" => new_key.for_type = ForTypes::Node,
-                                    b"graph" => new_key.for_type = ForTypes::Graph,
-                                    _ => panic!("This for type is unsupported!"),
-                                },
-                                _ => (),
-                            };
+            Item::Desc { element, text } => {
+                // Descriptions are kept as desc attributes, and the document one as a top level comment
+                let value = text.trim();
+                if value.is_empty() {
+                    continue;
+                }
+                let desc = ("desc".to_string(), DataValue::Json(Value::from(value)));
+                match element {
+                    None => {
+                        if !graph_info_added {
+                            write_line(writer, "", "comment", &gml_string(value))?;
                         }
-                        keys.insert(key_id.clone(), new_key.clone());
                     }
-                    b"data" => {
-                        // Ignore empty data tags
-                    }
-                    b"node" => {
-                        // Ignore empty node tags
+                    // Description of a nested graph, kept in its group node
+                    Some(Element::Graph) if !groups.is_empty() => {
+                        if group_pending {
+                            current_node.data.push(desc);
+                        }
                     }
-                    b"edge" => {
-                        // Ignore empty edge tags
+                    Some(Element::Graph) => current_graph.add_data(desc, graph_info_added),
+                    Some(Element::Node) => current_node.data.push(desc),
+                    Some(Element::Edge) => current_edge.data.push(desc),
+                    Some(Element::Hyperedge) => current_hyperedge.data.push(desc),
+                    Some(Element::Port) => {
+                        if let Some(port) = ports.last_mut() {
+                            port.insert(desc.0, Value::from(value));
+                        }
                     }
-                    _ => (),
+                    Some(Element::Endpoint) => (),
                 }
             }
-            // unescape and decode the text event using the reader encoding
-            Ok(Event::Text(e)) => {
-                // Extract the string data if in a data tag inside a node, edge or graph only
-                if !in_data {
-                    // Ignore text when not in data tag
+            Item::Data { element, key, text } => {
+                // Data of an undeclared key is skipped
+                let Some(key) = keys.get(&key) else {
+                    document
+                        .integrity
+                        .add_problem(format!("Undeclared key {:?} at position {}", key, position));
                     continue;
-                }
-                let mut value = e.unescape_and_decode(&reader).expect("Error getting value");
-
-                if value.is_empty() || value == "\"\"" {
-                    // Skip empty values
+                };
+                let Some(value) =
+                    checked_value(&text, key, position, options, &mut document.integrity)?
+                else {
                     continue;
-                }
-                // Get the attribute name and type from the current data key
-                let key = keys.get(&*current_data_key).expect("Issue getting key");
-                if key.attr_type == ValueTypes::String {
-                    // Add quotes around value if it's a string
-                    value = format!("\"{}\"", value);
-                }
-                if [ValueTypes::Float, ValueTypes::Double].contains(&key.attr_type)
-                    && !value.contains('.')
-                {
-                    // Add 1 decimal 0 to a value if it is formatted like an int ie. 1 -> 1.0
-                    // This is because GML floats always have a decimal.
-                    value = format!("{:.1}", value.parse::<f64>().expect("Issue parsing value"));
-                }
-                match state {
-                    CurrentState::Graph => {
-                        current_graph.data.push((key.attr_name.clone(), value));
-                    }
-                    CurrentState::Node => {
-                        current_node.data.push((key.attr_name.clone(), value));
+                };
+                let data = (key.attr_name.clone(), value);
+                match element {
+                    // Data of a nested graph, kept in its group node
+                    Some(Element::Graph) | None if !groups.is_empty() => {
+                        if group_pending {
+                            current_node.data.push(data);
+                        }
                     }
-                    CurrentState::Edge => {
-                        current_edge.data.push((key.attr_name.clone(), value));
+                    Some(Element::Graph) | None => current_graph.add_data(data, graph_info_added),
+                    Some(Element::Node) => current_node.data.push(data),
+                    Some(Element::Edge) => current_edge.data.push(data),
+                    Some(Element::Hyperedge) => current_hyperedge.data.push(data),
+                    Some(Element::Port) => {
+                        if let Some(port) = ports.last_mut() {
+                            port.insert(data.0, data.1.to_json());
+                        }
                     }
-                };
+                    Some(Element::Endpoint) => (),
+                }
             }
-            Ok(Event::Eof) => break, // exit the loop when reaching end of file
-            Err(e) => {
-                // Propagate error
-                panic!("Error at position {}: {:?}", reader.buffer_position(), e)
+            Item::Unsupported { name } => {
+                return Err(ConvertError::Graphml {
+                    position,
+                    message: format!("Unsupported tag <{}>", name),
+                });
             }
-            _ => (), // Ignore other Events
         }
+    }
+    if graph_info_added && options.graphs == MultipleGraphs::Merge {
+        // Close the merged graphs
+        write_graph_end(writer, &current_graph)?;
+    }
+    if hyperedges > 0 {
+        stats.warnings.push(match options.hyperedges {
+            HyperedgePolicy::Skip => format!("Skipped {} hyperedges", hyperedges),
+            _ => format!(
+                "Converted {} hyperedges to star nodes and edges",
+                hyperedges
+            ),
+        });
+    }
+    writer.flush()?;
+    Ok((graph_read || graph_info_added).then_some(stats))
+}
+
+pub fn convert<R: BufRead, W: Write>(input: R, output: W) -> Result<ConversionStats, ConvertError> {
+    // Convert a graphml input to a gml output
+    convert_with_options(input, output, &Options::default())
+}
+
+pub fn convert_with_options<R: BufRead, W: Write>(
+    input: R,
+    output: W,
+    options: &Options,
+) -> Result<ConversionStats, ConvertError> {
+    // Convert a graphml input to a gml output with the given options
+    convert_document(input, output, options, &mut Document::new(options))
+}
 
-        // if we don't keep a borrow elsewhere, we can clear the buffer to keep memory usage low
-        buf.clear();
+fn convert_document<R: BufRead, W: Write>(
+    input: R,
+    output: W,
+    options: &Options,
+    document: &mut Document,
+) -> Result<ConversionStats, ConvertError> {
+    let mut parser = Parser::new(input);
+    let mut writer = BufWriter::new(output);
+    let mut stats = ConversionStats::default();
+    // Separate graphs are written one after the other
+    loop {
+        let separator = document.graph_index > 0;
+        match write_gml(&mut parser, &mut writer, options, document, separator)? {
+            Some(graph_stats) => stats.merge(graph_stats),
+            None => break,
+        }
     }
+    if let Some(summary) = &mut document.summary {
+        // Types of the keys, json keys (without an attr.type) are summarized as strings
+        for key in parser.keys().values() {
+            let elements: &[&str] = match key.domain {
+                Some(Element::Node) => &["node"],
+                Some(Element::Edge) => &["edge"],
+                None => &["node", "edge"],
+                _ => &[],
+            };
+            for element in elements {
+                summary.add_key(
+                    element,
+                    &key.attr_name,
+                    &key.attr_name,
+                    key.attr_type.value().unwrap_or("string"),
+                );
+            }
+        }
+    }
+    document.integrity.finish(&mut stats)?;
+    Ok(stats)
+}
+
+pub fn summarize<R: BufRead>(input: R) -> Result<GraphSummary, ConvertError> {
+    // Summarize a graphml input with the types of its keys, without writing anything
+    let options = Options::default();
+    let mut document = Document::new(&options);
+    document.summary = Some(GraphSummary::new());
+    convert_document(input, io::sink(), &options, &mut document)?;
+    Ok(document.summary.take().unwrap_or_default())
+}
+
+fn indexed_path(path: &Path, index: usize) -> PathBuf {
+    // Get the path of the file of a graph after the first one (ie. graph.gml -> graph.1.gml)
+    let mut file_name = path.file_stem().unwrap_or_default().to_os_string();
+    file_name.push(format!(".{}", index));
+    if let Some(extension) = path.extension() {
+        file_name.push(".");
+        file_name.push(extension);
+    }
+    path.with_file_name(file_name)
+}
+
+pub fn export_to_gml_files(
+    input_graphml: &Path,
+    output_path: &Path,
+    options: &Options,
+) -> Result<ConversionStats, ConvertError> {
+    // Export each graph of the graphml file to its own gml file: the first one at the output path and the next
+    // ones with their index (ie. graph.gml, graph.1.gml, graph.2.gml)
+    let mut parser = Parser::new(BufReader::new(File::open(input_graphml)?));
+    let options = Options {
+        graphs: MultipleGraphs::Separate,
+        ..options.clone()
+    };
+    let mut document = Document::new(&options);
+    let mut stats = ConversionStats::default();
+    loop {
+        let path = match document.graph_index {
+            0 => output_path.to_path_buf(),
+            index => indexed_path(output_path, index),
+        };
+        let mut writer = BufWriter::new(File::create(&path)?);
+        match write_gml(&mut parser, &mut writer, &options, &mut document, false)? {
+            Some(graph_stats) => stats.merge(graph_stats),
+            None => {
+                // No graph left, only the first file is kept when the input has no graph
+                if document.graph_index > 0 {
+                    drop(writer);
+                    fs::remove_file(&path)?;
+                }
+                break;
+            }
+        }
+    }
+    document.integrity.finish(&mut stats)?;
+    Ok(stats)
+}
+
+pub fn export_to_gml(
+    input_graphml: &Path,
+    output_path: &Path,
+) -> Result<ConversionStats, ConvertError> {
+    // Export graphml from given path to a gml graph at output path
+    let input_file = File::open(input_graphml)?;
+    let output_file = File::create(output_path)?;
+    convert(BufReader::new(input_file), output_file)
 }
@@ -0,0 +1,91 @@
+// Referential integrity checks: edges refer to declared nodes, node ids are unique and data refers to
+// declared keys. In strict mode the problems make the conversion fail once the input is read, otherwise
+// they are reported as warnings.
+// Only the node ids and the references to nodes that are not declared yet are kept, so that memory stays
+// bounded for large graphs where edges come after their nodes.
+
+use std::collections::HashMap;
+use std::mem;
+
+use crate::error::ConvertError;
+use crate::node_ids::NodeIdSet;
+use crate::stats::ConversionStats;
+
+// Number of problems listed, the next ones are only counted
+const MAX_PROBLEMS: usize = 100;
+
+#[derive(Debug)]
+pub struct Integrity {
+    strict: bool,
+    nodes: NodeIdSet,
+    // References to undeclared nodes with the location of the first one, nodes can be declared after their edges
+    dangling: HashMap<String, String>,
+    problems: Vec<String>,
+    problem_count: usize,
+}
+
+impl Integrity {
+    pub fn new(strict: bool, number_prefix: &'static str) -> Self {
+        // The number prefix is the prefix of the numbered node ids of the format (ie. n for GraphML)
+        Integrity {
+            strict,
+            nodes: NodeIdSet::new(number_prefix),
+            dangling: HashMap::new(),
+            problems: Vec::new(),
+            problem_count: 0,
+        }
+    }
+
+    pub fn add_problem(&mut self, problem: String) {
+        self.problem_count += 1;
+        if self.problems.len() < MAX_PROBLEMS {
+            self.problems.push(problem);
+        }
+    }
+
+    pub fn add_node(&mut self, id: &str, location: impl FnOnce() -> String) {
+        // Declare a node, the location is used to report a duplicate id
+        if !self.nodes.insert(id) {
+            self.add_problem(format!("Duplicate node id {:?} at {}", id, location()));
+        }
+        self.dangling.remove(id);
+    }
+
+    pub fn add_reference(&mut self, id: &str, location: impl FnOnce() -> String) {
+        // Reference a node from an edge (or a hyperedge endpoint), which must be declared in the same graph
+        if !self.nodes.contains(id) && !self.dangling.contains_key(id) {
+            self.dangling.insert(id.to_string(), location());
+        }
+    }
+
+    pub fn end_graph(&mut self) {
+        // Report the references to nodes never declared in the graph, the next graph has its own nodes
+        let mut dangling: Vec<(String, String)> = self.dangling.drain().collect();
+        dangling.sort();
+        for (id, location) in dangling {
+            self.add_problem(format!(
+                "Edge at {} refers to undeclared node {:?}",
+                location, id
+            ));
+        }
+        self.nodes.clear();
+    }
+
+    pub fn finish(&mut self, stats: &mut ConversionStats) -> Result<(), ConvertError> {
+        // Fail with the problems in strict mode, or add them to the warnings
+        self.end_graph();
+        let mut problems = mem::take(&mut self.problems);
+        if self.problem_count > problems.len() {
+            problems.push(format!(
+                "{} more problems",
+                self.problem_count - problems.len()
+            ));
+        }
+        self.problem_count = 0;
+        if self.strict && !problems.is_empty() {
+            return Err(ConvertError::Integrity { problems });
+        }
+        stats.warnings.extend(problems);
+        Ok(())
+    }
+}
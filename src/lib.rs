@@ -1,2 +1,23 @@
+pub mod attr_types;
+pub mod cursor;
+pub mod dot;
+pub mod dot_lexer;
+pub mod error;
+pub mod format;
+pub mod gexf;
+pub mod gml;
+pub mod gml_lexer;
+pub mod gml_parser;
 pub mod gml_to_graphml;
+pub mod gml_writer;
+pub mod graph;
+pub mod graphml;
+pub mod graphml_parser;
 pub mod graphml_to_gml;
+pub mod integrity;
+pub mod multi_graph;
+pub mod node_ids;
+pub mod stats;
+pub mod summary;
+pub mod validate;
+pub mod xml;
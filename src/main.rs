@@ -5,40 +5,280 @@ Details:
 Keep none as empty attributes (like networkx)
 Convert lists to xml lists (unlike networkx which crashes for this step)
 
-Usage: graphconverter input_path output_path
+Usage: graphconverter [--from format] [--to format] [--write-defaults] [--fill-defaults]
+    [--hyperedges star|skip|error] [--graphs separate|merge] [--split-graphs] [--strict] input_path output_path
+Use - as the input or output path to read from stdin or write to stdout, the formats are then
+given by --from and --to. Progress messages are written to stderr so stdout can be piped.
+The formats are the names of the format registry (gml, graphml, gexf, dot), detected from the file extensions by default.
+Every conversion goes through the format registry: gml and graphml convert to each other with their converters,
+any other pair of formats through graph events, which the options below don't apply to (except --hyperedges for
+GraphML inputs).
+--write-defaults writes the most common node and edge values as GraphML key defaults (gml to graphml),
+--fill-defaults adds the GraphML key defaults to the nodes and edges without a value (graphml to gml).
+--hyperedges sets how GraphML hyperedges are converted: a node with an edge to each endpoint (star, the default),
+left out (skip) or as an error.
+--graphs sets how several top level graphs are converted: kept as separate graphs (the default) or merged into one,
+with the node ids of the next graphs prefixed by their graph id. --split-graphs writes each graph of a GraphML file
+to its own gml file (graph.gml, graph.1.gml, ...).
+Edges to undeclared nodes, duplicate node ids and data of undeclared keys are reported as warnings, --strict makes
+the conversion fail with all of them instead.
+
+Usage: graphconverter validate [--from gml|graphml] path (or - for stdin)
+Checks the syntax, key declarations, data value types and edges of a file. A json report is printed to stdout
+and the exit status is 1 when the file is invalid.
+
+Usage: graphconverter info [--from gml|graphml] path (or - for stdin)
+Prints a json summary of a file without converting it: node and edge counts, directedness, self loops,
+parallel edges, and for each key its type, the share of elements having it and its numeric min and max.
 
 URLS for info
 https://stackoverflow.com/questions/45882329/read-large-files-line-by-line-in-rust
 https://depth-first.com/articles/2020/07/20/reading-sd-files-in-rust/
  */
 
-use std::ffi::OsStr;
-use std::path::PathBuf;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::process;
 use std::time::Instant;
 
-use clap::{value_parser, Arg, Command};
-use graph_converter::{gml_to_graphml, graphml_to_gml};
+use clap::parser::ValueSource;
+use clap::{Arg, ArgAction, ArgMatches, Command, value_parser};
+use graph_converter::error::ConvertError;
+use graph_converter::format::Registry;
+use graph_converter::graphml_to_gml::HyperedgePolicy;
+use graph_converter::multi_graph::MultipleGraphs;
+use graph_converter::stats::ConversionStats;
+use graph_converter::validate::{validate, validate_file};
+use graph_converter::{gml, gml_to_graphml, graphml, graphml_to_gml};
+
+const STDIO_PATH: &str = "-";
+
+fn format_of(registry: &Registry, path: &Path) -> Option<String> {
+    // Detect the format from the file extension, among the registered formats
+    registry.format_of(path).map(|format| format.name.clone())
+}
+
+fn converter_format_of(path: &Path) -> Option<String> {
+    // Detect the format of a file to validate or summarize, which only gml and graphml support
+    format_of(&Registry::new(), path).filter(|format| ["gml", "graphml"].contains(&format.as_str()))
+}
+
+fn convert(
+    input_path: &Path,
+    output_path: &Path,
+    from: &str,
+    to: &str,
+    registry: &Registry,
+    graphml_options: &graphml_to_gml::Options,
+    split_graphs: bool,
+) -> Result<ConversionStats, ConvertError> {
+    // Convert between the given paths, where - is stdin or stdout
+    if split_graphs {
+        return graphml_to_gml::export_to_gml_files(input_path, output_path, graphml_options);
+    }
+    // The input is opened first, so that a missing input doesn't leave an empty output file
+    let input_file = if input_path == Path::new(STDIO_PATH) {
+        None
+    } else {
+        Some(BufReader::new(File::open(input_path)?))
+    };
+    let output: Box<dyn Write> = if output_path == Path::new(STDIO_PATH) {
+        Box::new(io::stdout().lock())
+    } else {
+        Box::new(File::create(output_path)?)
+    };
+    let mut output = BufWriter::new(output);
+    let stats = match input_file {
+        // Stdin can't be read twice, so the gml converter buffers the graph in a temp file
+        None => registry.convert(from, to, Box::new(io::stdin().lock()), &mut output)?,
+        Some(input_file) => registry.convert_file(from, to, input_file, &mut output)?,
+    };
+    // Flush before reporting success, as dropping the buffer would ignore a failed write
+    output.flush()?;
+    Ok(stats)
+}
+
+fn validate_command(matches: &ArgMatches) {
+    // Print the validation report of a file as json, and exit with a failure status when it is invalid
+    let path = matches
+        .get_one::<PathBuf>("PATH")
+        .expect("required by clap");
+    let Some(format) = matches
+        .get_one::<String>("FROM")
+        .cloned()
+        .or_else(|| converter_format_of(path))
+    else {
+        eprintln!("Error: Input format could not be detected, use --from gml or --from graphml");
+        process::exit(2);
+    };
+    let report = if path == Path::new(STDIO_PATH) {
+        validate(io::stdin().lock(), &format)
+    } else {
+        validate_file(path, &format)
+    };
+    println!("{:#}", report.to_json(path));
+    if !report.is_valid() {
+        process::exit(1);
+    }
+}
+
+fn info_command(matches: &ArgMatches) {
+    // Print the summary of a file as json
+    let path = matches
+        .get_one::<PathBuf>("PATH")
+        .expect("required by clap");
+    let Some(format) = matches
+        .get_one::<String>("FROM")
+        .cloned()
+        .or_else(|| converter_format_of(path))
+    else {
+        eprintln!("Error: Input format could not be detected, use --from gml or --from graphml");
+        process::exit(2);
+    };
+    let input: Box<dyn BufRead> = if path == Path::new(STDIO_PATH) {
+        Box::new(io::stdin().lock())
+    } else {
+        match File::open(path) {
+            Ok(file) => Box::new(BufReader::new(file)),
+            Err(e) => {
+                eprintln!("Error: {}", ConvertError::from(e));
+                process::exit(1);
+            }
+        }
+    };
+    let summary = match format.as_str() {
+        "gml" => gml_to_graphml::summarize(input),
+        _ => graphml_to_gml::summarize(input),
+    };
+    match summary {
+        Ok(summary) => println!("{:#}", summary.to_json()),
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            process::exit(1);
+        }
+    }
+}
 
 fn main() {
+    let registry = Registry::new();
+    let format_names = registry.names().collect::<Vec<_>>().join(", ");
     let matches = Command::new("Graph converter")
         .version("0.1.3")
-        .about("Graph file converter between gml and graphml formats")
+        .about(format!("Graph file converter between the {} formats", format_names))
+        .args_conflicts_with_subcommands(true)
+        .subcommand_negates_reqs(true)
+        .subcommand(
+            Command::new("validate")
+                .about("Validates a gml or graphml file and prints a json report")
+                .arg(
+                    Arg::new("PATH")
+                        .help("Sets the file path to validate, or - for stdin")
+                        .required(true)
+                        .index(1)
+                        .value_parser(value_parser!(PathBuf)),
+                )
+                .arg(
+                    Arg::new("FROM")
+                        .long("from")
+                        .help("Sets the file format, detected from the extension by default")
+                        .value_parser(["gml", "graphml"]),
+                ),
+        )
         .arg(
             Arg::new("INPUT")
-                .help("Sets the input file path to use")
+                .help("Sets the input file path to use, or - for stdin")
                 .required(true)
                 .index(1)
                 .value_parser(value_parser!(PathBuf)),
         )
         .arg(
             Arg::new("OUTPUT")
-                .help("Sets the output file path to use")
+                .help("Sets the output file path to use, or - for stdout")
                 .required(true)
                 .index(2)
                 .value_parser(value_parser!(PathBuf)),
         )
+        .arg(
+            Arg::new("FROM")
+                .long("from")
+                .help(format!(
+                    "Sets the input format ({}), detected from the input extension by default",
+                    format_names
+                )),
+        )
+        .arg(
+            Arg::new("TO")
+                .long("to")
+                .help(format!(
+                    "Sets the output format ({}), from the output extension or the other of gml and graphml by default",
+                    format_names
+                )),
+        )
+        .arg(
+            Arg::new("WRITE_DEFAULTS")
+                .long("write-defaults")
+                .help("Writes the most common node and edge values as GraphML key defaults")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("FILL_DEFAULTS")
+                .long("fill-defaults")
+                .help("Adds the GraphML key defaults to the nodes and edges without a value")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("HYPEREDGES")
+                .long("hyperedges")
+                .help("Sets how GraphML hyperedges are converted")
+                .value_parser(["star", "skip", "error"])
+                .default_value("star"),
+        )
+        .arg(
+            Arg::new("GRAPHS")
+                .long("graphs")
+                .help("Sets whether several top level graphs are kept separate or merged into one")
+                .value_parser(["separate", "merge"])
+                .default_value("separate"),
+        )
+        .arg(
+            Arg::new("SPLIT_GRAPHS")
+                .long("split-graphs")
+                .help("Writes each GraphML graph to its own gml file, with the graph index before the extension")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("STRICT")
+                .long("strict")
+                .help("Fails on edges to undeclared nodes, duplicate node ids or data of undeclared keys")
+                .action(ArgAction::SetTrue),
+        )
+        .subcommand(
+            Command::new("info")
+                .visible_alias("stats")
+                .about("Prints a json summary of a gml or graphml file")
+                .arg(
+                    Arg::new("PATH")
+                        .help("Sets the file path to summarize, or - for stdin")
+                        .required(true)
+                        .index(1)
+                        .value_parser(value_parser!(PathBuf)),
+                )
+                .arg(
+                    Arg::new("FROM")
+                        .long("from")
+                        .help("Sets the file format, detected from the extension by default")
+                        .value_parser(["gml", "graphml"]),
+                ),
+        )
         .get_matches();
 
+    match matches.subcommand() {
+        Some(("validate", validate_matches)) => return validate_command(validate_matches),
+        Some(("info", info_matches)) => return info_command(info_matches),
+        _ => {}
+    }
+
     let input_path = matches
         .get_one::<PathBuf>("INPUT")
         .expect("required by clap");
@@ -46,25 +286,132 @@ fn main() {
         .get_one::<PathBuf>("OUTPUT")
         .expect("required by clap");
 
-    println!("Using input file path: {}", input_path.display());
+    eprintln!("Using input file path: {}", input_path.display());
+
+    // Get the formats from the flags, or from the file extensions
+    let Some(from) = matches
+        .get_one::<String>("FROM")
+        .cloned()
+        .or_else(|| format_of(&registry, input_path))
+    else {
+        eprintln!(
+            "Error: Input format could not be detected, use --from with one of {}",
+            format_names
+        );
+        process::exit(2);
+    };
+    let to = matches
+        .get_one::<String>("TO")
+        .cloned()
+        .or_else(|| format_of(&registry, output_path))
+        .unwrap_or_else(|| if from == "gml" { "graphml" } else { "gml" }.to_string());
+    for format in [&from, &to] {
+        if registry.get(format).is_none() {
+            eprintln!(
+                "Error: Unknown format {}, use one of {}",
+                format, format_names
+            );
+            process::exit(2);
+        }
+    }
+
+    let graphs = match matches.get_one::<String>("GRAPHS").map(String::as_str) {
+        Some("merge") => MultipleGraphs::Merge,
+        _ => MultipleGraphs::Separate,
+    };
+    let split_graphs = matches.get_flag("SPLIT_GRAPHS");
+    let stdio = [input_path, output_path].contains(&&PathBuf::from(STDIO_PATH));
+    if split_graphs && (from != "graphml" || to != "gml" || stdio) {
+        eprintln!("Error: --split-graphs needs graphml input and gml output files");
+        process::exit(2);
+    }
+    if split_graphs && graphs == MultipleGraphs::Merge {
+        eprintln!("Error: --split-graphs can't be used with --graphs merge");
+        process::exit(2);
+    }
+
+    // The options of the gml and graphml converters don't apply to the other conversions, except the hyperedge
+    // policy which the GraphML reader follows too
+    let converters = matches!(
+        (from.as_str(), to.as_str()),
+        ("gml", "graphml") | ("graphml", "gml")
+    );
+    let misused = [
+        (
+            "WRITE_DEFAULTS",
+            from == "gml" && to == "graphml",
+            "conversions from gml to graphml",
+        ),
+        (
+            "FILL_DEFAULTS",
+            from == "graphml" && to == "gml",
+            "conversions from graphml to gml",
+        ),
+        ("GRAPHS", converters, "conversions between gml and graphml"),
+        ("STRICT", converters, "conversions between gml and graphml"),
+        ("HYPEREDGES", from == "graphml", "conversions from graphml"),
+    ]
+    .into_iter()
+    .find(|(id, applies, _)| {
+        !applies && matches.value_source(id) == Some(ValueSource::CommandLine)
+    });
+    if let Some((id, _, applies_to)) = misused {
+        eprintln!(
+            "Error: --{} only applies to {}",
+            id.to_lowercase().replace('_', "-"),
+            applies_to
+        );
+        process::exit(2);
+    }
+
+    let gml_options = gml_to_graphml::Options {
+        write_defaults: matches.get_flag("WRITE_DEFAULTS"),
+        graphs,
+        strict: matches.get_flag("STRICT"),
+    };
+    let hyperedges = match matches.get_one::<String>("HYPEREDGES").map(String::as_str) {
+        Some("skip") => HyperedgePolicy::Skip,
+        Some("error") => HyperedgePolicy::Error,
+        _ => HyperedgePolicy::Star,
+    };
+    let graphml_options = graphml_to_gml::Options {
+        fill_defaults: matches.get_flag("FILL_DEFAULTS"),
+        hyperedges,
+        graphs,
+        strict: matches.get_flag("STRICT"),
+    };
 
-    // Convert files at given paths
-    let extension = input_path
-        .extension()
-        .and_then(OsStr::to_str)
-        .expect("Error: File extension could not be detected!");
+    // The format registry holds the options of the converters
+    let registry = Registry::with_options(
+        gml::Gml {
+            options: gml_options,
+        },
+        graphml::Graphml {
+            options: graphml_options.clone(),
+        },
+    );
 
     let before = Instant::now();
-    match extension {
-        "gml" => {
-            println!("Converting gml file to graphml");
-            gml_to_graphml::export_to_graphml(input_path.as_path(), output_path.as_path());
+    eprintln!("Converting {} file to {}", from, to);
+    match convert(
+        input_path,
+        output_path,
+        &from,
+        &to,
+        &registry,
+        &graphml_options,
+        split_graphs,
+    ) {
+        Ok(stats) => {
+            for warning in &stats.warnings {
+                eprintln!("Warning: {}", warning);
+            }
+            eprintln!("Converted {} nodes and {} edges", stats.nodes, stats.edges);
+            eprintln!("Elapsed time: {:.2?}", before.elapsed());
         }
-        "graphml" => {
-            println!("Converting graphml file to gml");
-            graphml_to_gml::export_to_gml(input_path.as_path(), output_path.as_path());
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            process::exit(1);
         }
-        _ => panic!("Unexpected input file format (only .gml or .graphml files supported)"),
     }
-    println!("Elapsed time: {:.2?}", before.elapsed());
 }
@@ -0,0 +1,21 @@
+// Several top level graphs in one input (several <graph> elements or graph [ blocks)
+// The first graph keeps its ids, the node ids of the next ones are namespaced with a graph prefix
+// (ie. n1 in the second graph -> g1:n1) so that they stay unique in the document.
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum MultipleGraphs {
+    // Keep each graph separate in the output
+    #[default]
+    Separate,
+    // Merge all the graphs into the first one
+    Merge,
+}
+
+pub fn graph_prefix(index: usize, graph_id: Option<&str>) -> String {
+    // Get the prefix of the node ids of a graph, from its id when it has one
+    match (index, graph_id) {
+        (0, _) => String::new(),
+        (_, Some(graph_id)) => format!("{}:", graph_id),
+        (_, None) => format!("g{}:", index),
+    }
+}
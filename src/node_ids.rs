@@ -0,0 +1,232 @@
+// Node id mapping between GML integer ids and GraphML string ids
+// GML ids are integers, written as n<id> in GraphML (ie. 1 -> n1). Any other GraphML id gets a free GML
+// integer and is kept in the GML node as a graphmlId attribute, which is used to restore it on the way back.
+// Other node attributes (ie. a name) are plain data both ways.
+
+use std::collections::{HashMap, HashSet};
+
+// GML node attribute holding the original GraphML id when it can't be derived from the GML id
+pub const ORIGINAL_ID_ATTRIBUTE: &str = "graphmlId";
+
+pub fn graphml_id(gml_id: i64) -> String {
+    // Default GraphML id for a GML id
+    format!("n{}", gml_id)
+}
+
+pub fn graphml_edge_id(gml_id: i64) -> String {
+    // Default GraphML id for a GML edge id
+    format!("e{}", gml_id)
+}
+
+pub fn parse_graphml_edge_id(id: &str) -> Option<i64> {
+    // Get the GML edge id back from a default GraphML edge id (ie. e1 -> 1)
+    let gml_id = id.strip_prefix('e')?.parse::<i64>().ok()?;
+    (graphml_edge_id(gml_id) == id).then_some(gml_id)
+}
+
+fn parse_graphml_id(id: &str) -> Option<i64> {
+    // Get the GML id back from a default GraphML id (ie. n1 -> 1)
+    let number = id.strip_prefix('n')?;
+    let gml_id = number.parse::<i64>().ok()?;
+    // Only accept the exact default form, so that ie. n01 or n+1 keep their original id
+    (graphml_id(gml_id) == id).then_some(gml_id)
+}
+
+#[derive(Debug, Default)]
+pub struct GmlIds {
+    // Prefix of the default ids, to read back the ids of a graph after the first one (ie. g1:)
+    prefix: String,
+    // GraphML id to GML id for every node seen so far, including nodes only referenced by edges
+    ids: HashMap<String, i64>,
+    used: HashSet<i64>,
+    next: i64,
+}
+
+impl GmlIds {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn with_prefix(prefix: String) -> Self {
+        GmlIds {
+            prefix,
+            ..Default::default()
+        }
+    }
+
+    fn parse(&self, id: &str) -> Option<i64> {
+        // Get the GML id back from a default id with the prefix
+        id.strip_prefix(self.prefix.as_str())
+            .and_then(parse_graphml_id)
+    }
+
+    pub fn get_or_insert(&mut self, id: &str) -> i64 {
+        // Get the GML id for a GraphML id, using the number of default ids when it is still free
+        if let Some(&gml_id) = self.ids.get(id) {
+            return gml_id;
+        }
+        let gml_id = match self.parse(id) {
+            Some(gml_id) if !self.used.contains(&gml_id) => gml_id,
+            // Use the next free integer for any other id
+            _ => self.insert_unnamed(),
+        };
+        self.used.insert(gml_id);
+        self.ids.insert(id.to_string(), gml_id);
+        gml_id
+    }
+
+    pub fn insert_unnamed(&mut self) -> i64 {
+        // Get a free GML id for a node that has no GraphML id (ie. a node added for a hyperedge)
+        while self.used.contains(&self.next) {
+            self.next += 1;
+        }
+        self.used.insert(self.next);
+        self.next
+    }
+
+    pub fn strip_prefix<'a>(&self, id: &'a str) -> &'a str {
+        // Get an edge id without the prefix of its graph
+        id.strip_prefix(self.prefix.as_str()).unwrap_or(id)
+    }
+
+    pub fn original_id<'a>(&self, id: &'a str, gml_id: i64) -> Option<&'a str> {
+        // Get the GraphML id to keep as an attribute, when it can't be derived from the GML id
+        (self.parse(id) != Some(gml_id)).then_some(id)
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct GraphmlIds {
+    // Prefix of the default ids, to keep the ids of several graphs apart (ie. g1:)
+    prefix: String,
+    // GML id to GraphML id, only for the nodes that have an original id attribute
+    names: HashMap<i64, String>,
+    used_names: HashSet<String>,
+    // GML ids given their default GraphML id by an edge before their node was read
+    referenced: HashSet<i64>,
+}
+
+impl GraphmlIds {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn with_prefix(prefix: String) -> Self {
+        GraphmlIds {
+            prefix,
+            ..Default::default()
+        }
+    }
+
+    fn parse(&self, name: &str) -> Option<i64> {
+        // Get the GML id back from a default id with the prefix
+        name.strip_prefix(self.prefix.as_str())
+            .and_then(parse_graphml_id)
+    }
+
+    pub fn insert(&mut self, gml_id: i64, name: &str) -> bool {
+        // Use a node original id attribute as its GraphML id, returns false if it can't be used because
+        // it is already taken, could collide with a default id or an edge already used the default id of the
+        // node (then it should be kept as node data)
+        if self.names.get(&gml_id).is_some_and(|known| known == name) {
+            // Name known from a previous pass over the graph
+            return true;
+        }
+        if self.parse(name).is_some_and(|id| id != gml_id)
+            || self.used_names.contains(name)
+            || self.referenced.contains(&gml_id)
+        {
+            return false;
+        }
+        if self.parse(name).is_none() {
+            self.used_names.insert(name.to_string());
+            self.names.insert(gml_id, name.to_string());
+        }
+        true
+    }
+
+    pub fn edge_id(&self, id: &str) -> String {
+        // Get the GraphML id of an edge, with the prefix of its graph
+        format!("{}{}", self.prefix, id)
+    }
+
+    pub fn get(&self, gml_id: i64) -> String {
+        // Get the GraphML id of a node from its GML id
+        match self.names.get(&gml_id) {
+            Some(name) => name.to_string(),
+            None => format!("{}{}", self.prefix, graphml_id(gml_id)),
+        }
+    }
+
+    pub fn get_referenced(&mut self, gml_id: i64) -> String {
+        // Get the GraphML id of an edge end in a single pass, a node named after its edges keeps its default id
+        if !self.names.contains_key(&gml_id) {
+            self.referenced.insert(gml_id);
+        }
+        self.get(gml_id)
+    }
+
+    pub fn is_referenced(&self, gml_id: i64) -> bool {
+        self.referenced.contains(&gml_id)
+    }
+}
+
+// Largest number kept as a bit in a node id set (8 MB of bits), larger ones are kept as names
+const MAX_BIT_NUMBER: u64 = 1 << 26;
+
+#[derive(Debug)]
+pub struct NodeIdSet {
+    // Prefix of the numbered ids (ie. n for n1), whose numbers are kept as bits so that the set of a large
+    // graph with numbered nodes stays small
+    number_prefix: &'static str,
+    bits: Vec<u64>,
+    names: HashSet<String>,
+}
+
+impl NodeIdSet {
+    pub fn new(number_prefix: &'static str) -> Self {
+        NodeIdSet {
+            number_prefix,
+            bits: Vec::new(),
+            names: HashSet::new(),
+        }
+    }
+
+    fn number(&self, id: &str) -> Option<u64> {
+        // Get the number of a numbered id, only in its exact form so that ie. 01 is kept as a name
+        let number = id.strip_prefix(self.number_prefix)?.parse::<u64>().ok()?;
+        (number < MAX_BIT_NUMBER && number.to_string().len() + self.number_prefix.len() == id.len())
+            .then_some(number)
+    }
+
+    pub fn insert(&mut self, id: &str) -> bool {
+        // Add an id, returns false if it was already in the set
+        match self.number(id) {
+            Some(number) => {
+                let (index, bit) = ((number / 64) as usize, 1 << (number % 64));
+                if index >= self.bits.len() {
+                    self.bits.resize(index + 1, 0);
+                }
+                let added = self.bits[index] & bit == 0;
+                self.bits[index] |= bit;
+                added
+            }
+            None => self.names.insert(id.to_string()),
+        }
+    }
+
+    pub fn contains(&self, id: &str) -> bool {
+        match self.number(id) {
+            Some(number) => self
+                .bits
+                .get((number / 64) as usize)
+                .is_some_and(|bits| bits & (1 << (number % 64)) != 0),
+            None => self.names.contains(id),
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.bits.clear();
+        self.names.clear();
+    }
+}
@@ -0,0 +1,18 @@
+// Statistics about a conversion
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ConversionStats {
+    pub nodes: usize,
+    pub edges: usize,
+    // Problems that didn't stop the conversion (ie. skipped elements)
+    pub warnings: Vec<String>,
+}
+
+impl ConversionStats {
+    pub fn merge(&mut self, other: ConversionStats) {
+        // Add the statistics of another conversion (ie. of the next graph)
+        self.nodes += other.nodes;
+        self.edges += other.edges;
+        self.warnings.extend(other.warnings);
+    }
+}
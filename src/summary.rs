@@ -0,0 +1,163 @@
+// Summary of a graph file, collected by the converters in place of writing their output
+// Counts the nodes and edges, self loops and parallel edges, and for each node and edge key its type, the share
+// of the elements that have a value for it and the minimum and maximum of its numeric values.
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeMap, HashSet};
+use std::hash::{Hash, Hasher};
+
+use serde_json::{Value, json};
+
+#[derive(Debug, Clone, Default)]
+struct KeySummary {
+    name: String,
+    attr_type: String,
+    // Number of elements with a value for the key
+    count: usize,
+    // Numeric values as numbers and as written, to print them back unchanged
+    min: Option<(f64, String)>,
+    max: Option<(f64, String)>,
+}
+
+#[derive(Debug, Default)]
+pub struct GraphSummary {
+    // Edge default of the first graph, once known
+    directed: Option<Option<bool>>,
+    nodes: usize,
+    edges: usize,
+    self_loops: usize,
+    parallel_edges: usize,
+    // Hashes of the node pairs of the edges, to find parallel edges without keeping their ids
+    edge_pairs: HashSet<u64>,
+    // Keys by element (node or edge) and key id
+    keys: BTreeMap<(String, String), KeySummary>,
+}
+
+fn numeric_type(attr_type: &str) -> bool {
+    matches!(attr_type, "int" | "long" | "float" | "double")
+}
+
+impl GraphSummary {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn set_directed(&mut self, directed: Option<bool>) {
+        // Keep the direction of the first graph
+        self.directed.get_or_insert(directed);
+    }
+
+    pub fn add_key(&mut self, element: &str, id: &str, name: &str, attr_type: &str) {
+        // Declare the name and type of a key, its values are collected by id
+        let key = self
+            .keys
+            .entry((element.to_string(), id.to_string()))
+            .or_default();
+        key.name = name.to_string();
+        key.attr_type = attr_type.to_string();
+    }
+
+    fn add_values<'a>(&mut self, element: &str, data: impl Iterator<Item = (&'a str, &'a str)>) {
+        // Count the keys of an element, the values are gml values where strings are quoted
+        let mut seen = HashSet::new();
+        for (id, value) in data {
+            let key = self
+                .keys
+                .entry((element.to_string(), id.to_string()))
+                .or_default();
+            if seen.insert(id) {
+                key.count += 1;
+            }
+            let Ok(number) = value.parse::<f64>() else {
+                continue;
+            };
+            if key.min.as_ref().is_none_or(|(min, _)| number < *min) {
+                key.min = Some((number, value.to_string()));
+            }
+            if key.max.as_ref().is_none_or(|(max, _)| number > *max) {
+                key.max = Some((number, value.to_string()));
+            }
+        }
+    }
+
+    pub fn add_node<'a>(&mut self, data: impl Iterator<Item = (&'a str, &'a str)>) {
+        self.nodes += 1;
+        self.add_values("node", data);
+    }
+
+    pub fn add_edge<'a>(
+        &mut self,
+        source: &str,
+        target: &str,
+        directed: bool,
+        data: impl Iterator<Item = (&'a str, &'a str)>,
+    ) {
+        // Undirected edges are parallel in both directions
+        self.edges += 1;
+        if source == target {
+            self.self_loops += 1;
+        }
+        let (first, second) = if directed || source <= target {
+            (source, target)
+        } else {
+            (target, source)
+        };
+        let mut hasher = DefaultHasher::new();
+        (first, second, directed).hash(&mut hasher);
+        if !self.edge_pairs.insert(hasher.finish()) {
+            self.parallel_edges += 1;
+        }
+        self.add_values("edge", data);
+    }
+
+    pub fn to_json(&self) -> Value {
+        // {"nodes": .., "edges": .., "directed": .., "self_loops": .., "parallel_edges": .., "keys": [..]}
+        let mut keys: Vec<(&str, &str, &KeySummary)> = self
+            .keys
+            .iter()
+            .map(|((element, id), key)| {
+                let name = if key.name.is_empty() { id } else { &key.name };
+                (element.as_str(), name.as_str(), key)
+            })
+            .collect();
+        keys.sort_by_key(|&(element, name, _)| (element, name));
+        let keys: Vec<Value> = keys
+            .into_iter()
+            .map(|(element, name, key)| {
+                let elements = if element == "node" {
+                    self.nodes
+                } else {
+                    self.edges
+                };
+                let mut summary = json!({
+                    "for": element,
+                    "name": name,
+                    // Elements converted to data without a key (ie. desc or port) have no type
+                    "type": (!key.attr_type.is_empty()).then_some(&key.attr_type),
+                    "count": key.count,
+                    "share": if elements > 0 { key.count as f64 / elements as f64 } else { 0.0 },
+                });
+                if numeric_type(&key.attr_type) {
+                    // Numbers are printed as written (ie. 1 for an int key, not 1.0)
+                    let number = |value: &Option<(f64, String)>| {
+                        value
+                            .as_ref()
+                            .and_then(|(_, text)| serde_json::from_str::<Value>(text).ok())
+                            .unwrap_or(Value::Null)
+                    };
+                    summary["min"] = number(&key.min);
+                    summary["max"] = number(&key.max);
+                }
+                summary
+            })
+            .collect();
+        json!({
+            "nodes": self.nodes,
+            "edges": self.edges,
+            "directed": self.directed.flatten(),
+            "self_loops": self.self_loops,
+            "parallel_edges": self.parallel_edges,
+            "keys": keys,
+        })
+    }
+}
@@ -0,0 +1,96 @@
+// Validation of gml and graphml files, built on the converters writing to nowhere
+// A file is valid when it converts in strict mode: no syntax error, no data of undeclared keys, no data value
+// that doesn't match its key attr.type, no duplicate node ids and no edges to undeclared nodes. These problems are
+// all reported, only a syntax error stops the validation.
+
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
+use std::path::Path;
+
+use serde_json::{Value, json};
+
+use crate::error::ConvertError;
+use crate::stats::ConversionStats;
+use crate::{gml_to_graphml, graphml_to_gml};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ValidationReport {
+    pub format: String,
+    // Counts of the converted elements, only known when the file is valid
+    pub stats: Option<ConversionStats>,
+    pub errors: Vec<String>,
+}
+
+impl ValidationReport {
+    pub fn is_valid(&self) -> bool {
+        self.errors.is_empty()
+    }
+
+    pub fn to_json(&self, path: &Path) -> Value {
+        // Machine readable report: {"file": .., "format": .., "valid": .., "errors": [..], "warnings": [..], ..}
+        let warnings = self.stats.as_ref().map(|stats| stats.warnings.clone());
+        json!({
+            "file": path.display().to_string(),
+            "format": self.format,
+            "valid": self.is_valid(),
+            "errors": self.errors,
+            "warnings": warnings.unwrap_or_default(),
+            "nodes": self.stats.as_ref().map(|stats| stats.nodes),
+            "edges": self.stats.as_ref().map(|stats| stats.edges),
+        })
+    }
+}
+
+fn report(format: &str, result: Result<ConversionStats, ConvertError>) -> ValidationReport {
+    // Collect the problems of a strict conversion in a report instead of returning them as errors
+    let (stats, errors) = match result {
+        Ok(stats) => (Some(stats), vec![]),
+        Err(ConvertError::Integrity { problems }) => (None, problems),
+        Err(e) => (None, vec![e.to_string()]),
+    };
+    ValidationReport {
+        format: format.to_string(),
+        stats,
+        errors,
+    }
+}
+
+pub fn validate<R: BufRead>(input: R, format: &str) -> ValidationReport {
+    // Validate a gml or graphml input (ie. stdin)
+    let result = match format {
+        "gml" => {
+            let options = gml_to_graphml::Options {
+                strict: true,
+                ..Default::default()
+            };
+            gml_to_graphml::convert_with_options(input, io::sink(), &options)
+        }
+        _ => {
+            let options = graphml_to_gml::Options {
+                strict: true,
+                ..Default::default()
+            };
+            graphml_to_gml::convert_with_options(input, io::sink(), &options)
+        }
+    };
+    report(format, result)
+}
+
+pub fn validate_file(path: &Path, format: &str) -> ValidationReport {
+    // Validate a gml or graphml file, gml files are read twice instead of buffered in a temp file
+    let input = match File::open(path) {
+        Ok(file) => BufReader::new(file),
+        Err(e) => return report(format, Err(e.into())),
+    };
+    if format != "gml" {
+        return validate(input, format);
+    }
+    let options = gml_to_graphml::Options {
+        strict: true,
+        ..Default::default()
+    };
+    report(
+        format,
+        gml_to_graphml::convert_seekable_with_options(input, io::sink(), &options),
+    )
+}
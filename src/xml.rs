@@ -0,0 +1,113 @@
+// Xml helpers shared by the GraphML and GEXF readers and writers
+// Attributes are read from the quick-xml start elements, the attribute declarations of the written graphs
+// (GraphML keys or GEXF attributes) are collected with the widest type of their values, and the GraphML writers
+// share the header.
+
+use std::collections::HashMap;
+use std::io::Write;
+use std::str;
+
+use quick_xml::Writer;
+use quick_xml::events::{BytesDecl, BytesStart, Event};
+
+use crate::attr_types::GraphmlAttributeTypes;
+use crate::error::ConvertError;
+use crate::graph::AttrValue;
+
+// Xml format being read, for the errors of its elements
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Dialect {
+    Graphml,
+    Gexf,
+}
+
+impl Dialect {
+    pub fn error(self, position: usize, message: String) -> ConvertError {
+        match self {
+            Dialect::Graphml => ConvertError::Graphml { position, message },
+            Dialect::Gexf => ConvertError::Gexf { position, message },
+        }
+    }
+}
+
+pub fn write_graphml_header<W: Write>(writer: &mut Writer<W>) -> Result<(), ConvertError> {
+    // Write the xml declaration and open the graphml node with the boilerplate attributes
+    let header = BytesDecl::new(b"1.0", Some(b"UTF-8"), Some(b"yes"));
+    writer.write_event(Event::Decl(header))?;
+    let mut elem = BytesStart::borrowed_name(b"graphml");
+    elem.push_attribute(("xmlns", "http://graphml.graphdrawing.org/xmlns"));
+    elem.push_attribute(("xmlns:xsi", "http://www.w3.org/2001/XMLSchema-instance"));
+    elem.push_attribute(("xsi:schemaLocation", "http://graphml.graphdrawing.org/xmlns http://graphml.graphdrawing.org/xmlns/1.0/graphml.xsd"));
+    writer.write_event(Event::Start(elem))?;
+    Ok(())
+}
+
+pub fn get_attribute(
+    element: &BytesStart,
+    search_term: &[u8],
+    position: usize,
+) -> Result<Option<String>, ConvertError> {
+    // Get the select attribute by keyword
+    for attr in element.html_attributes() {
+        let val = attr.map_err(|source| ConvertError::Xml { position, source })?;
+        if val.key == search_term {
+            let value = val
+                .unescaped_value()
+                .map_err(|source| ConvertError::Xml { position, source })?;
+            let value = str::from_utf8(&value).map_err(|e| ConvertError::Xml {
+                position,
+                source: quick_xml::Error::Utf8(e),
+            })?;
+            return Ok(Some(value.to_string()));
+        }
+    }
+    Ok(None)
+}
+
+pub fn required_attribute(
+    element: &BytesStart,
+    search_term: &[u8],
+    position: usize,
+    dialect: Dialect,
+) -> Result<String, ConvertError> {
+    // Get an attribute that must be present on the element
+    get_attribute(element, search_term, position)?.ok_or_else(|| {
+        dialect.error(
+            position,
+            format!(
+                "Missing {} attribute on <{}>",
+                String::from_utf8_lossy(search_term),
+                String::from_utf8_lossy(element.name())
+            ),
+        )
+    })
+}
+
+// Attribute declarations of a written graph by element and attribute name, in the order they are first seen, with
+// the widest type of their values
+#[derive(Debug, Default)]
+pub struct Keys {
+    ids: HashMap<(&'static str, String), usize>,
+    pub keys: Vec<(&'static str, String, GraphmlAttributeTypes)>,
+}
+
+impl Keys {
+    pub fn add(&mut self, element: &'static str, name: &str, value: &AttrValue) {
+        let value_type = GraphmlAttributeTypes::of_value(value);
+        match self.ids.get(&(element, name.to_string())) {
+            Some(&index) => {
+                let key = &mut self.keys[index];
+                key.2 = key.2.widen(value_type);
+            }
+            None => {
+                self.ids
+                    .insert((element, name.to_string()), self.keys.len());
+                self.keys.push((element, name.to_string(), value_type));
+            }
+        }
+    }
+
+    pub fn index(&self, element: &'static str, name: &str) -> usize {
+        self.ids[&(element, name.to_string())]
+    }
+}
@@ -0,0 +1,205 @@
+use std::fs;
+use std::io::{self, Write};
+use std::process::{Command, Stdio};
+
+#[test]
+fn converts_stdin_to_stdout() -> io::Result<()> {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_graph_converter"))
+        .args(["--from", "gml", "--to", "graphml", "-", "-"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    child
+        .stdin
+        .take()
+        .expect("stdin is piped")
+        .write_all(&fs::read("tests/data/simple.gml")?)?;
+    let output = child.wait_with_output()?;
+
+    assert!(output.status.success());
+    // Progress messages go to stderr, so stdout only holds the converted graph
+    assert_eq!(output.stdout, fs::read("tests/data/simple.graphml")?);
+    Ok(())
+}
+
+#[test]
+fn requires_a_format_for_stdin() -> io::Result<()> {
+    let output = Command::new(env!("CARGO_BIN_EXE_graph_converter"))
+        .args(["-", "-"])
+        .stdin(Stdio::null())
+        .output()?;
+    assert!(!output.status.success());
+    Ok(())
+}
+
+#[test]
+fn validates_files_with_a_json_report() -> io::Result<()> {
+    let output = Command::new(env!("CARGO_BIN_EXE_graph_converter"))
+        .args(["validate", "tests/data/simple.graphml"])
+        .output()?;
+    assert!(output.status.success());
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout)?;
+    assert_eq!(report["valid"], true);
+    assert_eq!(
+        (report["nodes"].as_u64(), report["edges"].as_u64()),
+        (Some(2), Some(1))
+    );
+    Ok(())
+}
+
+#[test]
+fn fails_validation_of_dangling_edges() -> io::Result<()> {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_graph_converter"))
+        .args(["validate", "--from", "gml", "-"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()?;
+    child
+        .stdin
+        .take()
+        .expect("stdin is piped")
+        .write_all(b"graph [ node [ id 1 ] edge [ source 1 target 2 ] ]")?;
+    let output = child.wait_with_output()?;
+
+    assert_eq!(output.status.code(), Some(1));
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout)?;
+    assert_eq!(report["valid"], false);
+    assert_eq!(
+        report["errors"],
+        serde_json::json!(["Edge at line 1, column 46 refers to undeclared node \"2\""])
+    );
+    Ok(())
+}
+
+#[test]
+fn prints_file_info() -> io::Result<()> {
+    let output = Command::new(env!("CARGO_BIN_EXE_graph_converter"))
+        .args(["info", "tests/data/simple.gml"])
+        .output()?;
+    assert!(output.status.success());
+    let summary: serde_json::Value = serde_json::from_slice(&output.stdout)?;
+    assert_eq!(
+        (summary["nodes"].as_u64(), summary["edges"].as_u64()),
+        (Some(2), Some(1))
+    );
+    Ok(())
+}
+
+#[test]
+fn converts_between_any_registered_formats() -> io::Result<()> {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_graph_converter"))
+        .args(["--from", "gml", "--to", "gml", "-", "-"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    child
+        .stdin
+        .take()
+        .expect("stdin is piped")
+        .write_all(&fs::read("tests/data/simple.gml")?)?;
+    let output = child.wait_with_output()?;
+
+    assert!(output.status.success());
+    assert_eq!(output.stdout, fs::read("tests/data/simple.gml")?);
+    Ok(())
+}
+
+#[test]
+fn rejects_converter_options_for_other_formats() -> io::Result<()> {
+    for args in [
+        ["--strict", "tests/data/simple.gml", "-", "--to", "dot"],
+        [
+            "--graphs",
+            "merge",
+            "tests/data/simple.gml",
+            "-",
+            "--to=gexf",
+        ],
+        [
+            "--split-graphs",
+            "tests/data/graphs.graphml",
+            "graphs.dot",
+            "--to",
+            "dot",
+        ],
+    ] {
+        let output = Command::new(env!("CARGO_BIN_EXE_graph_converter"))
+            .args(args)
+            .output()?;
+        assert_eq!(output.status.code(), Some(2), "{args:?}");
+        assert!(output.stdout.is_empty(), "{args:?}");
+    }
+    Ok(())
+}
+
+#[test]
+fn rejects_defaults_options_for_the_other_direction() -> io::Result<()> {
+    for (args, message) in [
+        (
+            ["--fill-defaults", "tests/data/simple.gml", "-"],
+            "--fill-defaults only applies to conversions from graphml to gml",
+        ),
+        (
+            ["--write-defaults", "tests/data/simple.graphml", "-"],
+            "--write-defaults only applies to conversions from gml to graphml",
+        ),
+    ] {
+        let output = Command::new(env!("CARGO_BIN_EXE_graph_converter"))
+            .args(args)
+            .output()?;
+        assert_eq!(output.status.code(), Some(2), "{args:?}");
+        assert!(String::from_utf8_lossy(&output.stderr).contains(message));
+    }
+    Ok(())
+}
+
+#[test]
+fn applies_the_hyperedge_policy_to_graphml_inputs() -> io::Result<()> {
+    let convert = |policy: &str| {
+        Command::new(env!("CARGO_BIN_EXE_graph_converter"))
+            .args([
+                "--hyperedges",
+                policy,
+                "tests/data/ports.graphml",
+                "-",
+                "--to",
+                "dot",
+            ])
+            .output()
+    };
+    let star = convert("star")?;
+    assert!(star.status.success());
+    assert!(String::from_utf8_lossy(&star.stdout).contains("hyperedge=h0"));
+    let skip = convert("skip")?;
+    assert!(skip.status.success());
+    assert!(!String::from_utf8_lossy(&skip.stdout).contains("h0"));
+    assert_eq!(convert("error")?.status.code(), Some(1));
+
+    let output = Command::new(env!("CARGO_BIN_EXE_graph_converter"))
+        .args([
+            "--hyperedges",
+            "skip",
+            "tests/data/simple.gml",
+            "-",
+            "--to",
+            "dot",
+        ])
+        .output()?;
+    assert_eq!(output.status.code(), Some(2));
+    Ok(())
+}
+
+#[test]
+fn leaves_no_output_file_for_a_missing_input() -> io::Result<()> {
+    let directory = tempfile::tempdir()?;
+    let output_path = directory.path().join("missing.graphml");
+    let output = Command::new(env!("CARGO_BIN_EXE_graph_converter"))
+        .arg("tests/data/missing.gml")
+        .arg(&output_path)
+        .output()?;
+    assert!(!output.status.success());
+    assert!(!output_path.exists());
+    Ok(())
+}
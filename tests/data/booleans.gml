@@ -0,0 +1,18 @@
+graph [
+  directed 0
+  node [
+    id 1
+    visited 1
+    size -5000000000
+  ]
+  node [
+    id 2
+    visited 0
+    size 7
+  ]
+  edge [
+    source 1
+    target 2
+    weight -2.0
+  ]
+]
\ No newline at end of file
@@ -0,0 +1,27 @@
+graph [
+  directed 1
+  node [
+    id 1
+    size 1
+    color "red"
+  ]
+  node [
+    id 2
+    size 2
+    color "red"
+  ]
+  node [
+    id 3
+    color "blue"
+  ]
+  edge [
+    source 1
+    target 2
+    weight 1.0
+  ]
+  edge [
+    source 2
+    target 3
+    weight 1.0
+  ]
+]
\ No newline at end of file
@@ -0,0 +1,24 @@
+graph [
+  node [
+    id 1
+    label "a"
+  ]
+  node [
+    id 2
+  ]
+  edge [
+    source 1
+    target 2
+  ]
+]
+graph [
+  directed 1
+  node [
+    id 1
+    label "b"
+  ]
+  edge [
+    source 1
+    target 1
+  ]
+]
\ No newline at end of file
@@ -0,0 +1,41 @@
+graph [
+  directed 1
+  node [
+    id 0
+    isGroup 1
+    label "outer"
+  ]
+  node [
+    id 1
+    isGroup 1
+    gid 0
+    label "inner"
+  ]
+  node [
+    id 2
+    gid 1
+  ]
+  node [
+    id 3
+    gid 1
+  ]
+  edge [
+    source 2
+    target 3
+  ]
+  node [
+    id 4
+    gid 0
+  ]
+  edge [
+    source 1
+    target 4
+  ]
+  node [
+    id 5
+  ]
+  edge [
+    source 5
+    target 2
+  ]
+]
\ No newline at end of file
@@ -0,0 +1,21 @@
+graph [
+  directed 0
+  node [
+    id 0
+    style [
+      visible 1
+      hidden 0
+      title "say &quot;hi&quot;"
+      border [
+        line [
+          width 1.5
+          dash 2
+          dash 4
+        ]
+      ]
+      empty "[]"
+    ]
+    layers "[1,2]"
+    layers "[3]"
+  ]
+]
\ No newline at end of file
@@ -0,0 +1,18 @@
+graph [
+  directed 1
+  node [
+    id 1
+  ]
+  node [
+    id 2
+  ]
+  edge [
+    source 1
+    target 2
+  ]
+  edge [
+    source 2
+    target 1
+    directed 0
+  ]
+]
\ No newline at end of file
@@ -0,0 +1,17 @@
+graph [
+  directed 0
+  node [
+    id 1
+    x 1
+    y 2
+  ]
+  node [
+    id 2
+    x 1.5
+    y "b"
+  ]
+  edge [
+    source 1
+    target 2
+  ]
+]
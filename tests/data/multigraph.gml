@@ -0,0 +1,28 @@
+graph [
+  directed 1
+  multigraph 1
+  node [
+    id 1
+  ]
+  node [
+    id 2
+  ]
+  edge [
+    source 1
+    target 2
+    key 0
+    weight 1
+  ]
+  edge [
+    source 1
+    target 2
+    key 1
+    weight 2
+  ]
+  edge [
+    id 7
+    source 2
+    target 1
+    key "back"
+  ]
+]
\ No newline at end of file
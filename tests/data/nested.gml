@@ -0,0 +1,33 @@
+graph [
+  directed 1
+  metadata [
+    source "x"
+    authors [ name "a" name "b" ]
+  ]
+  node [
+    id 1
+    graphics [
+      x 10.5
+      y -3
+      style [
+        fill "#ff0000"
+        line [
+          width 2
+          dash 1
+          dash 3
+        ]
+      ]
+    ]
+  ]
+  node [
+    id 2
+  ]
+  edge [
+    source 1
+    target 2
+    path [
+      point [ x 1 y 2 ]
+      point [ x 3 y 4 ]
+    ]
+  ]
+]
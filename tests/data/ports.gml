@@ -0,0 +1,58 @@
+comment "Ports, descriptions and hyperedges"
+graph [
+  directed 1
+  desc "The &quot;main&quot; graph"
+  node [
+    id 0
+    desc "First node"
+    port [
+      name "north"
+      side "top"
+      port [
+        name "north-east"
+      ]
+      port [
+        name "north-west"
+      ]
+    ]
+  ]
+  node [
+    id 1
+    port [
+      name "south"
+    ]
+  ]
+  node [
+    id 2
+    locator "other.graphml#n2"
+  ]
+  edge [
+    source 0
+    target 1
+    port [
+      source "north-east"
+      target "south"
+    ]
+    desc "Port to port"
+  ]
+  node [
+    id 3
+    hyperedge "h0"
+    weight 0.5
+  ]
+  edge [
+    source 0
+    target 3
+    port [
+      source "north"
+    ]
+  ]
+  edge [
+    source 3
+    target 1
+  ]
+  edge [
+    source 2
+    target 3
+  ]
+]
\ No newline at end of file
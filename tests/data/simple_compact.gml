@@ -0,0 +1,5 @@
+# Same graph as simple.gml, written with several pairs per line
+graph [ directed 0 label "test_label" name "test_name"
+  node [ id 1 a 73 a 74 diseases 28 diseases 46 diseases 78 weight 1.0 ]
+  node [ id 2 weight 1.0 ] edge [ source 1 target 2 weight 1.1 ]
+]
@@ -0,0 +1,34 @@
+graph [
+  directed 1
+  node [
+    id 0
+    graphmlId "alice"
+    weight 1.5
+  ]
+  node [
+    id 1
+  ]
+  node [
+    id 2
+    graphmlId "a1"
+  ]
+  node [
+    id -5
+  ]
+  node [
+    id 3
+    graphmlId "b&amp;1"
+  ]
+  edge [
+    source 0
+    target 2
+  ]
+  edge [
+    source -5
+    target 3
+  ]
+  edge [
+    source 1
+    target 0
+  ]
+]
\ No newline at end of file
@@ -0,0 +1,27 @@
+graph [
+  directed 1
+  node [
+    id 1
+    offset -3
+    count 5000000000
+    ratio 0.5
+    precise 0.123456789
+    big 2
+  ]
+  node [
+    id 2
+    offset 4
+    count 1
+    ratio 1.5
+    precise 1.0
+    big 1.5
+  ]
+  node [
+    id 3
+    big 3000000000
+  ]
+  edge [
+    source 1
+    target 2
+  ]
+]
\ No newline at end of file
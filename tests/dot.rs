@@ -0,0 +1,254 @@
+use std::fs::{self, File};
+use std::io::{self, BufReader, Cursor};
+
+use graph_converter::dot;
+use graph_converter::error::ConvertError;
+use graph_converter::format::Registry;
+use graph_converter::gml;
+use graph_converter::graph::{AttrValue, Graph};
+
+fn read_dot<R: io::BufRead>(input: R) -> Graph {
+    Graph::read_events(&mut dot::events(input))
+        .expect("dot should be read")
+        .expect("dot has a graph")
+}
+
+fn write_dot(graph: &Graph) -> String {
+    let mut writer = dot::EventWriter::new(Vec::new());
+    for event in graph.events() {
+        writer.write(&event).expect("event should be written");
+    }
+    String::from_utf8(writer.finish().expect("dot should be written")).expect("dot is utf-8")
+}
+
+fn string(value: &str) -> AttrValue {
+    AttrValue::String(value.to_string())
+}
+
+#[test]
+fn reads_dot_statements() -> io::Result<()> {
+    let graph = read_dot(BufReader::new(File::open("tests/data/clusters.dot")?));
+    assert_eq!(graph.directed, Some(true));
+    assert_eq!(graph.attrs["name"], string("G"));
+    assert_eq!(graph.attrs["rankdir"], string("LR"));
+
+    // Node defaults apply to the nodes declared after them, in their subgraph
+    let node = graph.node("b1").expect("b1 is read");
+    assert_eq!(node.attrs["color"], string("blue"));
+    assert_eq!(node.attrs["shape"], string("box"));
+    assert_eq!(node.attrs["label"], string("multi \"line\" label"));
+    assert_eq!(
+        node.attrs["graphics"].to_json(),
+        serde_json::json!({"x": 1.5, "y": -2})
+    );
+    assert_eq!(
+        graph.node("b0").expect("b0 is read").attrs["label"],
+        string("<b>bold</b>")
+    );
+    assert_eq!(
+        graph.node("start").expect("start is read").attrs["shape"],
+        string("Mdiamond")
+    );
+
+    // Edge chains and subgraph endpoints give an edge for each pair of nodes
+    let edges: Vec<(&str, &str)> = graph
+        .edges
+        .iter()
+        .map(|edge| (edge.source.as_str(), edge.target.as_str()))
+        .collect();
+    assert_eq!(
+        edges,
+        [
+            ("a0", "a1"),
+            ("a1", "a2"),
+            ("start", "a0"),
+            ("start", "b0"),
+            ("a2", "b1"),
+            ("b1", "start")
+        ]
+    );
+    let edge = &graph.edges[4];
+    assert_eq!(edge.id.as_deref(), Some("e7"));
+    assert_eq!(edge.directed, Some(false));
+    assert_eq!(edge.attrs["tailport"], string("p1:s"));
+    assert_eq!(edge.attrs["weight"], AttrValue::Int(2));
+    assert_eq!(graph.edges[5].attrs["label"], string("ab"));
+    assert_eq!(graph.edges[5].attrs["constraint"], AttrValue::Bool(true));
+    Ok(())
+}
+
+#[test]
+fn reads_clusters_as_group_nodes() -> io::Result<()> {
+    let graph = read_dot(BufReader::new(File::open("tests/data/clusters.dot")?));
+    let group = graph.node("a").expect("cluster_a is a group node");
+    assert_eq!(group.attrs["isGroup"], AttrValue::Int(1));
+    assert_eq!(group.attrs["label"], string("Cluster A"));
+    assert_eq!(
+        graph.node("a2").expect("a2 is read").attrs["gid"],
+        string("a")
+    );
+    assert!(
+        !graph
+            .node("start")
+            .expect("start is read")
+            .attrs
+            .contains_key("gid")
+    );
+    Ok(())
+}
+
+#[test]
+fn keeps_cluster_ids_apart_from_node_ids() {
+    let graph = read_dot(Cursor::new("graph { subgraph cluster_1 { 1; 2 } 1 -- 3 }"));
+    let group = graph.node("cluster_1").expect("cluster_1 keeps its name");
+    assert_eq!(group.attrs["isGroup"], AttrValue::Int(1));
+    for id in ["1", "2"] {
+        let node = graph.node(id).expect("the node is read");
+        assert!(!node.attrs.contains_key("isGroup"));
+        assert_eq!(node.attrs["gid"], string("cluster_1"));
+    }
+    assert_eq!(graph.nodes.len(), 4);
+}
+
+#[test]
+fn keeps_clusters_through_gml_to_graphml() {
+    let registry = Registry::new();
+    let input = "digraph { subgraph cluster_x { a } a -> b }";
+    let mut gml = Vec::new();
+    registry
+        .convert("dot", "gml", Box::new(Cursor::new(input)), &mut gml)
+        .expect("dot should be converted");
+    // The gid is the GML id of the group node
+    assert!(String::from_utf8_lossy(&gml).contains("graphmlId \"a\"\n    gid 0\n"));
+    let mut graphml = Vec::new();
+    registry
+        .convert("gml", "graphml", Box::new(Cursor::new(gml)), &mut graphml)
+        .expect("gml should be converted");
+    assert!(String::from_utf8_lossy(&graphml).contains(concat!(
+        "  <node id=\"x\">\n",
+        "    <graph id=\"x:\" edgedefault=\"directed\">\n",
+        "      <node id=\"a\">\n",
+    )));
+}
+
+#[test]
+fn reads_every_graph_of_a_file() -> io::Result<()> {
+    let mut events = dot::events(BufReader::new(File::open("tests/data/clusters.dot")?));
+    Graph::read_events(&mut events).expect("first graph should be read");
+    let graph = Graph::read_events(&mut events)
+        .expect("second graph should be read")
+        .expect("second graph is read");
+    assert_eq!(graph.directed, Some(false));
+    assert_eq!(graph.edges[0].source, "g1:x");
+    Ok(())
+}
+
+#[test]
+fn writes_dot_attributes() {
+    let mut graph = Graph::new();
+    graph.directed = Some(false);
+    graph.attrs.insert("name".to_string(), string("my graph"));
+    let node = graph.add_node("n1");
+    node.attrs.insert("label".to_string(), string("say \"hi\""));
+    node.attrs.insert(
+        "graphics".to_string(),
+        AttrValue::Map([("x".to_string(), AttrValue::Float(1.0))].into()),
+    );
+    node.attrs.insert(
+        "tags".to_string(),
+        AttrValue::List(vec![string("a"), string("1")]),
+    );
+    graph
+        .add_node("node 2")
+        .attrs
+        .insert("visited".to_string(), AttrValue::Bool(true));
+    let edge = graph.add_edge("n1", "node 2");
+    edge.id = Some("e0".to_string());
+    edge.directed = Some(true);
+
+    assert_eq!(
+        write_dot(&graph),
+        concat!(
+            "graph \"my graph\" {\n",
+            "  n1 [\"graphics.x\"=1.0, label=\"say \\\"hi\\\"\", \"tags.0\"=a, \"tags.1\"=\"1\"];\n",
+            "  \"node 2\" [visited=true];\n",
+            "  n1 -- \"node 2\" [dir=forward, id=e0];\n",
+            "}\n"
+        )
+    );
+}
+
+#[test]
+fn writes_edge_ids_and_directions_once() {
+    let input = r#"graph [ directed 1 edge [ id 5 source 1 target 2 directed 0 dir "back" ] ]"#;
+    let graph = gml::read(Cursor::new(input)).expect("gml should be read");
+    assert_eq!(
+        write_dot(&graph),
+        concat!("digraph {\n", "  n1 -> n2 [dir=none, id=e5];\n", "}\n")
+    );
+}
+
+#[test]
+fn converts_gml_through_dot() -> io::Result<()> {
+    for path in ["tests/data/simple.gml", "tests/data/nested.gml"] {
+        let graph = gml::read(BufReader::new(File::open(path)?)).expect("gml should be read");
+        let read_back = read_dot(Cursor::new(write_dot(&graph)));
+        assert_eq!(read_back, graph, "{path}");
+    }
+    let mut output = Vec::new();
+    let graph = gml::read(BufReader::new(File::open("tests/data/simple.gml")?))
+        .expect("gml should be read");
+    gml::write(&read_dot(Cursor::new(write_dot(&graph))), &mut output)
+        .expect("gml should be written");
+    assert_eq!(
+        String::from_utf8_lossy(&output),
+        fs::read_to_string("tests/data/simple.gml")?
+    );
+    Ok(())
+}
+
+#[test]
+fn fails_on_invalid_dot_with_its_location() {
+    let input = "digraph {\n  a -> ;\n}";
+    let result = Graph::read_events(&mut dot::events(Cursor::new(input)));
+    assert!(matches!(
+        result,
+        Err(ConvertError::Dot {
+            line: 2,
+            column: 8,
+            ..
+        })
+    ));
+}
+
+#[test]
+fn reads_numeral_ids_next_to_edge_operators() {
+    let graph = read_dot(Cursor::new("digraph { 1->2; 2->3; -1->-2.5 [weight=-3] }"));
+    let edges: Vec<(&str, &str)> = graph
+        .edges
+        .iter()
+        .map(|edge| (edge.source.as_str(), edge.target.as_str()))
+        .collect();
+    assert_eq!(edges, [("1", "2"), ("2", "3"), ("-1", "-2.5")]);
+    assert_eq!(graph.edges[2].attrs["weight"], AttrValue::Int(-3));
+}
+
+#[test]
+fn writes_backslashes_that_read_back() {
+    let mut graph = Graph::new();
+    graph.directed = Some(true);
+    for (id, label) in [
+        ("n1", "C:\\dir\\"),
+        ("n2", "a\\\"b"),
+        ("n3", "line\\nbreak"),
+    ] {
+        graph
+            .add_node(id)
+            .attrs
+            .insert("label".to_string(), string(label));
+    }
+    let dot = write_dot(&graph);
+    // Escape sequences of Graphviz labels are written as is
+    assert!(dot.contains("label=\"line\\nbreak\""), "{dot}");
+    assert_eq!(read_dot(Cursor::new(dot)), graph);
+}
@@ -0,0 +1,224 @@
+use std::io::{self, Write};
+use std::path::Path;
+
+use graph_converter::error::ConvertError;
+use graph_converter::gml_to_graphml;
+use graph_converter::graphml_to_gml::{self, HyperedgePolicy};
+use graph_converter::stats::ConversionStats;
+use graph_converter::validate::validate_file;
+use graph_converter::{gml_to_graphml::export_to_graphml, graphml_to_gml::export_to_gml};
+use tempfile::NamedTempFile;
+
+fn input_file(content: &str) -> io::Result<NamedTempFile> {
+    let mut file = NamedTempFile::new()?;
+    file.write_all(content.as_bytes())?;
+    Ok(file)
+}
+
+#[test]
+fn returns_conversion_stats() -> io::Result<()> {
+    let output_file = NamedTempFile::new()?;
+    let stats = export_to_gml(Path::new("tests/data/simple.graphml"), output_file.path())
+        .expect("conversion should succeed");
+    assert_eq!(
+        stats,
+        ConversionStats {
+            nodes: 2,
+            edges: 1,
+            warnings: vec![]
+        }
+    );
+    Ok(())
+}
+
+#[test]
+fn reports_missing_input_file() -> io::Result<()> {
+    let output_file = NamedTempFile::new()?;
+    let result = export_to_graphml(Path::new("tests/data/missing.gml"), output_file.path());
+    assert!(matches!(result, Err(ConvertError::Io(_))));
+    Ok(())
+}
+
+#[test]
+fn reports_gml_errors_with_line_and_column() -> io::Result<()> {
+    let input = input_file("graph [\n  node [\n    id 1\n    label\n  ]\n]\n")?;
+    let output_file = NamedTempFile::new()?;
+    match export_to_graphml(input.path(), output_file.path()) {
+        Err(ConvertError::Gml { line, column, .. }) => assert_eq!((line, column), (5, 3)),
+        other => panic!("expected a gml error, got {:?}", other),
+    }
+    Ok(())
+}
+
+#[test]
+fn reports_lists_nested_too_deep() {
+    let depth = 20_000;
+    let input = format!(
+        "graph [ node [ id 1 {}{} ] ]",
+        "a [ ".repeat(depth),
+        "] ".repeat(depth)
+    );
+    let result = gml_to_graphml::convert(input.as_bytes(), io::sink());
+    assert!(matches!(result, Err(ConvertError::Gml { .. })));
+    let result = graph_converter::gml::read(input.as_bytes());
+    assert!(matches!(result, Err(ConvertError::Gml { .. })));
+    let input = format!(
+        r#"<graphml><key id="d0" for="node" attr.name="a" attr.type="string"/><graph><node id="n0"><data key="d0">{}{}</data></node></graph></graphml>"#,
+        "[".repeat(depth),
+        "]".repeat(depth)
+    );
+    let result = graphml_to_gml::convert(input.as_bytes(), io::sink());
+    assert!(matches!(result, Err(ConvertError::Graphml { .. })));
+}
+
+#[test]
+fn reports_undeclared_graphml_keys() {
+    let input = r#"<graphml><graph><node id="n0"><data key="d9">1</data></node></graph></graphml>"#;
+    let options = graphml_to_gml::Options {
+        strict: true,
+        ..Default::default()
+    };
+    match graphml_to_gml::convert_with_options(input.as_bytes(), io::sink(), &options) {
+        Err(ConvertError::Integrity { problems }) => {
+            assert_eq!(problems.len(), 1);
+            assert!(problems[0].starts_with("Undeclared key \"d9\" at position "));
+        }
+        other => panic!("expected an integrity error, got {:?}", other),
+    }
+}
+
+#[test]
+fn reports_unknown_graphml_types() -> io::Result<()> {
+    let input = input_file(
+        r#"<graphml><key id="d0" for="node" attr.name="a" attr.type="complex"/></graphml>"#,
+    )?;
+    let output_file = NamedTempFile::new()?;
+    let result = export_to_gml(input.path(), output_file.path());
+    assert!(matches!(result, Err(ConvertError::Graphml { .. })));
+    Ok(())
+}
+
+#[test]
+fn reports_invalid_typed_values() -> io::Result<()> {
+    let input = input_file(
+        r#"<graphml><key id="d0" for="node" attr.name="a" attr.type="boolean"/><graph><node id="n1"><data key="d0">maybe</data></node></graph></graphml>"#,
+    )?;
+    let output_file = NamedTempFile::new()?;
+    let result = export_to_gml(input.path(), output_file.path());
+    assert!(matches!(result, Err(ConvertError::Graphml { .. })));
+    Ok(())
+}
+
+#[test]
+fn reports_hyperedges_when_asked_to() {
+    let input = r#"<graphml><graph><node id="a"/><hyperedge><endpoint node="a"/></hyperedge></graph></graphml>"#;
+    let options = graphml_to_gml::Options {
+        hyperedges: HyperedgePolicy::Error,
+        ..Default::default()
+    };
+    let result = graphml_to_gml::convert_with_options(input.as_bytes(), io::sink(), &options);
+    assert!(matches!(result, Err(ConvertError::Graphml { .. })));
+}
+
+#[test]
+fn reports_invalid_edge_directions() {
+    let input = r#"<graphml><graph><node id="a"/><edge source="a" target="a" directed="yes"/></graph></graphml>"#;
+    let result = graphml_to_gml::convert(input.as_bytes(), io::sink());
+    assert!(matches!(result, Err(ConvertError::Graphml { .. })));
+}
+
+#[test]
+fn reports_dangling_edges_and_duplicate_nodes_as_warnings() {
+    let input = "graph [\n  node [ id 1 ]\n  node [ id 1 ]\n  edge [ source 1 target 5 ]\n]";
+    let stats =
+        gml_to_graphml::convert(input.as_bytes(), io::sink()).expect("conversion should succeed");
+    assert_eq!(
+        stats.warnings,
+        vec![
+            "Duplicate node id \"1\" at line 3, column 3".to_string(),
+            "Edge at line 4, column 26 refers to undeclared node \"5\"".to_string(),
+        ]
+    );
+
+    // Nodes can be declared after their edges
+    let input = r#"<graphml><graph><edge source="a" target="b"/><node id="a"/><node id="b"/></graph></graphml>"#;
+    let stats =
+        graphml_to_gml::convert(input.as_bytes(), io::sink()).expect("conversion should succeed");
+    assert_eq!(stats.warnings, Vec::<String>::new());
+}
+
+#[test]
+fn fails_on_dangling_edges_in_strict_mode() {
+    let input = "graph [ node [ id 1 ] edge [ source 1 target 2 ] edge [ source 3 target 2 ] ]";
+    let options = gml_to_graphml::Options {
+        strict: true,
+        ..Default::default()
+    };
+    match gml_to_graphml::convert_with_options(input.as_bytes(), io::sink(), &options) {
+        Err(ConvertError::Integrity { problems }) => assert_eq!(
+            problems,
+            vec![
+                "Edge at line 1, column 46 refers to undeclared node \"2\"".to_string(),
+                "Edge at line 1, column 64 refers to undeclared node \"3\"".to_string(),
+            ]
+        ),
+        other => panic!("expected an integrity error, got {:?}", other),
+    }
+
+    let input =
+        r#"<graphml><graph><node id="n1"/><edge source="n1" target="n2"/></graph></graphml>"#;
+    let options = graphml_to_gml::Options {
+        strict: true,
+        ..Default::default()
+    };
+    let result = graphml_to_gml::convert_with_options(input.as_bytes(), io::sink(), &options);
+    assert!(matches!(result, Err(ConvertError::Integrity { .. })));
+}
+
+#[test]
+fn fails_on_edges_without_an_end_in_strict_mode() {
+    let input = "graph [\n  node [ id 1 ]\n  edge [ source 1 target 1 ]\n  edge [ target 1 ]\n]";
+    let stats =
+        gml_to_graphml::convert(input.as_bytes(), io::sink()).expect("conversion should succeed");
+    assert_eq!(
+        stats.warnings,
+        vec!["Edge without source at line 4, column 3".to_string()]
+    );
+
+    let options = gml_to_graphml::Options {
+        strict: true,
+        ..Default::default()
+    };
+    match gml_to_graphml::convert_with_options(input.as_bytes(), io::sink(), &options) {
+        Err(ConvertError::Integrity { problems }) => {
+            assert_eq!(
+                problems,
+                vec!["Edge without source at line 4, column 3".to_string()]
+            )
+        }
+        other => panic!("expected an integrity error, got {:?}", other),
+    }
+}
+
+#[test]
+fn validates_data_types_against_keys() -> io::Result<()> {
+    let input = input_file(
+        r#"<graphml><key id="d0" for="node" attr.name="a" attr.type="int"/><graph><node id="n1"><data key="d0">1.5</data></node></graph></graphml>"#,
+    )?;
+    let report = validate_file(input.path(), "graphml");
+    assert!(!report.is_valid());
+    assert_eq!(report.stats, None);
+    assert_eq!(report.errors.len(), 1);
+
+    // The other problems are still reported after a value of another type
+    let input = input_file(
+        r#"<graphml><key id="d0" for="node" attr.name="a" attr.type="int"/><graph><node id="n1"><data key="d0">1.5</data></node><node id="n1"><data key="d9">1</data></node><edge source="n1" target="n2"/></graph></graphml>"#,
+    )?;
+    let report = validate_file(input.path(), "graphml");
+    assert_eq!(report.errors.len(), 4, "{:?}", report.errors);
+    assert!(report.errors[0].starts_with("Invalid a value \"1.5\" at position "));
+
+    let report = validate_file(Path::new("tests/data/simple.gml"), "gml");
+    assert!(report.is_valid());
+    Ok(())
+}
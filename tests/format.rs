@@ -0,0 +1,88 @@
+use std::fs::{self, File};
+use std::io::{self, BufReader, Cursor, Write};
+use std::path::Path;
+
+use graph_converter::error::ConvertError;
+use graph_converter::format::{Events, Format, GraphWriter, Registry};
+use graph_converter::graph::GraphEvent;
+
+// In house format writing one "source target" line per edge
+struct EdgeList;
+
+impl GraphWriter for EdgeList {
+    fn write_events(
+        &self,
+        events: &mut Events<'_>,
+        output: &mut dyn Write,
+    ) -> Result<(), ConvertError> {
+        for event in events {
+            if let GraphEvent::Edge { source, target, .. } = event? {
+                writeln!(output, "{} {}", source, target)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn detects_formats_from_extensions() {
+    let registry = Registry::new();
+    let name_of = |path: &str| {
+        registry
+            .format_of(Path::new(path))
+            .map(|format| format.name.as_str())
+    };
+    assert_eq!(name_of("graph.gml"), Some("gml"));
+    assert_eq!(name_of("graph.GraphML"), Some("graphml"));
+    assert_eq!(name_of("graph.gexf"), Some("gexf"));
+    assert_eq!(name_of("graph.gv"), Some("dot"));
+    assert_eq!(name_of("graph.txt"), None);
+    assert_eq!(
+        registry.names().collect::<Vec<_>>(),
+        ["gml", "graphml", "gexf", "dot"]
+    );
+}
+
+#[test]
+fn converts_through_graph_events() -> io::Result<()> {
+    let input = BufReader::new(File::open("tests/data/simple.gml")?);
+    let mut output = Vec::new();
+    let stats = Registry::new()
+        .convert("gml", "graphml", Box::new(input), &mut output)
+        .expect("conversion should succeed");
+    assert_eq!((stats.nodes, stats.edges), (2, 1));
+    assert_eq!(output, fs::read("tests/data/simple.graphml")?);
+    Ok(())
+}
+
+#[test]
+fn converts_to_registered_formats() -> io::Result<()> {
+    let mut registry = Registry::new();
+    registry.register(Format::new("edgelist", &["edges", "txt"]).with_writer(EdgeList));
+    assert_eq!(
+        registry
+            .format_of(Path::new("graph.txt"))
+            .map(|format| format.name.as_str()),
+        Some("edgelist")
+    );
+
+    let input = BufReader::new(File::open("tests/data/string_ids.graphml")?);
+    let mut output = Vec::new();
+    registry
+        .convert("graphml", "edgelist", Box::new(input), &mut output)
+        .expect("conversion should succeed");
+    assert_eq!(
+        String::from_utf8_lossy(&output),
+        "alice a1\nn-5 b&1\nn1 alice\n"
+    );
+
+    // The format has no reader
+    let error = registry
+        .convert("edgelist", "gml", Box::new(Cursor::new("")), io::sink())
+        .expect_err("edgelist can't be read");
+    assert_eq!(
+        error.to_string(),
+        "Unsupported format \"edgelist\": no reader"
+    );
+    Ok(())
+}
@@ -0,0 +1,164 @@
+use std::fs::File;
+use std::io::{self, BufReader, Cursor};
+
+use graph_converter::error::ConvertError;
+use graph_converter::gexf;
+use graph_converter::gml;
+use graph_converter::graph::{AttrValue, Graph, GraphEvent};
+
+fn read_gexf<R: io::BufRead>(input: R) -> Graph {
+    Graph::read_events(&mut gexf::events(input))
+        .expect("gexf should be read")
+        .expect("gexf has a graph")
+}
+
+fn write_gexf(graph: &Graph) -> Vec<u8> {
+    let mut writer = gexf::EventWriter::new(Vec::new()).expect("temp files should be created");
+    for event in graph.events() {
+        writer.write(&event).expect("event should be written");
+    }
+    writer.finish().expect("gexf should be written")
+}
+
+#[test]
+fn reads_gexf_attributes_with_their_types() -> io::Result<()> {
+    let graph = read_gexf(BufReader::new(File::open("tests/data/gephi.gexf")?));
+    assert_eq!(graph.directed, Some(true));
+    assert_eq!(
+        graph.attrs["creator"],
+        AttrValue::String("Gephi 0.10".to_string())
+    );
+    let node = graph.node("0").expect("node 0 is read");
+    assert_eq!(node.attrs["label"], AttrValue::String("Gephi".to_string()));
+    assert_eq!(node.attrs["indegree"], AttrValue::Int(1));
+    assert_eq!(node.attrs["tags"].to_json(), serde_json::json!(["a", "b"]));
+    assert_eq!(graph.edges[0].attrs["weight"], AttrValue::Float(2.5));
+    // Mutual edges are undirected
+    assert_eq!(graph.edges[1].directed, Some(false));
+    Ok(())
+}
+
+#[test]
+fn reads_viz_and_dynamic_attributes() -> io::Result<()> {
+    let graph = read_gexf(BufReader::new(File::open("tests/data/gephi.gexf")?));
+    let viz = graph.node("0").expect("node 0 is read").attrs["viz"].to_json();
+    assert_eq!(
+        viz["color"],
+        serde_json::json!({"a": 0.6, "b": 66, "g": 173, "r": 239})
+    );
+    assert_eq!(viz["position"]["x"], serde_json::json!(15.78));
+    assert_eq!(viz["size"], serde_json::json!(2.0375757));
+
+    let node = graph.node("1").expect("node 1 is read");
+    assert_eq!(
+        node.attrs["spells"].to_json(),
+        serde_json::json!([{"start": "1.0", "end": "2.0"}, {"start": "3.0"}])
+    );
+    assert_eq!(
+        graph.edges[0].attrs["score"].to_json(),
+        serde_json::json!([
+            {"value": 0.5, "start": "1.0", "end": "2.0"},
+            {"value": 1.5, "start": "2.0"}
+        ])
+    );
+    assert_eq!(
+        graph.edges[0].attrs["viz"].to_json(),
+        serde_json::json!({"thickness": 3})
+    );
+    Ok(())
+}
+
+#[test]
+fn reads_and_writes_gexf_unchanged() -> io::Result<()> {
+    let graph = read_gexf(BufReader::new(File::open("tests/data/gephi.gexf")?));
+    let output = write_gexf(&graph);
+    let text = String::from_utf8_lossy(&output);
+    assert!(text.contains(r#"<graph defaultedgetype="directed" mode="dynamic">"#));
+    assert!(text.contains(r#"<attributes class="edge" mode="dynamic">"#));
+    assert!(text.contains(r#"<viz:size value="2.0375757"/>"#));
+    assert_eq!(read_gexf(Cursor::new(output)), graph);
+    Ok(())
+}
+
+#[test]
+fn converts_gml_through_gexf() -> io::Result<()> {
+    let mut graph = gml::read(BufReader::new(File::open("tests/data/simple.gml")?))
+        .expect("gml should be read");
+    let read_back = read_gexf(Cursor::new(write_gexf(&graph)));
+    // GEXF has no graph attributes other than its meta ones, and edges always have an id
+    graph.attrs.clear();
+    graph.edges[0].id = Some("e0".to_string());
+    assert_eq!(read_back, graph);
+    Ok(())
+}
+
+#[test]
+fn fails_on_undeclared_gexf_attributes() {
+    let input = r#"<gexf><graph><nodes><node id="0"><attvalues><attvalue for="0" value="1"/></attvalues></node></nodes></graph></gexf>"#;
+    let events: Result<Vec<GraphEvent>, _> = gexf::events(Cursor::new(input)).collect();
+    assert!(matches!(events, Err(ConvertError::Gexf { .. })));
+}
+
+#[test]
+fn reads_gexf_types_like_graphml_types() {
+    let input = r#"<gexf><graph><attributes class="node"><attribute id="0" title="count" type="short"/><attribute id="1" title="ratio" type="bigdecimal"/><attribute id="2" title="flags" type="listboolean"/><attribute id="3" title="day" type="date"/></attributes><nodes><node id="0"><attvalues><attvalue for="0" value="7"/><attvalue for="1" value="0.5"/><attvalue for="2" value="true|0"/><attvalue for="3" value="2026-10-17"/></attvalues></node></nodes></graph></gexf>"#;
+    let graph = read_gexf(Cursor::new(input));
+    let node = graph.node("0").expect("node 0 is read");
+    assert_eq!(node.attrs["count"], AttrValue::Int(7));
+    assert_eq!(node.attrs["ratio"], AttrValue::Float(0.5));
+    assert_eq!(
+        node.attrs["flags"],
+        AttrValue::List(vec![AttrValue::Bool(true), AttrValue::Bool(false)])
+    );
+    assert_eq!(
+        node.attrs["day"],
+        AttrValue::String("2026-10-17".to_string())
+    );
+
+    let input = r#"<gexf><graph><attributes class="node"><attribute id="0" title="count" type="integer"/></attributes><nodes><node id="0"><attvalues><attvalue for="0" value="1.5"/></attvalues></node></nodes></graph></gexf>"#;
+    let events: Result<Vec<GraphEvent>, _> = gexf::events(Cursor::new(input)).collect();
+    assert!(matches!(events, Err(ConvertError::Gexf { .. })));
+}
+
+#[test]
+fn keeps_the_direction_and_ids_of_merged_graph_edges() {
+    let input = "graph [ directed 1 edge [ id 0 source 1 target 2 ] edge [ source 1 target 2 ] ] graph [ edge [ source 2 target 1 ] ]";
+    let mut writer = gexf::EventWriter::new(Vec::new()).expect("temp files should be created");
+    for event in gml::events(Cursor::new(input)) {
+        writer
+            .write(&event.expect("gml should be read"))
+            .expect("event should be written");
+    }
+    let output = writer.finish().expect("gexf should be written");
+    let graph = read_gexf(Cursor::new(output));
+    let edges: Vec<_> = graph
+        .edges
+        .iter()
+        .map(|edge| (edge.id.as_deref(), edge.directed))
+        .collect();
+    assert_eq!(
+        edges,
+        [
+            (Some("e0"), None),
+            (Some("e1"), None),
+            (Some("e2"), Some(false))
+        ]
+    );
+
+    // The edges without an id skip the explicit ids of the later edges too
+    let input = "graph [ edge [ source 1 target 2 ] edge [ id 0 source 2 target 1 ] ]";
+    let mut writer = gexf::EventWriter::new(Vec::new()).expect("temp files should be created");
+    for event in gml::events(Cursor::new(input)) {
+        writer
+            .write(&event.expect("gml should be read"))
+            .expect("event should be written");
+    }
+    let output = writer.finish().expect("gexf should be written");
+    let graph = read_gexf(Cursor::new(output));
+    let edges: Vec<_> = graph
+        .edges
+        .iter()
+        .map(|edge| (edge.id.as_deref(), edge.source.as_str()))
+        .collect();
+    assert_eq!(edges, [(Some("e1"), "n1"), (Some("e0"), "n2")]);
+}
@@ -0,0 +1,62 @@
+use std::io::Cursor;
+
+use graph_converter::error::ConvertError;
+use graph_converter::gml_lexer::{Lexer, Token};
+
+fn tokenize(input: &str) -> Vec<Token> {
+    Lexer::new(Cursor::new(input))
+        .collect::<Result<_, _>>()
+        .expect("input should be valid gml")
+}
+
+#[test]
+fn tokenizes_regardless_of_layout() {
+    let expected = vec![
+        Token::Key("node".to_string()),
+        Token::ListStart,
+        Token::Key("id".to_string()),
+        Token::Int("-1".to_string()),
+        Token::Key("x".to_string()),
+        Token::Real("2.5E3".to_string()),
+        Token::ListEnd,
+    ];
+    assert_eq!(tokenize("node [ id -1 x 2.5E3 ]"), expected);
+    assert_eq!(
+        tokenize("node\n[\n  id -1 # comment\n  x 2.5E3\n]\n"),
+        expected
+    );
+}
+
+#[test]
+fn tokenizes_multiline_strings_with_entities() {
+    let tokens = tokenize("label \"a &quot;b&quot;\nc &amp; d\"");
+    assert_eq!(
+        tokens,
+        vec![
+            Token::Key("label".to_string()),
+            Token::Str("a \"b\"\nc & d".to_string()),
+        ]
+    );
+}
+
+#[test]
+fn reports_the_position_of_the_last_token() {
+    let mut lexer = Lexer::new(Cursor::new("graph [\n  label \"a\nb\" é ]"));
+    let positions: Vec<_> = (0..3)
+        .map(|_| {
+            lexer.next();
+            lexer.position()
+        })
+        .collect();
+    assert_eq!(positions, [(1, 1), (1, 7), (2, 3)]);
+    lexer.next();
+    assert_eq!(lexer.position(), (2, 9));
+    assert!(matches!(
+        lexer.next(),
+        Some(Err(ConvertError::Gml {
+            line: 3,
+            column: 4,
+            ..
+        }))
+    ));
+}
@@ -0,0 +1,338 @@
+use std::fs::{self, File};
+use std::io::{self, BufReader, Cursor};
+
+use graph_converter::graph::{AttrValue, Graph, GraphEvent};
+use graph_converter::graphml_to_gml::{self, HyperedgePolicy};
+use graph_converter::{gml, graphml};
+
+fn write_gml(graph: &Graph) -> String {
+    let mut output = Vec::new();
+    gml::write(graph, &mut output).expect("gml should be written");
+    String::from_utf8(output).expect("gml is utf-8")
+}
+
+fn write_graphml(graph: &Graph) -> Vec<u8> {
+    let mut output = Vec::new();
+    graphml::write(graph, &mut output).expect("graphml should be written");
+    output
+}
+
+#[test]
+fn reads_and_writes_gml_unchanged() -> io::Result<()> {
+    for path in ["tests/data/simple.gml", "tests/data/string_ids.gml"] {
+        let graph = gml::read(BufReader::new(File::open(path)?)).expect("gml should be read");
+        assert_eq!(write_gml(&graph), fs::read_to_string(path)?, "{path}");
+    }
+    Ok(())
+}
+
+#[test]
+fn reads_gml_attributes() -> io::Result<()> {
+    let graph = gml::read(BufReader::new(File::open("tests/data/nested.gml")?))
+        .expect("gml should be read");
+    assert_eq!(graph.directed, Some(true));
+    let node = graph.node("n1").expect("node 1 is read");
+    let AttrValue::Map(graphics) = &node.attrs["graphics"] else {
+        panic!("graphics should be a map");
+    };
+    assert_eq!(graphics["x"], AttrValue::Float(10.5));
+    assert_eq!(graphics["y"], AttrValue::Int(-3));
+    assert_eq!(
+        graphics["style"].to_json()["line"]["dash"],
+        serde_json::json!([1, 3])
+    );
+    assert_eq!(
+        (
+            graph.edges[0].source.as_str(),
+            graph.edges[0].target.as_str()
+        ),
+        ("n1", "n2")
+    );
+    Ok(())
+}
+
+#[test]
+fn reads_graphml_attributes_with_their_key_types() -> io::Result<()> {
+    let graph = graphml::read(BufReader::new(File::open("tests/data/booleans.graphml")?))
+        .expect("graphml should be read");
+    assert_eq!(graph.directed, Some(false));
+    assert_eq!(graph.nodes.len(), 2);
+    assert_eq!(graph.nodes[0].attrs["visited"], AttrValue::Bool(true));
+    assert_eq!(graph.nodes[0].attrs["size"], AttrValue::Int(-5000000000));
+    assert_eq!(graph.edges[0].attrs["weight"], AttrValue::Float(-2.0));
+    Ok(())
+}
+
+#[test]
+fn reads_graphml_values_like_the_converter() {
+    let input = r#"<graphml><key id="d0" for="node" attr.name="label" attr.type="string"/><key id="d1" for="node" attr.name="tags"/><key id="d2" for="node" attr.name="size" attr.type="double"/><graph><node id="n0"><data key="d0">[1,2]</data><data key="d1">[1,2]</data><data key="d2"></data></node><node id="n1"><data key="d2">1.50</data><data key="d1">text</data></node><node id="n2"><data key="d1">123</data></node><node id="n3"><data key="d1">true</data></node></graph></graphml>"#;
+    let graph = graphml::read(Cursor::new(input)).expect("graphml should be read");
+    // Serialized lists are read whatever the key type, and empty values are left out
+    assert_eq!(
+        graph.nodes[0].attrs["label"],
+        AttrValue::List(vec![AttrValue::Int(1), AttrValue::Int(2)])
+    );
+    assert_eq!(
+        graph.nodes[0].attrs["tags"],
+        AttrValue::List(vec![AttrValue::Int(1), AttrValue::Int(2)])
+    );
+    assert!(!graph.nodes[0].attrs.contains_key("size"));
+    assert_eq!(
+        graph.nodes[1].attrs["tags"],
+        AttrValue::String("text".to_string())
+    );
+    // Other json values are strings
+    assert_eq!(
+        graph.nodes[2].attrs["tags"],
+        AttrValue::String("123".to_string())
+    );
+    assert_eq!(
+        graph.nodes[3].attrs["tags"],
+        AttrValue::String("true".to_string())
+    );
+
+    let mut converted = Vec::new();
+    graphml_to_gml::convert(input.as_bytes(), &mut converted).expect("conversion should succeed");
+    let mut writer = gml::EventWriter::new(Vec::new());
+    for event in graphml::events(Cursor::new(input)) {
+        writer
+            .write(&event.expect("graphml should be read"))
+            .expect("event should be written");
+    }
+    let written = writer.finish().expect("gml should be written");
+    assert_eq!(
+        String::from_utf8_lossy(&written),
+        String::from_utf8_lossy(&converted)
+    );
+}
+
+#[test]
+fn converts_between_formats_through_the_model() -> io::Result<()> {
+    let mut graph = gml::read(BufReader::new(File::open("tests/data/simple.gml")?))
+        .expect("gml should be read");
+    graph
+        .add_node("extra")
+        .attrs
+        .insert("weight".to_string(), AttrValue::Float(2.5));
+    graph.add_edge("n2", "extra").directed = Some(true);
+
+    let output = write_graphml(&graph);
+    let read_back = graphml::read(Cursor::new(&output)).expect("graphml should be read");
+    assert_eq!(read_back, graph);
+    // The graph is written like its events are
+    let mut writer = graphml::EventWriter::new(Vec::new()).expect("temp file should be created");
+    for event in graph.events() {
+        writer.write(&event).expect("event should be written");
+    }
+    assert_eq!(writer.finish().expect("graphml should be written"), output);
+    let read_back = gml::read(Cursor::new(write_gml(&graph))).expect("gml should be read");
+    assert_eq!(read_back, graph);
+    Ok(())
+}
+
+#[test]
+fn fails_on_undeclared_graphml_keys() {
+    let input = r#"<graphml><graph><node id="n0"><data key="d0">1</data></node></graph></graphml>"#;
+    assert!(graphml::read(Cursor::new(input)).is_err());
+}
+
+#[test]
+fn reads_graphml_key_defaults() -> io::Result<()> {
+    let graph = graphml::read(BufReader::new(File::open("tests/data/defaults.graphml")?))
+        .expect("graphml should be read");
+    let color = |id: &str| graph.node(id).expect("node is read").attrs["color"].clone();
+    assert_eq!(color("n1"), AttrValue::String("red".to_string()));
+    assert_eq!(color("n3"), AttrValue::String("blue".to_string()));
+    assert_eq!(graph.edges[0].attrs["weight"], AttrValue::Float(1.0));
+    Ok(())
+}
+
+#[test]
+fn reads_graphml_ports_descriptions_and_hyperedges() -> io::Result<()> {
+    let graph = graphml::read(BufReader::new(File::open("tests/data/ports.graphml")?))
+        .expect("graphml should be read");
+    assert_eq!(
+        graph.attrs["comment"],
+        AttrValue::String("Ports, descriptions and hyperedges".to_string())
+    );
+    assert_eq!(
+        graph.attrs["desc"],
+        AttrValue::String("The \"main\" graph".to_string())
+    );
+    let n0 = graph.node("n0").expect("node 0 is read");
+    assert_eq!(
+        n0.attrs["desc"],
+        AttrValue::String("First node".to_string())
+    );
+    assert_eq!(
+        n0.attrs["port"].to_json(),
+        serde_json::json!({
+            "name": "north",
+            "side": "top",
+            "port": [{"name": "north-east"}, {"name": "north-west"}]
+        })
+    );
+    assert_eq!(
+        graph.node("n2").expect("node 2 is read").attrs["locator"],
+        AttrValue::String("other.graphml#n2".to_string())
+    );
+    assert_eq!(
+        graph.edges[0].attrs["port"].to_json(),
+        serde_json::json!({"source": "north-east", "target": "south"})
+    );
+
+    // The hyperedge is read as a star node with an edge to each endpoint
+    let center = graph.node("h0").expect("hyperedge node is read");
+    assert_eq!(
+        center.attrs["hyperedge"],
+        AttrValue::String("h0".to_string())
+    );
+    assert_eq!(center.attrs["weight"], AttrValue::Float(0.5));
+    let ends: Vec<_> = graph.edges[1..]
+        .iter()
+        .map(|edge| (edge.source.as_str(), edge.target.as_str()))
+        .collect();
+    assert_eq!(ends, [("n0", "h0"), ("h0", "n1"), ("n2", "h0")]);
+    Ok(())
+}
+
+#[test]
+fn reads_graphml_hyperedges_with_their_policy() -> io::Result<()> {
+    let events = |policy| -> io::Result<Vec<_>> {
+        let input = BufReader::new(File::open("tests/data/ports.graphml")?);
+        Ok(graphml::events_with_hyperedges(input, policy).collect())
+    };
+    let skipped = events(HyperedgePolicy::Skip)?;
+    assert!(skipped.iter().all(|event| event.is_ok()));
+    assert_eq!(
+        skipped
+            .iter()
+            .filter(|event| matches!(event, Ok(GraphEvent::Edge { .. })))
+            .count(),
+        1
+    );
+    let failed = events(HyperedgePolicy::Error)?;
+    assert!(failed.last().is_some_and(|event| event.is_err()));
+    Ok(())
+}
+
+#[test]
+fn streams_events_between_formats() -> io::Result<()> {
+    let input = BufReader::new(File::open("tests/data/simple.gml")?);
+    let mut writer = graphml::EventWriter::new(Vec::new()).expect("temp file should be created");
+    for event in gml::events(input) {
+        writer
+            .write(&event.expect("gml should be read"))
+            .expect("event should be written");
+    }
+    let output = writer.finish().expect("graphml should be written");
+    assert_eq!(output, fs::read("tests/data/simple.graphml")?);
+
+    let input = BufReader::new(File::open("tests/data/graphs.graphml")?);
+    let mut writer = gml::EventWriter::new(Vec::new());
+    for event in graphml::events(input) {
+        writer
+            .write(&event.expect("graphml should be read"))
+            .expect("event should be written");
+    }
+    let output = writer.finish().expect("gml should be written");
+    assert_eq!(
+        String::from_utf8_lossy(&output),
+        fs::read_to_string("tests/data/graphs.gml")?
+    );
+    Ok(())
+}
+
+#[test]
+fn returns_events_in_file_order() {
+    let input = "graph [ directed 1 label \"g\" node [ id 1 ] edge [ source 1 target 1 ] ]";
+    let events: Vec<GraphEvent> = gml::events(Cursor::new(input))
+        .collect::<Result<_, _>>()
+        .expect("gml should be read");
+    assert_eq!(
+        events,
+        vec![
+            GraphEvent::GraphStart {
+                directed: Some(true),
+                attrs: [("label".to_string(), AttrValue::String("g".to_string()))].into(),
+            },
+            GraphEvent::Node {
+                id: "n1".to_string(),
+                attrs: Default::default(),
+            },
+            GraphEvent::Edge {
+                id: None,
+                source: "n1".to_string(),
+                target: "n1".to_string(),
+                directed: None,
+                attrs: Default::default(),
+            },
+            GraphEvent::GraphEnd {
+                attrs: Default::default(),
+            },
+        ]
+    );
+}
+
+#[test]
+fn keeps_default_ids_of_nodes_named_after_their_edges() {
+    let input =
+        "graph [ edge [ source 1 target 2 ] node [ id 1 graphmlId \"alice\" ] node [ id 2 ] ]";
+    let graph = gml::read(Cursor::new(input)).expect("gml should be read");
+    assert_eq!(graph.edges[0].source, "n1");
+    let node = graph.node("n1").expect("n1 keeps its default id");
+    assert_eq!(
+        node.attrs["graphmlId"],
+        AttrValue::String("alice".to_string())
+    );
+}
+
+#[test]
+fn keeps_graph_attributes_after_the_first_node() -> io::Result<()> {
+    let input = "graph [ directed 1 node [ id 1 ] label \"late\" directed 1 ]";
+    let events: Vec<GraphEvent> = gml::events(Cursor::new(input))
+        .collect::<Result<_, _>>()
+        .expect("gml should be read");
+    assert_eq!(
+        events.last(),
+        Some(&GraphEvent::GraphEnd {
+            attrs: [("label".to_string(), AttrValue::String("late".to_string()))].into(),
+        })
+    );
+    let mut writer = graphml::EventWriter::new(Vec::new()).expect("temp file should be created");
+    for event in &events {
+        writer.write(event).expect("event should be written");
+    }
+    let output = writer.finish().expect("graphml should be written");
+    assert!(String::from_utf8_lossy(&output).contains(">late</data>\n</graph>"));
+
+    let graph = gml::read(Cursor::new(input)).expect("gml should be read");
+    assert_eq!(graph.attrs["label"], AttrValue::String("late".to_string()));
+    let read_back =
+        graphml::read(Cursor::new(write_graphml(&graph))).expect("graphml should be read");
+    assert_eq!(read_back, graph);
+
+    // The edges before a change of direction were read with the previous one
+    let input = "graph [ edge [ source 1 target 1 ] directed 1 ]";
+    assert!(gml::read(Cursor::new(input)).is_err());
+    Ok(())
+}
+
+#[test]
+fn reads_gml_like_the_converter() {
+    // Keys before the graph are graph attributes, and repeated json list strings are lists of lists
+    let input = "Creator \"me\" graph [ node [ id 1 layers \"[1,2]\" layers \"[3]\" ] ]";
+    let graph = gml::read(Cursor::new(input)).expect("gml should be read");
+    assert_eq!(graph.attrs["Creator"], AttrValue::String("me".to_string()));
+    assert_eq!(
+        graph.nodes[0].attrs["layers"].to_json(),
+        serde_json::json!([[1, 2], [3]])
+    );
+
+    let mut output = Vec::new();
+    graph_converter::gml_to_graphml::convert(Cursor::new(input), &mut output)
+        .expect("gml should be converted");
+    let output = String::from_utf8(output).expect("graphml is utf-8");
+    assert!(output.contains("<data key=\"d0\">me</data>"));
+    assert!(output.contains("<data key=\"d1\">[[1,2],[3]]</data>"));
+}
@@ -1,8 +1,10 @@
 use std::fs;
-use std::io;
+use std::io::{self, Cursor};
 use std::path::Path;
 
-use graph_converter::{gml_to_graphml::export_to_graphml, graphml_to_gml::export_to_gml};
+use graph_converter::gml_to_graphml::{self, export_to_graphml};
+use graph_converter::graphml_to_gml::{self, export_to_gml};
+use graph_converter::multi_graph::MultipleGraphs;
 use tempfile::NamedTempFile;
 
 fn assert_output_matches_fixture(
@@ -12,7 +14,8 @@ fn assert_output_matches_fixture(
 ) -> io::Result<()> {
     let actual = fs::read_to_string(output_path)?;
     let expected = fs::read_to_string(expected_path)?;
-    assert_eq!(&actual, &expected,
+    assert_eq!(
+        &actual, &expected,
         "{label} output should match the golden fixture"
     );
     Ok(())
@@ -22,18 +25,609 @@ fn assert_output_matches_fixture(
 fn converts_simple_gml_to_graphml() -> io::Result<()> {
     let input_path = Path::new("tests/data/simple.gml");
     let output_file = NamedTempFile::new()?;
-    export_to_graphml(input_path, output_file.path());
-    
+    export_to_graphml(input_path, output_file.path()).expect("conversion should succeed");
+
     let expected_path = Path::new("tests/data/simple.graphml");
     assert_output_matches_fixture(output_file.path(), expected_path, "graphml")
 }
 
+#[test]
+fn converts_compact_gml_to_graphml() -> io::Result<()> {
+    let input_path = Path::new("tests/data/simple_compact.gml");
+    let output_file = NamedTempFile::new()?;
+    export_to_graphml(input_path, output_file.path()).expect("conversion should succeed");
+
+    let expected_path = Path::new("tests/data/simple.graphml");
+    assert_output_matches_fixture(output_file.path(), expected_path, "graphml")
+}
+
+#[test]
+fn converts_nested_gml_lists_to_json_data() -> io::Result<()> {
+    let input_path = Path::new("tests/data/nested.gml");
+    let output_file = NamedTempFile::new()?;
+    export_to_graphml(input_path, output_file.path()).expect("conversion should succeed");
+
+    let expected_path = Path::new("tests/data/nested.graphml");
+    assert_output_matches_fixture(output_file.path(), expected_path, "graphml")
+}
+
 #[test]
 fn converts_simple_graphml_to_gml() -> io::Result<()> {
     let input_path = Path::new("tests/data/simple.graphml");
     let output_file = NamedTempFile::new()?;
-    export_to_gml(input_path, output_file.path());
-    
+    export_to_gml(input_path, output_file.path()).expect("conversion should succeed");
+
     let expected_path = Path::new("tests/data/simple.gml");
     assert_output_matches_fixture(output_file.path(), expected_path, "gml")
 }
+
+#[test]
+fn converts_nested_json_data_to_gml_lists() -> io::Result<()> {
+    let input_path = Path::new("tests/data/json_values.graphml");
+    let output_file = NamedTempFile::new()?;
+    export_to_gml(input_path, output_file.path()).expect("conversion should succeed");
+
+    let expected_path = Path::new("tests/data/json_values.gml");
+    assert_output_matches_fixture(output_file.path(), expected_path, "gml")
+}
+
+#[test]
+fn nested_lists_survive_a_round_trip() -> io::Result<()> {
+    let input_path = Path::new("tests/data/nested.graphml");
+    let gml_file = NamedTempFile::new()?;
+    let graphml_file = NamedTempFile::new()?;
+    export_to_gml(input_path, gml_file.path()).expect("conversion should succeed");
+    export_to_graphml(gml_file.path(), graphml_file.path()).expect("conversion should succeed");
+
+    assert_output_matches_fixture(graphml_file.path(), input_path, "graphml")
+}
+
+#[test]
+fn maps_string_node_ids_to_gml_integers() -> io::Result<()> {
+    let input_path = Path::new("tests/data/string_ids.graphml");
+    let output_file = NamedTempFile::new()?;
+    export_to_gml(input_path, output_file.path()).expect("conversion should succeed");
+
+    let expected_path = Path::new("tests/data/string_ids.gml");
+    assert_output_matches_fixture(output_file.path(), expected_path, "gml")
+}
+
+#[test]
+fn restores_string_node_ids_from_gml() -> io::Result<()> {
+    let input_path = Path::new("tests/data/string_ids.gml");
+    let output_file = NamedTempFile::new()?;
+    export_to_graphml(input_path, output_file.path()).expect("conversion should succeed");
+
+    let expected_path = Path::new("tests/data/string_ids.graphml");
+    assert_output_matches_fixture(output_file.path(), expected_path, "graphml")
+}
+
+#[test]
+fn converts_between_in_memory_streams() -> io::Result<()> {
+    let gml = fs::read("tests/data/simple.gml")?;
+    let mut graphml = Vec::new();
+    gml_to_graphml::convert(gml.as_slice(), &mut graphml).expect("conversion should succeed");
+    assert_eq!(graphml, fs::read("tests/data/simple.graphml")?);
+
+    let mut converted_gml = Vec::new();
+    graphml_to_gml::convert(graphml.as_slice(), &mut converted_gml)
+        .expect("conversion should succeed");
+    assert_eq!(converted_gml, gml);
+    Ok(())
+}
+
+#[test]
+fn converts_seekable_gml_input_in_two_passes() -> io::Result<()> {
+    let gml = fs::read("tests/data/nested.gml")?;
+    let mut graphml = Vec::new();
+    gml_to_graphml::convert_seekable(Cursor::new(gml), &mut graphml)
+        .expect("conversion should succeed");
+    assert_eq!(graphml, fs::read("tests/data/nested.graphml")?);
+    Ok(())
+}
+
+#[test]
+fn widens_key_types_over_every_value() -> io::Result<()> {
+    // x is an int then a float, y is an int then a string
+    let input_path = Path::new("tests/data/mixed_types.gml");
+    let output_file = NamedTempFile::new()?;
+    export_to_graphml(input_path, output_file.path()).expect("conversion should succeed");
+
+    let expected_path = Path::new("tests/data/mixed_types.graphml");
+    assert_output_matches_fixture(output_file.path(), expected_path, "graphml")?;
+
+    // The streaming conversion writes the same key types
+    let input = fs::read(input_path)?;
+    let mut output = Vec::new();
+    gml_to_graphml::convert(Cursor::new(input), &mut output).expect("conversion should succeed");
+    assert_eq!(
+        String::from_utf8_lossy(&output),
+        fs::read_to_string(expected_path)?
+    );
+    Ok(())
+}
+
+#[test]
+fn widens_integers_and_reals_to_double() {
+    // 123456789 has no exact single precision value
+    let input = "graph [ node [ id 1 x 123456789 ] node [ id 2 x 1.5 ] ]";
+    let mut output = Vec::new();
+    gml_to_graphml::convert(input.as_bytes(), &mut output).expect("conversion should succeed");
+    assert!(
+        String::from_utf8_lossy(&output)
+            .contains(r#"<key id="d0" for="node" attr.name="x" attr.type="double"/>"#)
+    );
+}
+
+#[test]
+fn widens_longs_and_reals_to_string() {
+    // 9007199254740993 (2^53 + 1) has no exact double precision value
+    let input = "graph [ node [ id 1 x 9007199254740993 ] node [ id 2 x 1.5 ] ]";
+    let mut output = Vec::new();
+    gml_to_graphml::convert(input.as_bytes(), &mut output).expect("conversion should succeed");
+    let output = String::from_utf8_lossy(&output);
+    assert!(
+        output.contains(r#"<key id="d0" for="node" attr.name="x" attr.type="string"/>"#),
+        "{output}"
+    );
+    assert!(output.contains(">9007199254740993</data>"), "{output}");
+}
+
+#[test]
+fn infers_long_double_and_negative_types() -> io::Result<()> {
+    let input_path = Path::new("tests/data/types.gml");
+    let output_file = NamedTempFile::new()?;
+    export_to_graphml(input_path, output_file.path()).expect("conversion should succeed");
+
+    let expected_path = Path::new("tests/data/types.graphml");
+    assert_output_matches_fixture(output_file.path(), expected_path, "graphml")
+}
+
+#[test]
+fn converts_boolean_and_long_graphml_types_to_gml() -> io::Result<()> {
+    let input_path = Path::new("tests/data/booleans.graphml");
+    let output_file = NamedTempFile::new()?;
+    export_to_gml(input_path, output_file.path()).expect("conversion should succeed");
+
+    let expected_path = Path::new("tests/data/booleans.gml");
+    assert_output_matches_fixture(output_file.path(), expected_path, "gml")
+}
+
+#[test]
+fn writes_most_common_values_as_key_defaults() -> io::Result<()> {
+    let input = fs::read("tests/data/defaults.gml")?;
+    let expected = fs::read_to_string("tests/data/defaults.graphml")?;
+    let options = gml_to_graphml::Options {
+        write_defaults: true,
+        ..Default::default()
+    };
+
+    let mut output = Vec::new();
+    gml_to_graphml::convert_seekable_with_options(Cursor::new(&input), &mut output, &options)
+        .expect("conversion should succeed");
+    assert_eq!(String::from_utf8_lossy(&output), expected);
+
+    // The streaming conversion buffers the input to omit the default values
+    let mut output = Vec::new();
+    gml_to_graphml::convert_with_options(Cursor::new(&input), &mut output, &options)
+        .expect("conversion should succeed");
+    assert_eq!(String::from_utf8_lossy(&output), expected);
+    Ok(())
+}
+
+#[test]
+fn fills_key_defaults_in_nodes_and_edges() -> io::Result<()> {
+    let input = fs::read("tests/data/defaults.graphml")?;
+    let options = graphml_to_gml::Options {
+        fill_defaults: true,
+        ..Default::default()
+    };
+    let mut output = Vec::new();
+    graphml_to_gml::convert_with_options(Cursor::new(&input), &mut output, &options)
+        .expect("conversion should succeed");
+    assert_eq!(
+        String::from_utf8_lossy(&output),
+        fs::read_to_string("tests/data/defaults_filled.gml")?
+    );
+
+    // Defaults are only read by default, nodes without a value keep no value
+    let mut output = Vec::new();
+    graphml_to_gml::convert(Cursor::new(&input), &mut output).expect("conversion should succeed");
+    let output = String::from_utf8_lossy(&output);
+    assert_eq!(output.matches("color").count(), 1);
+    assert!(!output.contains("weight"));
+    Ok(())
+}
+
+#[test]
+fn converts_ports_descriptions_and_hyperedges_to_gml() -> io::Result<()> {
+    let input_path = Path::new("tests/data/ports.graphml");
+    let output_file = NamedTempFile::new()?;
+    let stats = export_to_gml(input_path, output_file.path()).expect("conversion should succeed");
+    assert_eq!((stats.nodes, stats.edges), (4, 4));
+    assert_eq!(stats.warnings.len(), 1);
+
+    let expected_path = Path::new("tests/data/ports.gml");
+    assert_output_matches_fixture(output_file.path(), expected_path, "gml")
+}
+
+#[test]
+fn skips_hyperedges_with_a_warning() -> io::Result<()> {
+    let input = fs::read("tests/data/ports.graphml")?;
+    let options = graphml_to_gml::Options {
+        hyperedges: graphml_to_gml::HyperedgePolicy::Skip,
+        ..Default::default()
+    };
+    let mut output = Vec::new();
+    let stats = graphml_to_gml::convert_with_options(Cursor::new(&input), &mut output, &options)
+        .expect("conversion should succeed");
+    assert_eq!((stats.nodes, stats.edges), (3, 1));
+    assert_eq!(stats.warnings, vec!["Skipped 1 hyperedges".to_string()]);
+    assert!(!String::from_utf8_lossy(&output).contains("hyperedge \""));
+    Ok(())
+}
+
+#[test]
+fn flattens_nested_graphs_to_gml_groups() -> io::Result<()> {
+    let input_path = Path::new("tests/data/groups.graphml");
+    let output_file = NamedTempFile::new()?;
+    export_to_gml(input_path, output_file.path()).expect("conversion should succeed");
+
+    let expected_path = Path::new("tests/data/groups.gml");
+    assert_output_matches_fixture(output_file.path(), expected_path, "gml")
+}
+
+#[test]
+fn rebuilds_nested_graphs_from_gml_groups() -> io::Result<()> {
+    let input_path = Path::new("tests/data/groups.gml");
+    let output_file = NamedTempFile::new()?;
+    let stats =
+        export_to_graphml(input_path, output_file.path()).expect("conversion should succeed");
+    assert_eq!((stats.nodes, stats.edges), (6, 3));
+
+    let expected_path = Path::new("tests/data/groups_nested.graphml");
+    assert_output_matches_fixture(output_file.path(), expected_path, "graphml")
+}
+
+#[test]
+fn keeps_unknown_group_ids_as_data() -> io::Result<()> {
+    // Node 2 is in a group that doesn't exist, nodes 3 and 4 are in each other's group
+    let input =
+        "graph [ node [ id 1 ] node [ id 2 gid 9 ] node [ id 3 gid 4 ] node [ id 4 gid 3 ] ]";
+    let mut output = Vec::new();
+    let stats = gml_to_graphml::convert(Cursor::new(input), &mut output)
+        .expect("conversion should succeed");
+    assert_eq!(stats.nodes, 4);
+
+    let output = String::from_utf8_lossy(&output);
+    assert!(output.contains(r#"<key id="d0" for="node" attr.name="gid" attr.type="int"/>"#));
+    assert!(output.contains(r#"<data key="d0">9</data>"#));
+    assert_eq!(output.matches("<node id=").count(), 4);
+    assert_eq!(output.matches("<graph id=").count(), 1);
+    Ok(())
+}
+
+#[test]
+fn keeps_gml_graphs_separate() -> io::Result<()> {
+    let input_path = Path::new("tests/data/graphs.gml");
+    let output_file = NamedTempFile::new()?;
+    export_to_graphml(input_path, output_file.path()).expect("conversion should succeed");
+
+    let expected_path = Path::new("tests/data/graphs.graphml");
+    assert_output_matches_fixture(output_file.path(), expected_path, "graphml")
+}
+
+#[test]
+fn keeps_graphml_graphs_separate() -> io::Result<()> {
+    let input_path = Path::new("tests/data/graphs.graphml");
+    let output_file = NamedTempFile::new()?;
+    let stats = export_to_gml(input_path, output_file.path()).expect("conversion should succeed");
+    assert_eq!((stats.nodes, stats.edges), (3, 2));
+
+    let expected_path = Path::new("tests/data/graphs.gml");
+    assert_output_matches_fixture(output_file.path(), expected_path, "gml")
+}
+
+#[test]
+fn merges_graphs_with_namespaced_ids() -> io::Result<()> {
+    let input = fs::read_to_string("tests/data/graphs.gml")?;
+    let options = gml_to_graphml::Options {
+        graphs: MultipleGraphs::Merge,
+        ..Default::default()
+    };
+    let mut output = Vec::new();
+    gml_to_graphml::convert_with_options(Cursor::new(input), &mut output, &options)
+        .expect("conversion should succeed");
+
+    let output = String::from_utf8_lossy(&output);
+    assert_eq!(
+        output.matches("<graph").count() - output.matches("<graphml").count(),
+        1
+    );
+    assert!(output.contains(r#"<node id="n1">"#));
+    assert!(output.contains(r#"<edge source="g1:n1" target="g1:n1" directed="true">"#));
+
+    // The graph ids are used as prefixes when the graphs have one
+    let input = r#"<graphml><graph id="a"><node id="x"/></graph><graph id="b"><node id="x"/></graph></graphml>"#;
+    let options = graphml_to_gml::Options {
+        graphs: MultipleGraphs::Merge,
+        ..Default::default()
+    };
+    let mut output = Vec::new();
+    let stats = graphml_to_gml::convert_with_options(input.as_bytes(), &mut output, &options)
+        .expect("conversion should succeed");
+    assert_eq!(stats.nodes, 2);
+
+    let output = String::from_utf8_lossy(&output);
+    assert_eq!(output.matches("graph [").count(), 1);
+    assert!(output.contains(r#"graphmlId "x""#));
+    assert!(output.contains(r#"graphmlId "b:x""#));
+    Ok(())
+}
+
+#[test]
+fn splits_graphml_graphs_to_gml_files() -> io::Result<()> {
+    let output_dir = tempfile::tempdir()?;
+    let output_path = output_dir.path().join("graphs.gml");
+    let stats = graphml_to_gml::export_to_gml_files(
+        Path::new("tests/data/graphs.graphml"),
+        &output_path,
+        &Default::default(),
+    )
+    .expect("conversion should succeed");
+    assert_eq!((stats.nodes, stats.edges), (3, 2));
+
+    let first = fs::read_to_string(&output_path)?;
+    let second = fs::read_to_string(output_dir.path().join("graphs.1.gml"))?;
+    assert_eq!(
+        format!("{}\n{}", first, second),
+        fs::read_to_string("tests/data/graphs.gml")?
+    );
+    assert!(!output_dir.path().join("graphs.2.gml").exists());
+    Ok(())
+}
+
+#[test]
+fn keeps_multigraph_edge_keys_as_graphml_edge_ids() -> io::Result<()> {
+    let input_path = Path::new("tests/data/multigraph.gml");
+    let output_file = NamedTempFile::new()?;
+    export_to_graphml(input_path, output_file.path()).expect("conversion should succeed");
+
+    let expected_path = Path::new("tests/data/multigraph.graphml");
+    assert_output_matches_fixture(output_file.path(), expected_path, "graphml")
+}
+
+#[test]
+fn converts_graphml_edge_ids_to_gml() -> io::Result<()> {
+    let input_path = Path::new("tests/data/multigraph.graphml");
+    let output_file = NamedTempFile::new()?;
+    export_to_gml(input_path, output_file.path()).expect("conversion should succeed");
+
+    let expected_path = Path::new("tests/data/multigraph.gml");
+    assert_output_matches_fixture(output_file.path(), expected_path, "gml")?;
+
+    // Without multigraph, edge ids are kept as gml edge ids
+    let input = r#"<graphml><graph><node id="n1"/><edge id="a" source="n1" target="n1"/></graph></graphml>"#;
+    let mut output = Vec::new();
+    graphml_to_gml::convert(input.as_bytes(), &mut output).expect("conversion should succeed");
+    assert!(String::from_utf8_lossy(&output).contains("    id \"a\"\n    source 1\n"));
+    Ok(())
+}
+
+#[test]
+fn writes_edge_directions_that_differ_from_the_graph() -> io::Result<()> {
+    let input_path = Path::new("tests/data/mixed.gml");
+    let output_file = NamedTempFile::new()?;
+    export_to_graphml(input_path, output_file.path()).expect("conversion should succeed");
+
+    let expected_path = Path::new("tests/data/mixed.graphml");
+    assert_output_matches_fixture(output_file.path(), expected_path, "graphml")
+}
+
+#[test]
+fn reads_edge_directions_of_mixed_graphml_graphs() -> io::Result<()> {
+    let input_path = Path::new("tests/data/mixed.graphml");
+    let output_file = NamedTempFile::new()?;
+    export_to_gml(input_path, output_file.path()).expect("conversion should succeed");
+
+    let expected_path = Path::new("tests/data/mixed.gml");
+    assert_output_matches_fixture(output_file.path(), expected_path, "gml")?;
+
+    // Edges of a merged graph keep its edge default, redundant directions are left out
+    let input = r#"<graphml><graph edgedefault="undirected"><node id="a"/><edge source="a" target="a" directed="false"/></graph><graph edgedefault="directed"><node id="a"/><edge source="a" target="a"/></graph></graphml>"#;
+    let options = graphml_to_gml::Options {
+        graphs: MultipleGraphs::Merge,
+        ..Default::default()
+    };
+    let mut output = Vec::new();
+    graphml_to_gml::convert_with_options(input.as_bytes(), &mut output, &options)
+        .expect("conversion should succeed");
+    assert_eq!(
+        String::from_utf8_lossy(&output)
+            .matches("directed 1")
+            .count(),
+        1
+    );
+    Ok(())
+}
+
+#[test]
+fn escapes_quotes_and_ampersands_of_graphml_strings() -> io::Result<()> {
+    let input = r#"<graphml><key id="d0" for="node" attr.name="label" attr.type="string"/><graph edgedefault="directed"><node id="a"><data key="d0">say "hi" &amp; bye</data></node></graph></graphml>"#;
+    let mut gml = Vec::new();
+    graphml_to_gml::convert(input.as_bytes(), &mut gml).expect("conversion should succeed");
+    assert!(
+        String::from_utf8_lossy(&gml).contains(r#"label "say &quot;hi&quot; &amp; bye""#),
+        "{}",
+        String::from_utf8_lossy(&gml)
+    );
+
+    let mut graphml = Vec::new();
+    gml_to_graphml::convert(gml.as_slice(), &mut graphml).expect("conversion should succeed");
+    assert!(
+        String::from_utf8_lossy(&graphml).contains(">say &quot;hi&quot; &amp; bye</data>"),
+        "{}",
+        String::from_utf8_lossy(&graphml)
+    );
+    Ok(())
+}
+
+#[test]
+fn names_nodes_declared_after_their_edges() -> io::Result<()> {
+    let gml = r#"graph [
+  directed 1
+  edge [
+    source 1
+    target 2
+  ]
+  node [
+    id 1
+    graphmlId "alice"
+  ]
+  node [
+    id 2
+    graphmlId "bob"
+  ]
+]"#;
+    // Seekable inputs get the names from a first pass
+    let mut graphml = Vec::new();
+    let stats = gml_to_graphml::convert_seekable(Cursor::new(gml), &mut graphml)
+        .expect("conversion should succeed");
+    let graphml = String::from_utf8_lossy(&graphml);
+    assert!(
+        graphml.contains(r#"<edge source="alice" target="bob">"#),
+        "{graphml}"
+    );
+    assert!(graphml.contains(r#"<node id="alice">"#), "{graphml}");
+    assert!(stats.warnings.is_empty());
+
+    // A single pass keeps the default ids of the nodes and their names as data
+    let mut graphml = Vec::new();
+    let stats =
+        gml_to_graphml::convert(gml.as_bytes(), &mut graphml).expect("conversion should succeed");
+    let graphml = String::from_utf8_lossy(&graphml);
+    assert!(
+        graphml.contains(r#"<edge source="n1" target="n2">"#),
+        "{graphml}"
+    );
+    assert!(graphml.contains(r#"<node id="n1">"#), "{graphml}");
+    assert!(graphml.contains(">alice</data>"), "{graphml}");
+    assert_eq!(stats.warnings.len(), 2, "{:?}", stats.warnings);
+    Ok(())
+}
+
+#[test]
+fn keeps_node_names_as_data() {
+    // igraph writes the node names as data of nodes with default ids
+    let graphml = concat!(
+        "<graphml>\n",
+        "  <key id=\"v_name\" for=\"node\" attr.name=\"name\" attr.type=\"string\"/>\n",
+        "  <graph edgedefault=\"undirected\">\n",
+        "    <node id=\"n0\"><data key=\"v_name\">Alice</data></node>\n",
+        "    <node id=\"n1\"><data key=\"v_name\">Bob</data></node>\n",
+        "    <edge source=\"n0\" target=\"n1\"/>\n",
+        "  </graph>\n",
+        "</graphml>\n"
+    );
+    let mut gml = Vec::new();
+    graphml_to_gml::convert(graphml.as_bytes(), &mut gml).expect("conversion should succeed");
+    let mut converted = Vec::new();
+    gml_to_graphml::convert(gml.as_slice(), &mut converted).expect("conversion should succeed");
+
+    let converted = String::from_utf8_lossy(&converted);
+    assert!(converted.contains(r#"<node id="n0">"#), "{converted}");
+    assert!(converted.contains(r#"<node id="n1">"#), "{converted}");
+    assert!(converted.contains(r#"attr.name="name""#), "{converted}");
+    assert!(converted.contains(">Alice</data>"), "{converted}");
+    assert!(
+        converted.contains(r#"<edge source="n0" target="n1">"#),
+        "{converted}"
+    );
+}
+
+#[test]
+fn quotes_strings_that_look_like_lists() {
+    let graphml = concat!(
+        "<graphml>\n",
+        "  <key id=\"d0\" for=\"node\" attr.name=\"label\" attr.type=\"string\"/>\n",
+        "  <graph edgedefault=\"undirected\">\n",
+        "    <node id=\"n0\"><data key=\"d0\">[see note]</data></node>\n",
+        "    <node id=\"n1\"><data key=\"d0\">{&quot;a&quot;</data></node>\n",
+        "  </graph>\n",
+        "</graphml>\n"
+    );
+    let mut gml = Vec::new();
+    graphml_to_gml::convert(graphml.as_bytes(), &mut gml).expect("conversion should succeed");
+    let gml = String::from_utf8_lossy(&gml);
+    assert!(gml.contains(r#"label "[see note]""#), "{gml}");
+    assert!(gml.contains(r#"label "{&quot;a&quot;""#), "{gml}");
+
+    let mut converted = Vec::new();
+    gml_to_graphml::convert(gml.as_bytes(), &mut converted).expect("conversion should succeed");
+    let converted = String::from_utf8_lossy(&converted);
+    assert!(converted.contains(">[see note]</data>"), "{converted}");
+}
+
+#[test]
+fn serializes_repeated_strings_as_json_lists() {
+    let gml = r#"graph [ a "x&quot;y" a "c:\dir" node [ id 1 ] ]"#;
+    let mut graphml = Vec::new();
+    gml_to_graphml::convert(gml.as_bytes(), &mut graphml).expect("conversion should succeed");
+    let graphml = String::from_utf8_lossy(&graphml);
+    assert!(
+        graphml.contains(r#">[&quot;x\&quot;y&quot;,&quot;c:\\dir&quot;]</data>"#),
+        "{graphml}"
+    );
+
+    let mut converted = Vec::new();
+    graphml_to_gml::convert(graphml.as_bytes(), &mut converted).expect("conversion should succeed");
+    let converted = String::from_utf8_lossy(&converted);
+    assert!(
+        converted.contains("  a \"x&quot;y\"\n  a \"c:\\dir\"\n"),
+        "{converted}"
+    );
+}
+
+#[test]
+fn writes_graph_attributes_after_the_first_node() {
+    let gml = r#"graph [ graphics [ x 1 ] node [ id 1 ] label "late" meta [ a 1 ] ]"#;
+    let mut graphml = Vec::new();
+    gml_to_graphml::convert(gml.as_bytes(), &mut graphml).expect("conversion should succeed");
+    let graphml = String::from_utf8_lossy(&graphml);
+    assert!(
+        graphml.contains(concat!(
+            "  </node>\n",
+            "  <data key=\"d1\">late</data>\n",
+            "  <data key=\"d2\">{&quot;a&quot;:1}</data>\n",
+            "</graph>"
+        )),
+        "{graphml}"
+    );
+}
+
+#[test]
+fn keeps_graph_data_after_the_first_node() {
+    let gml = "graph [ node [ id 1 ] after 3 ]";
+    let mut graphml = Vec::new();
+    gml_to_graphml::convert(gml.as_bytes(), &mut graphml).expect("conversion should succeed");
+    let mut gml = Vec::new();
+    graphml_to_gml::convert(graphml.as_slice(), &mut gml).expect("conversion should succeed");
+    let gml = String::from_utf8_lossy(&gml);
+    assert!(gml.ends_with("  ]\n  after 3\n]"), "{gml}");
+}
+
+#[test]
+fn keeps_deeply_nested_lists() {
+    let depth = 200;
+    let gml = format!(
+        "graph [ node [ id 1 {}{} ] ]",
+        "a [ ".repeat(depth),
+        "] ".repeat(depth)
+    );
+    let mut graphml = Vec::new();
+    gml_to_graphml::convert(gml.as_bytes(), &mut graphml).expect("conversion should succeed");
+    let mut gml = Vec::new();
+    graphml_to_gml::convert(graphml.as_slice(), &mut gml).expect("conversion should succeed");
+    let gml = String::from_utf8_lossy(&gml);
+    assert_eq!(gml.matches("a [").count(), depth, "{gml}");
+}
@@ -0,0 +1,60 @@
+use std::fs::File;
+use std::io::{self, BufReader};
+
+use graph_converter::{gml_to_graphml, graphml_to_gml};
+use serde_json::json;
+
+#[test]
+fn summarizes_gml_files() -> io::Result<()> {
+    let input = BufReader::new(File::open("tests/data/multigraph.gml")?);
+    let summary = gml_to_graphml::summarize(input).expect("summary should succeed");
+    assert_eq!(
+        summary.to_json(),
+        json!({
+            "nodes": 2,
+            "edges": 3,
+            "directed": true,
+            "self_loops": 0,
+            "parallel_edges": 1,
+            "keys": [
+                {"for": "edge", "name": "key", "type": "string", "count": 1, "share": 1.0 / 3.0},
+                {"for": "edge", "name": "weight", "type": "int", "count": 2, "share": 2.0 / 3.0, "min": 1, "max": 2},
+            ]
+        })
+    );
+    Ok(())
+}
+
+#[test]
+fn summarizes_graphml_files() {
+    let input = r#"<graphml>
+        <key id="d0" for="node" attr.name="size" attr.type="double"/>
+        <key id="d1" for="all" attr.name="label" attr.type="string"/>
+        <graph edgedefault="undirected">
+            <node id="a"><data key="d0">2.5</data><data key="d1">A</data></node>
+            <node id="b"><data key="d0">-1</data></node>
+            <edge source="a" target="b"/>
+            <edge source="b" target="a"/>
+            <edge source="a" target="a" directed="true"/>
+        </graph>
+    </graphml>"#;
+    let summary = graphml_to_gml::summarize(input.as_bytes()).expect("summary should succeed");
+    let summary = summary.to_json();
+    assert_eq!(
+        (&summary["nodes"], &summary["edges"], &summary["directed"]),
+        (&json!(2), &json!(3), &json!(false))
+    );
+    // The undirected edges between a and b are parallel
+    assert_eq!(
+        (&summary["self_loops"], &summary["parallel_edges"]),
+        (&json!(1), &json!(1))
+    );
+    assert_eq!(
+        summary["keys"],
+        json!([
+            {"for": "edge", "name": "label", "type": "string", "count": 0, "share": 0.0},
+            {"for": "node", "name": "label", "type": "string", "count": 1, "share": 0.5},
+            {"for": "node", "name": "size", "type": "double", "count": 2, "share": 1.0, "min": -1.0, "max": 2.5},
+        ])
+    );
+}